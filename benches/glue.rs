@@ -0,0 +1,87 @@
+//! Overhead of `manyhow`'s glue around trivial macro implementations.
+use criterion::{criterion_group, criterion_main, Criterion};
+use manyhow::bench::Benches;
+use manyhow::{attribute, bail, derive, emit, function, Dummy, Emitter, Result};
+use proc_macro2::{TokenStream, TokenTree};
+
+const ITEM: &str = "#[attr] pub struct Struct<T: Clone> { field: T, other: Vec<u8> }";
+
+fn bench_function(c: &mut Criterion) {
+    Benches::new("function")
+        .case("passing", ITEM)
+        .error_case("error", "")
+        .run(c, |input| {
+            function!(input, |input: TokenStream| -> Result {
+                if input.is_empty() {
+                    bail!("expected input");
+                }
+                Ok(input)
+            })
+        });
+    Benches::new("function_emitter")
+        .case("passing", "")
+        .error_case("errors", ITEM)
+        .run(c, |input| {
+            function!(input, |input: TokenStream, emitter: &mut Emitter| {
+                for token in input {
+                    emit!(emitter, token, "unexpected `{token}`");
+                }
+                TokenStream::new()
+            })
+        });
+}
+
+fn bench_attribute(c: &mut Criterion) {
+    Benches::new("attribute")
+        .case("passing", ITEM)
+        .error_case("error", "")
+        .run(c, |item| {
+            attribute!(TokenStream::new(), item, |_: TokenStream,
+                                                  item: TokenStream|
+             -> Result {
+                if item.is_empty() {
+                    bail!("expected item");
+                }
+                Ok(item)
+            })
+        });
+}
+
+fn bench_derive(c: &mut Criterion) {
+    Benches::new("derive")
+        .case("passing", ITEM)
+        .error_case("error", "")
+        .run(c, |item| {
+            derive!(item, |item: syn2::DeriveInput| -> TokenStream {
+                let _ = item;
+                TokenStream::new()
+            })
+        });
+}
+
+/// Combining a large output, or dummy, with the errors.
+fn bench_output(c: &mut Criterion) {
+    let items = ITEM.repeat(200);
+    Benches::new("large_output")
+        .case("passing", &items)
+        .error_case("error", format!("error {items}"))
+        .run(c, |input| {
+            function!(input, |input: TokenStream, dummy: &mut Dummy| -> Result {
+                let first = input.clone().into_iter().next();
+                if matches!(first, Some(TokenTree::Ident(ident)) if ident == "error") {
+                    dummy.set(input);
+                    bail!("unexpected `error`");
+                }
+                Ok(input)
+            })
+        });
+}
+
+criterion_group!(
+    glue,
+    bench_function,
+    bench_attribute,
+    bench_derive,
+    bench_output
+);
+criterion_main!(glue);
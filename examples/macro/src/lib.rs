@@ -1,4 +1,4 @@
-use manyhow::{bail, manyhow, Emitter, ErrorMessage, Result, SilentError};
+use manyhow::{bail, manyhow, Dummy, Emitter, ErrorMessage, Result, SilentError};
 use proc_macro::TokenStream;
 use proc_macro2::{Span, TokenStream as TokenStream2};
 use quote::quote;
@@ -11,6 +11,12 @@ pub fn attr_item_as_dummy(_input: TokenStream, _item: TokenStream) -> SilentResu
     Err(SilentError)
 }
 
+#[manyhow(item_as_dummy(helper))]
+#[proc_macro_attribute]
+pub fn attr_item_as_dummy_helper(_input: TokenStream, _item: TokenStream) -> SilentResult {
+    Err(SilentError)
+}
+
 #[manyhow(item_as_dummy)]
 #[proc_macro_attribute]
 pub fn attr_item_as_dummy_ok(_input: TokenStream, _item: TokenStream) -> TokenStream2 {
@@ -51,6 +57,13 @@ pub fn attr_flag_dummy(_input: TokenStream, _item: TokenStream) -> SilentResult
     Err(SilentError)
 }
 
+#[manyhow(strip_attrs(doc))]
+#[proc_macro_attribute]
+pub fn attr_strip_attrs(_input: TokenStream, item: TokenStream2) -> TokenStream2 {
+    assert!(!item.to_string().contains("doc"), "doc comments should be removed");
+    item
+}
+
 #[manyhow(input_as_dummy)]
 #[proc_macro]
 pub fn input_as_dummy(_: TokenStream) -> SilentResult {
@@ -90,8 +103,8 @@ pub fn derive_no_dummy(_: TokenStream) -> SilentResult {
 
 #[manyhow]
 #[proc_macro_derive(Dummy)]
-pub fn derive_dummy(_: TokenStream, dummy: &mut TokenStream2) -> SilentResult {
-    *dummy = quote! {fn dummy(){}};
+pub fn derive_dummy(_: TokenStream, dummy: &mut Dummy) -> SilentResult {
+    dummy.set(quote! {fn dummy(){}});
     Err(SilentError)
 }
 
@@ -107,6 +120,11 @@ pub fn derive_flag(_: TokenStream) -> SilentResult {
     Err(SilentError)
 }
 
+#[manyhow(proc_macro_derive(Stub), derive_dummy(Stub { fn stub(&self) -> u8; }))]
+pub fn derive_stub(_: TokenStream) -> SilentResult {
+    Err(SilentError)
+}
+
 #[manyhow(impl_fn)]
 #[proc_macro]
 pub fn impl_fn(input: TokenStream2) -> TokenStream2 {
@@ -118,6 +136,12 @@ fn unit_test() {
     assert_eq!(impl_fn_impl(quote!(Hello World)).to_string(), "Hello World");
 }
 
+#[test]
+fn snapshot_test() {
+    let expansion = manyhow::function_expansion(quote!(Hello World), false, impl_fn_impl);
+    assert_eq!(expansion.to_snapshot(), "Hello World\n");
+}
+
 #[manyhow(impl_fn, input_as_dummy)]
 #[proc_macro]
 pub fn impl_fn_with_dummy(input: TokenStream2) -> TokenStream2 {
@@ -132,6 +156,11 @@ fn unit_test_with_dummy() {
     );
 }
 
+#[test]
+fn assert_expansion() {
+    manyhow::assert_expansion!(impl_fn_with_dummy_impl, { Hello World } => { Hello World });
+}
+
 mod module {
     use manyhow::SilentError;
     use proc_macro2::TokenStream;
@@ -152,6 +181,12 @@ pub fn parse_quote(input: syn::LitStr) -> syn::LitStr {
     input
 }
 
+#[manyhow]
+#[proc_macro]
+pub fn parse_tuple((name, value): (syn::Ident, syn::LitStr)) -> TokenStream2 {
+    quote!(const #name: &str = #value;)
+}
+
 #[manyhow(input_as_dummy)]
 #[proc_macro]
 pub fn parse_quote_dummy(input: syn::DeriveInput) -> syn::DeriveInput {
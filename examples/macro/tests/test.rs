@@ -19,6 +19,18 @@ fn attr() {
     #[parse_quote_attribute("string")]
     struct Struct;
     _ = Struct;
+
+    #[attr_item_as_dummy_helper]
+    struct Helper {
+        #[helper]
+        field: u8,
+    }
+    _ = Helper { field: 0 }.field;
+
+    #[attr_strip_attrs]
+    /// Documentation
+    struct Stripped;
+    _ = Stripped;
 }
 
 #[test]
@@ -38,6 +50,9 @@ fn function() {
     dummy();
 
     assert_eq!("hello", parse_quote!("hello"));
+
+    parse_tuple!(NAME, "value");
+    assert_eq!(NAME, "value");
 }
 
 #[test]
@@ -49,4 +64,11 @@ fn derive() {
     struct Dummy;
     _ = Dummy;
     dummy();
+
+    trait Stub {
+        fn stub(&self) -> u8;
+    }
+    #[derive(Stub)]
+    struct Stubbed;
+    let _: fn(&Stubbed) -> u8 = Stub::stub;
 }
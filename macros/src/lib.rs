@@ -27,18 +27,31 @@ impl ProcMacroType {
     fn dummy_flag(self) -> &'static str {
         match self {
             ProcMacroType::Function => "input_as_dummy",
-            ProcMacroType::Derive => "",
+            ProcMacroType::Derive => "derive_dummy",
             ProcMacroType::Attribute => "item_as_dummy",
         }
     }
 }
 impl ProcMacroType {
-    fn to_tokens(self, impl_path: TokenStream, as_dummy: bool) -> TokenStream {
-        let mut as_dummy = if as_dummy {
-            quote!(#[as_dummy])
-        } else {
-            quote!()
+    fn to_tokens(
+        self,
+        macro_name: &Ident,
+        impl_path: TokenStream,
+        as_dummy: Option<&Param>,
+        derive_dummy: Option<&Group>,
+        strip_attrs: Option<&Group>,
+    ) -> TokenStream {
+        let mut as_dummy = match as_dummy {
+            Some(Param::Complex(_, helper_attrs)) => quote!(#[as_dummy #helper_attrs]),
+            Some(Param::Flag(_)) => quote!(#[as_dummy]),
+            None => quote!(),
         };
+        if let Some(derive_dummy) = derive_dummy {
+            quote!(#[derive_dummy #derive_dummy]).to_tokens(&mut as_dummy);
+        }
+        if let Some(strip_attrs) = strip_attrs {
+            quote!(#[strip_attrs #strip_attrs]).to_tokens(&mut as_dummy);
+        }
 
         let fn_name = match self {
             ProcMacroType::Function => quote!(function),
@@ -52,8 +65,41 @@ impl ProcMacroType {
         } else {
             quote!()
         };
+        let mut call = quote!(::manyhow::#fn_name!(#as_dummy __input #item, #impl_path));
+        // function like macros can expand to expressions, where no marker can be added
+        if self != ProcMacroType::Function {
+            call = quote! {
+                ::manyhow::__private::provenance(
+                    #call,
+                    ::core::concat!(
+                        ::core::env!("CARGO_PKG_NAME"),
+                        "::",
+                        ::core::stringify!(#macro_name),
+                        " v",
+                        ::core::env!("CARGO_PKG_VERSION")
+                    ),
+                )
+            };
+        }
+        let inputs = match self {
+            ProcMacroType::Function => quote!([("input", __input.clone().into())]),
+            ProcMacroType::Derive => quote!([("item", __input.clone().into())]),
+            ProcMacroType::Attribute => {
+                quote!([("input", __input.clone().into()), ("item", __item.clone().into())])
+            }
+        };
         quote! {
-            ::manyhow::#fn_name!(#as_dummy __input #item, #impl_path)
+            ::manyhow::__private::Trace::finish(
+                ::manyhow::__private::Trace::start(
+                    ::core::concat!(
+                        ::core::env!("CARGO_PKG_NAME"),
+                        "::",
+                        ::core::stringify!(#macro_name)
+                    ),
+                    || #inputs,
+                ),
+                #call,
+            )
         }
     }
 }
@@ -152,7 +198,33 @@ pub fn manyhow(
                 format_args!("try `#[manyhow(impl_fn)]`"),
             );
         };
-        if ident == "proc_macro_derive" {
+        if ident == "strip_attrs" {
+            let Some(group) = input.next_group() else {
+                return with_helpful_error(
+                    item,
+                    input.next().unwrap_or(ident.into()).span(),
+                    "`strip_attrs` expects `(attribute, ...)`",
+                    format_args!("try `#[manyhow(strip_attrs(doc))]`"),
+                );
+            };
+            flags.push(Param::Complex(ident, group));
+        } else if ident == "derive_dummy" {
+            let Some(group) = input.next_group() else {
+                return with_helpful_error(
+                    item,
+                    input.next().unwrap_or(ident.into()).span(),
+                    "`derive_dummy` expects `(TraitPath { trait items })`",
+                    format_args!("try `#[manyhow(derive_dummy(Trait {{ fn method(&self); }}))]`"),
+                );
+            };
+            flags.push(Param::Complex(ident, group));
+        } else if ident == "input_as_dummy" || ident == "item_as_dummy" {
+            // optional list of helper attributes to strip from the dummy
+            match input.next_parenthesized() {
+                Some(group) => flags.push(Param::Complex(ident, group)),
+                None => flags.push(Param::Flag(ident)),
+            }
+        } else if ident == "proc_macro_derive" {
             let Some(group) = input.next_group() else {
                 return with_helpful_error(
                     item,
@@ -205,13 +277,33 @@ pub fn manyhow(
         }
     };
 
-    let mut as_dummy = false;
+    let mut as_dummy = None;
     let mut create_impl_fn = None;
+    let mut strip_attrs = None;
+    let mut derive_dummy = None;
     for (i, param) in flags.iter().enumerate() {
         let ident = param.ident();
         match (ident.to_string().as_str(), kind) {
             ("impl_fn", _) => create_impl_fn = Some((param.ident(), i)),
-            ("item_as_dummy", ProcMacroType::Attribute) => as_dummy = true,
+            ("strip_attrs", _) => {
+                if let Param::Complex(_, group) = param {
+                    strip_attrs = Some(group);
+                }
+            }
+            ("derive_dummy", ProcMacroType::Derive) => {
+                if let Param::Complex(_, group) = param {
+                    derive_dummy = Some(group);
+                }
+            }
+            ("derive_dummy", _) => {
+                return with_helpful_error(
+                    item,
+                    param.span(),
+                    "`derive_dummy` is only supported with `#[proc_macro_derive]`",
+                    format_args!("try `#[manyhow{}]` instead", flags_replace(i, None)),
+                );
+            }
+            ("item_as_dummy", ProcMacroType::Attribute) => as_dummy = Some(param),
             ("item_as_dummy", ProcMacroType::Function) => {
                 return with_helpful_error(
                     item,
@@ -225,7 +317,7 @@ pub fn manyhow(
                     ),
                 );
             }
-            ("input_as_dummy", ProcMacroType::Function) => as_dummy = true,
+            ("input_as_dummy", ProcMacroType::Function) => as_dummy = Some(param),
             ("input_as_dummy", ProcMacroType::Attribute) => {
                 return with_helpful_error(
                     item,
@@ -255,7 +347,7 @@ pub fn manyhow(
                     param.span(),
                     format_args!(
                         "only `proc_macro`, `proc_macro_attribute`, `proc_macro_derive`, `{}`, \
-                         and `impl_fn` are supported",
+                         `impl_fn` and `strip_attrs` are supported",
                         kind.dummy_flag(),
                     ),
                     format_args!("try `#[manyhow{}]", flags_replace(i, None)),
@@ -271,6 +363,7 @@ pub fn manyhow(
     let outer_impl_fn: Option<TokenStream>;
     let impl_fn_path: TokenStream;
     let inner_impl_fn: Option<TokenStream>;
+    let macro_name: Ident;
 
     // we support both use and fn
     if parser.next_keyword("use").is_some() {
@@ -307,6 +400,7 @@ pub fn manyhow(
 
         quote!(fn #fn_name).to_tokens(&mut output);
         impl_fn_path = path.into_iter().collect();
+        macro_name = fn_name;
 
         outer_impl_fn = None;
         inner_impl_fn = None;
@@ -334,6 +428,7 @@ pub fn manyhow(
         };
         // function name
         fn_name.to_tokens(&mut output);
+        macro_name = fn_name.clone();
 
         // there should not be any generics
         match parser.next_tt_lt() {
@@ -378,7 +473,13 @@ pub fn manyhow(
 
     kind.to_signature(&mut output);
 
-    let kind = kind.to_tokens(impl_fn_path, as_dummy);
+    let kind = kind.to_tokens(
+        &macro_name,
+        impl_fn_path,
+        as_dummy,
+        derive_dummy,
+        strip_attrs,
+    );
 
     quote! {
         {
@@ -0,0 +1,396 @@
+use std::iter::Peekable;
+
+use proc_macro2::{token_stream, Ident, Spacing, Span, TokenStream, TokenTree};
+use syn2::parse::{Parse, ParseStream};
+
+use crate::ErrorMessage;
+
+/// Simple key-value arguments, e.g., for attribute macros, as a lightweight
+/// alternative to [`darling`](https://docs.rs/darling).
+///
+/// Supports the syntax `name = "value", flag, path = some::Path<T, U>`, values
+/// are all tokens up to the next comma outside of generics.
+///
+/// Duplicate keys are reported while parsing, arguments are then consumed
+/// through typed getters like [`Args::get`] and [`Args::flag`]. Finally,
+/// [`Args::finish`] reports all arguments that were not consumed as unknown,
+/// suggesting similar keys, i.e., keys within an edit distance of a third of
+/// the unknown key's length.
+///
+/// ```
+/// use manyhow::Args;
+/// use quote::quote;
+/// # use syn2 as syn;
+///
+/// let mut args: Args =
+///     syn::parse2(quote!(name = "value", flag, path = some::Path<T, U>)).unwrap();
+/// let name: syn::LitStr = args.required("name")?;
+/// let path: Option<syn::Path> = args.get("path")?;
+/// let flag = args.flag("flag")?;
+/// let other_flag = args.flag("other_flag")?;
+/// args.finish()?;
+///
+/// assert_eq!(name.value(), "value");
+/// assert!(path.is_some());
+/// assert!(flag);
+/// assert!(!other_flag);
+///
+/// let mut args: Args = syn::parse2(quote!(nam = "value")).unwrap();
+/// let name: Option<syn::LitStr> = args.get("name")?;
+/// assert_eq!(
+///     args.finish().unwrap_err().to_string(),
+///     "unknown argument `nam`\n\n  = help: did you mean `name`?\n"
+/// );
+/// # Ok::<_, syn::Error>(())
+/// ```
+#[derive(Debug, Default)]
+pub struct Args {
+    args: Vec<Arg>,
+    known: Vec<&'static str>,
+}
+
+#[derive(Debug)]
+struct Arg {
+    key: Ident,
+    value: Option<(Span, TokenStream)>,
+}
+
+impl Args {
+    fn parse_args(tokens: TokenStream) -> syn2::Result<Self> {
+        let mut args: Vec<Arg> = Vec::new();
+        let mut errors = Vec::new();
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(tt) = tokens.next() {
+            let TokenTree::Ident(key) = tt else {
+                errors.push(error_message!(tt.span(), "expected argument name"));
+                // skip to next argument
+                tokens.find(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == ','));
+                continue;
+            };
+            let value = match tokens.next() {
+                None => None,
+                Some(TokenTree::Punct(p)) if p.as_char() == ',' => None,
+                Some(TokenTree::Punct(p)) if p.as_char() == '=' => {
+                    Some((p.span(), Self::parse_value(&mut tokens)))
+                }
+                Some(tt) => {
+                    errors.push(error_message!(
+                        tt.span(), "expected `,` or `=`";
+                        help = "arguments have the form `flag` or `name = value`"
+                    ));
+                    tokens.find(|tt| matches!(tt, TokenTree::Punct(p) if p.as_char() == ','));
+                    continue;
+                }
+            };
+            if args.iter().any(|arg| arg.key == key) {
+                errors.push(error_message!(
+                    key.span(), "duplicate argument `{key}`";
+                    help = "remove one of the `{key}` arguments"
+                ));
+            } else {
+                args.push(Arg { key, value });
+            }
+        }
+        combine(errors)?;
+        Ok(Self {
+            args,
+            known: Vec::new(),
+        })
+    }
+
+    /// Takes the tokens up to and including the next comma that is not inside
+    /// of generics, e.g., `some::Path<T, U>`.
+    fn parse_value(tokens: &mut Peekable<token_stream::IntoIter>) -> TokenStream {
+        let mut value = TokenStream::new();
+        let mut depth = 0_usize;
+        // `->` and `=>` do not close generics
+        let mut arrow = false;
+        while let Some(tt) = tokens.next() {
+            if let TokenTree::Punct(p) = &tt {
+                // `<=` and `>=` neither open nor close generics
+                let comparison = p.spacing() == Spacing::Joint
+                    && matches!(tokens.peek(), Some(TokenTree::Punct(p)) if p.as_char() == '=');
+                match p.as_char() {
+                    ',' if depth == 0 => break,
+                    '<' if !comparison => depth += 1,
+                    '>' if !arrow && !comparison => depth = depth.saturating_sub(1),
+                    _ => {}
+                }
+                arrow = p.spacing() == Spacing::Joint && matches!(p.as_char(), '-' | '=');
+            } else {
+                arrow = false;
+            }
+            value.extend([tt]);
+        }
+        value
+    }
+
+    fn take(&mut self, key: &'static str) -> Option<Arg> {
+        if !self.known.contains(&key) {
+            self.known.push(key);
+        }
+        let idx = self.args.iter().position(|arg| arg.key == key)?;
+        Some(self.args.remove(idx))
+    }
+
+    /// Returns whether the flag `key` was specified.
+    ///
+    /// # Errors
+    /// Errors if `key` was specified with a value.
+    pub fn flag(&mut self, key: &'static str) -> syn2::Result<bool> {
+        match self.take(key) {
+            None => Ok(false),
+            Some(Arg { value: None, .. }) => Ok(true),
+            Some(Arg {
+                key,
+                value: Some((eq, value)),
+            }) => Err(error_message!(
+                key.span()..value.into_iter().last().map_or(eq, |tt| tt.span()),
+                "`{key}` does not take a value";
+                help = "try `{key}`"
+            )
+            .into()),
+        }
+    }
+
+    /// Returns the value of `key` parsed as `T`, if specified.
+    ///
+    /// # Errors
+    /// Errors if `key` was specified without a value, or the value could not
+    /// be parsed as `T`.
+    pub fn get<T: Parse>(&mut self, key: &'static str) -> syn2::Result<Option<T>> {
+        match self.take(key) {
+            None => Ok(None),
+            Some(Arg { key, value: None }) => Err(error_message!(
+                key.span(), "`{key}` expects a value";
+                help = "try `{key} = ...`"
+            )
+            .into()),
+            Some(Arg {
+                key,
+                value: Some((eq, value)),
+            }) => {
+                if value.is_empty() {
+                    Err(error_message!(eq, "expected value for `{key}` after `=`").into())
+                } else {
+                    syn2::parse2(value).map(Some)
+                }
+            }
+        }
+    }
+
+    /// Returns the value of `key` parsed as `T`.
+    ///
+    /// # Errors
+    /// Errors if `key` was not specified, specified without a value, or the
+    /// value could not be parsed as `T`.
+    pub fn required<T: Parse>(&mut self, key: &'static str) -> syn2::Result<T> {
+        self.get(key)?.ok_or_else(|| {
+            error_message!("missing required argument `{key}`"; help = "try adding `{key} = ...`")
+                .into()
+        })
+    }
+
+    /// Checks that all arguments were consumed.
+    ///
+    /// # Errors
+    /// Errors for every argument that was not consumed through any of the
+    /// getters, i.e., is unknown, suggesting similar keys.
+    pub fn finish(self) -> syn2::Result<()> {
+        combine(self.args.into_iter().map(|Arg { key, .. }| {
+            let name = key.to_string();
+            let error = error_message!(key.span(), "unknown argument `{name}`");
+            // every one-character key is similar to every other one
+            let len = name.chars().count();
+            let max_distance = if len > 1 { (len / 3).max(1) } else { 0 };
+            if let Some(similar) = self
+                .known
+                .iter()
+                .filter(|known| levenshtein(known, &name) <= max_distance)
+                .min_by_key(|known| levenshtein(known, &name))
+            {
+                error.help(format_args!("did you mean `{similar}`?"))
+            } else if self.known.is_empty() {
+                error.help("no arguments are supported")
+            } else {
+                error.help(format_args!(
+                    "supported arguments are {}",
+                    self.known
+                        .iter()
+                        .map(|known| format!("`{known}`"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ))
+            }
+        }))
+    }
+
+    /// Returns `true` if there are no (unconsumed) arguments.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty()
+    }
+}
+
+impl Parse for Args {
+    fn parse(input: ParseStream) -> syn2::Result<Self> {
+        Self::parse_args(input.parse()?)
+    }
+}
+
+fn combine(errors: impl IntoIterator<Item = ErrorMessage>) -> syn2::Result<()> {
+    let mut errors = errors.into_iter().map(syn2::Error::from);
+    if let Some(mut error) = errors.next() {
+        for other in errors {
+            error.combine(other);
+        }
+        Err(error)
+    } else {
+        Ok(())
+    }
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, a) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, b) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if a == *b {
+                prev
+            } else {
+                prev.min(row[j]).min(current) + 1
+            };
+            prev = current;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use quote::{quote, ToTokens};
+    use syn2::{Ident, LitInt, LitStr};
+
+    use super::*;
+
+    #[test]
+    fn args() {
+        let mut args = syn2::parse2::<Args>(quote!(a = 1, b, c = "c", d = some::path)).unwrap();
+        assert_eq!(args.required::<LitInt>("a").unwrap().base10_digits(), "1");
+        assert!(args.flag("b").unwrap());
+        assert!(!args.flag("e").unwrap());
+        assert_eq!(args.get::<LitStr>("c").unwrap().unwrap().value(), "c");
+        assert!(!args.is_empty());
+        assert_eq!(
+            args.finish().unwrap_err().to_string(),
+            "unknown argument `d`\n\n  = help: supported arguments are `a`, `b`, `e`, `c`\n"
+        );
+        // values end at the next comma outside of generics
+        let mut args = syn2::parse2::<Args>(
+            quote!(d = some::path<T, U>, e = fn(u8, u8) -> Vec<(u8, u8)>, f = a >= b, g),
+        )
+        .unwrap();
+        let to_string = |tokens: &dyn ToTokens| tokens.to_token_stream().to_string();
+        assert_eq!(
+            to_string(&args.get::<syn2::Path>("d").unwrap().unwrap()),
+            quote!(some::path<T, U>).to_string()
+        );
+        assert_eq!(
+            to_string(&args.get::<syn2::Type>("e").unwrap().unwrap()),
+            quote!(fn(u8, u8) -> Vec<(u8, u8)>).to_string()
+        );
+        assert_eq!(
+            to_string(&args.get::<syn2::Expr>("f").unwrap().unwrap()),
+            quote!(a >= b).to_string()
+        );
+        assert!(args.flag("g").unwrap());
+        args.finish().unwrap();
+        assert_eq!(
+            syn2::parse2::<Args>(quote!(1, a)).unwrap_err().to_string(),
+            "expected argument name"
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            syn2::parse2::<Args>(quote!(a, b = 1, a = 2))
+                .unwrap_err()
+                .to_string(),
+            "duplicate argument `a`\n\n  = help: remove one of the `a` arguments\n"
+        );
+        assert_eq!(
+            syn2::parse2::<Args>(quote!(a b)).unwrap_err().to_string(),
+            "expected `,` or `=`\n\n  = help: arguments have the form `flag` or `name = value`\n"
+        );
+
+        let mut args = syn2::parse2::<Args>(quote!(a = 1, b, c =)).unwrap();
+        assert_eq!(
+            args.flag("a").unwrap_err().to_string(),
+            "`a` does not take a value\n\n  = help: try `a`\n"
+        );
+        assert_eq!(
+            args.get::<Ident>("b").unwrap_err().to_string(),
+            "`b` expects a value\n\n  = help: try `b = ...`\n"
+        );
+        assert_eq!(
+            args.get::<Ident>("c").unwrap_err().to_string(),
+            "expected value for `c` after `=`"
+        );
+        assert_eq!(
+            args.required::<Ident>("d").unwrap_err().to_string(),
+            "missing required argument `d`\n\n  = help: try adding `d = ...`\n"
+        );
+
+        let args = syn2::parse2::<Args>(quote!(something)).unwrap();
+        assert_eq!(
+            args.finish().unwrap_err().to_string(),
+            "unknown argument `something`\n\n  = help: no arguments are supported\n"
+        );
+
+        let mut args = syn2::parse2::<Args>(quote!(something)).unwrap();
+        args.flag("a").unwrap();
+        args.flag("b").unwrap();
+        assert_eq!(
+            args.finish().unwrap_err().to_string(),
+            "unknown argument `something`\n\n  = help: supported arguments are `a`, `b`\n"
+        );
+
+        let mut args = syn2::parse2::<Args>(quote!(something)).unwrap();
+        args.flag("a").unwrap();
+        args.flag("a").unwrap();
+        args.get::<Ident>("a").unwrap();
+        args.flag("b").unwrap();
+        assert_eq!(
+            args.finish().unwrap_err().to_string(),
+            "unknown argument `something`\n\n  = help: supported arguments are `a`, `b`\n"
+        );
+
+        let mut args = syn2::parse2::<Args>(quote!(nam, nmae, argumnet)).unwrap();
+        args.flag("name").unwrap();
+        args.flag("argument").unwrap();
+        assert_eq!(
+            args.finish()
+                .unwrap_err()
+                .into_iter()
+                .map(|error| error.to_string())
+                .collect::<Vec<_>>(),
+            [
+                "unknown argument `nam`\n\n  = help: did you mean `name`?\n",
+                "unknown argument `nmae`\n\n  = help: supported arguments are `name`, `argument`\n",
+                "unknown argument `argumnet`\n\n  = help: did you mean `argument`?\n",
+            ]
+        );
+    }
+
+    #[test]
+    fn levenshtein() {
+        assert_eq!(super::levenshtein("name", "name"), 0);
+        assert_eq!(super::levenshtein("name", "nmae"), 2);
+        assert_eq!(super::levenshtein("name", "names"), 1);
+        assert_eq!(super::levenshtein("", "abc"), 3);
+    }
+}
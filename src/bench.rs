@@ -0,0 +1,119 @@
+//! Benchmarking of macro implementations with
+//! [criterion](https://docs.rs/criterion).
+//!
+//! [`Benches`] measures only the expansion: the inputs are tokenized once
+//! upfront, and each case is expanded once before measuring, which also
+//! warms up the source map `proc-macro2` uses for spans outside of proc
+//! macros. Cases are declared as passing or failing, so both the regular and
+//! the error path of a macro are measured.
+//!
+//! ```no_run
+//! use criterion::{criterion_group, criterion_main, Criterion};
+//! use manyhow::bench::Benches;
+//! use manyhow::{bail, function, Result};
+//! use proc_macro2::TokenStream;
+//!
+//! fn my_macro(input: TokenStream) -> Result {
+//!     if input.is_empty() {
+//!         bail!("expected input");
+//!     }
+//!     Ok(input)
+//! }
+//!
+//! fn benches(c: &mut Criterion) {
+//!     Benches::new("my_macro")
+//!         .case("struct", "struct Struct { field: u8 }")
+//!         .error_case("empty", "")
+//!         .run(c, |input| function!(input, my_macro));
+//! }
+//!
+//! criterion_group!(group, benches);
+//! criterion_main!(group);
+//! ```
+use criterion::{BatchSize, Criterion};
+use proc_macro2::TokenStream;
+
+use crate::error::compile_errors;
+use crate::token_stream_from_str;
+
+/// Benchmarks of a macro, run as a criterion benchmark group.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Benches {
+    name: String,
+    /// name, input and whether errors are expected
+    cases: Vec<(String, String, bool)>,
+}
+
+impl Benches {
+    /// Creates the benchmark group `name`, e.g., the name of the macro.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            cases: Vec::new(),
+        }
+    }
+
+    /// Adds a case called `name`, expanding `input` without errors.
+    pub fn case(mut self, name: impl Into<String>, input: impl Into<String>) -> Self {
+        self.cases.push((name.into(), input.into(), false));
+        self
+    }
+
+    /// Adds a case called `name`, expanding `input` with errors, i.e.,
+    /// measuring the error path.
+    pub fn error_case(mut self, name: impl Into<String>, input: impl Into<String>) -> Self {
+        self.cases.push((name.into(), input.into(), true));
+        self
+    }
+
+    /// Runs the benchmarks, expanding each case with `expand`.
+    ///
+    /// # Panics
+    /// Panics if an input is not a valid token stream, or a case does not
+    /// emit errors as declared, i.e., would measure the wrong path.
+    #[track_caller]
+    pub fn run(&self, c: &mut Criterion, expand: impl Fn(TokenStream) -> TokenStream) {
+        let mut group = c.benchmark_group(&self.name);
+        for (name, input, errors) in &self.cases {
+            let tokens = token_stream_from_str(input)
+                .unwrap_or_else(|error| panic!("case `{name}`: {error}"));
+            check_case(name, *errors, expand(tokens.clone()));
+            group.bench_function(name, |b| {
+                b.iter_batched(|| tokens.clone(), &expand, BatchSize::SmallInput);
+            });
+        }
+        group.finish();
+    }
+}
+
+#[track_caller]
+fn check_case(name: &str, errors: bool, output: TokenStream) {
+    let emitted = compile_errors(output);
+    assert!(
+        errors != emitted.is_empty(),
+        "case `{name}` {}",
+        if errors {
+            "emits no errors, use `case()`".to_owned()
+        } else {
+            format!("emits errors, use `error_case()`: {:?}", emitted[0].1)
+        }
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use quote::quote;
+
+    #[test]
+    fn check_case() {
+        super::check_case("ok", false, quote! { struct A; });
+        super::check_case("error", true, quote!(::core::compile_error! { "a" }));
+    }
+
+    #[test]
+    #[should_panic = "case `error` emits errors, use `error_case()`: \"a\""]
+    fn check_case_errors() {
+        super::check_case("error", false, quote!(::core::compile_error! { "a" }));
+    }
+}
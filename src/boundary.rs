@@ -0,0 +1,48 @@
+use proc_macro2::{LexError, TokenStream};
+
+use crate::{ErrorMessage, ToTokensError};
+
+/// Parses `input` into a [`TokenStream`], spanning lexing errors, e.g., an
+/// unclosed delimiter, where the error occurred.
+///
+/// Together with [`expand_str()`], this allows passing token streams as
+/// strings, e.g., to run a macro in a [watt](https://docs.rs/watt)-style WASM
+/// runtime. As [`function()`](crate::function()),
+/// [`attribute()`](crate::attribute()) and [`derive()`](crate::derive())
+/// only use [`proc_macro`] when given a [`proc_macro::TokenStream`], they work
+/// outside of proc macros when given a [`proc_macro2::TokenStream`].
+///
+/// # Errors
+/// Errors if `input` is not a valid token stream.
+pub fn token_stream_from_str(input: &str) -> Result<TokenStream, ErrorMessage> {
+    input.parse().map_err(|error: LexError| {
+        ErrorMessage::new(error.span(), format_args!("invalid tokens: {error}"))
+    })
+}
+
+/// Runs `expand` on `input` parsed as a [`TokenStream`] and returns the
+/// output as a string, e.g., for a [watt](https://docs.rs/watt)-style WASM
+/// runtime.
+///
+/// If `input` is not a valid token stream, `expand` is not called and the
+/// error is returned as `compile_error!`, see [`token_stream_from_str()`].
+///
+/// ```
+/// use manyhow::{expand_str, function};
+/// use proc_macro2::TokenStream;
+///
+/// let output = expand_str("1 + 2", |input| {
+///     function!(input, |input: TokenStream| -> TokenStream { input })
+/// });
+/// assert_eq!(output, "1 + 2");
+///
+/// let output = expand_str("(1 + 2", |_| unreachable!());
+/// assert!(output.starts_with(":: core :: compile_error !"));
+/// ```
+pub fn expand_str(input: &str, expand: impl FnOnce(TokenStream) -> TokenStream) -> String {
+    match token_stream_from_str(input) {
+        Ok(input) => expand(input),
+        Err(error) => error.to_token_stream(),
+    }
+    .to_string()
+}
@@ -0,0 +1,281 @@
+//! Coverage of error sites, i.e., which invocations of
+//! [`error_message!`](crate::error_message!),
+//! [`spanned_error!`](crate::spanned_error!), [`bail!`](crate::bail!),
+//! [`bail_if!`](crate::bail_if!), [`ensure!`](crate::ensure!),
+//! [`ensure_eq!`](crate::ensure_eq!), [`ensure_ne!`](crate::ensure_ne!),
+//! [`ensure_matches!`](crate::ensure_matches!),
+//! [`ensure_or!`](crate::ensure_or!), [`emit!`](crate::emit!),
+//! [`emit_handle!`](crate::emit_handle!), [`try_emit!`](crate::try_emit!) and
+//! [`collect_errors!`](crate::collect_errors!) fired, to find diagnostics that
+//! are never tested.
+//!
+//! With the `coverage` feature, every error site records its file and line
+//! when it fires. As UI tests, e.g., with [trybuild](https://docs.rs/trybuild),
+//! expand the macro inside of `rustc`, the hits can be collected across
+//! processes by pointing `MANYHOW_COVERAGE` to a directory, otherwise only the
+//! hits of the current process are known.
+//!
+//! [`report()`] compares the hits with the error sites found in the source
+//! code:
+//!
+//! ```
+//! use manyhow::{bail, coverage, Result};
+//!
+//! fn check(value: u8) -> Result<u8> {
+//!     if value == 0 {
+//!         bail!("expected non zero value");
+//!     }
+//!     Ok(value)
+//! }
+//!
+//! # fn main() -> std::io::Result<()> {
+//! // e.g., in a test
+//! assert!(check(0).is_err());
+//! let report = coverage::report("src")?;
+//! println!("{report}");
+//! for site in report.uncovered() {
+//!     eprintln!("untested error at {}:{}", site.file.display(), site.line);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+use std::collections::BTreeMap;
+use std::fmt::{self, Display};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Hits of the error sites in this process.
+static HITS: Mutex<BTreeMap<(&'static str, u32), u64>> = Mutex::new(BTreeMap::new());
+
+/// Name of the file in `MANYHOW_COVERAGE` the hits are appended to.
+const HITS_FILE: &str = "hits.tsv";
+
+/// Records a hit of the error site at `file` and `line`.
+#[doc(hidden)]
+pub fn record(file: &'static str, line: u32) {
+    *HITS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .entry((file, line))
+        .or_default() += 1;
+    if let Some(target) = std::env::var_os("MANYHOW_COVERAGE") {
+        // Recording should never fail the macro
+        let _ = std::fs::create_dir_all(&target).and_then(|()| {
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(Path::new(&target).join(HITS_FILE))?
+                .write_all(format!("{file}\t{line}\n").as_bytes())
+        });
+    }
+}
+
+/// Returns the hits, read from `MANYHOW_COVERAGE` if set.
+fn hits() -> io::Result<BTreeMap<(String, u32), u64>> {
+    let mut hits = BTreeMap::new();
+    if let Some(target) = std::env::var_os("MANYHOW_COVERAGE") {
+        let content = match std::fs::read_to_string(Path::new(&target).join(HITS_FILE)) {
+            Ok(content) => content,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(error),
+        };
+        for (file, line) in content.lines().filter_map(|line| line.split_once('\t')) {
+            if let Ok(line) = line.parse() {
+                *hits.entry((file.to_owned(), line)).or_default() += 1;
+            }
+        }
+    } else {
+        let recorded = HITS
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        for (&(file, line), &count) in recorded.iter() {
+            hits.insert((file.to_owned(), line), count);
+        }
+    }
+    Ok(hits)
+}
+
+/// An error site found in the source code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Site {
+    /// The file containing the site.
+    pub file: PathBuf,
+    /// The 1-based line of the macro invocation.
+    pub line: u32,
+    /// How often the site fired.
+    pub hits: u64,
+}
+
+/// Coverage of the error sites, created by [`report()`].
+///
+/// Displays as a summary listing each site with its hits.
+#[derive(Debug, Clone)]
+pub struct Report {
+    sites: Vec<Site>,
+}
+
+impl Report {
+    /// Returns all error sites, ordered by file and line.
+    #[must_use]
+    pub fn sites(&self) -> &[Site] {
+        &self.sites
+    }
+
+    /// Returns the error sites that never fired.
+    pub fn uncovered(&self) -> impl Iterator<Item = &Site> {
+        self.sites.iter().filter(|site| site.hits == 0)
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fired = self.sites.iter().filter(|site| site.hits > 0).count();
+        writeln!(f, "error sites: {fired}/{} fired", self.sites.len())?;
+        for site in &self.sites {
+            write!(f, "  {}:{} ", site.file.display(), site.line)?;
+            if site.hits == 0 {
+                writeln!(f, "never fired")?;
+            } else {
+                writeln!(f, "{}", site.hits)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reports the coverage of the error sites in `source`, a file or a
+/// directory searched recursively for `.rs` files, e.g., `src`.
+///
+/// Error sites are found by searching for invocations of `error_message!`,
+/// `spanned_error!`, `bail!`, `bail_if!`, `ensure!`, `ensure_eq!`,
+/// `ensure_ne!`, `ensure_matches!`, `ensure_or!`, `emit!`, `emit_handle!`,
+/// `try_emit!` and `collect_errors!` outside of comments. The hits are matched
+/// to the files by path suffix, as `file!()` is relative to the workspace root.
+///
+/// # Errors
+/// Errors if `source` or the hits in `MANYHOW_COVERAGE` cannot be read.
+pub fn report(source: impl AsRef<Path>) -> io::Result<Report> {
+    let hits = hits()?;
+    let mut files = Vec::new();
+    collect_files(source.as_ref(), &mut files)?;
+    files.sort();
+    let mut sites = Vec::new();
+    for file in files {
+        let content = std::fs::read_to_string(&file)?;
+        for line in error_site_lines(&content) {
+            let hits = hits
+                .iter()
+                .filter(|((hit_file, hit_line), _)| {
+                    *hit_line == line && {
+                        let hit_file = Path::new(hit_file);
+                        hit_file.ends_with(&file) || file.ends_with(hit_file)
+                    }
+                })
+                .map(|(_, count)| count)
+                .sum();
+            sites.push(Site {
+                file: file.clone(),
+                line,
+                hits,
+            });
+        }
+    }
+    Ok(Report { sites })
+}
+
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    if path.is_dir() {
+        for entry in std::fs::read_dir(path)? {
+            collect_files(&entry?.path(), files)?;
+        }
+    } else if path.extension().is_some_and(|extension| extension == "rs") {
+        files.push(path.to_owned());
+    }
+    Ok(())
+}
+
+/// Returns the 1-based lines of `content` invoking an error site macro.
+fn error_site_lines(content: &str) -> impl Iterator<Item = u32> + '_ {
+    const MACROS: [&str; 13] = [
+        "error_message!",
+        "spanned_error!",
+        "bail!",
+        "bail_if!",
+        "ensure!",
+        "ensure_eq!",
+        "ensure_ne!",
+        "ensure_matches!",
+        "ensure_or!",
+        "emit!",
+        "emit_handle!",
+        "try_emit!",
+        "collect_errors!",
+    ];
+    content.lines().zip(1..).filter_map(|(line, number)| {
+        let code = line.split("//").next().unwrap_or_default();
+        MACROS
+            .iter()
+            .any(|name| {
+                code.match_indices(name).any(|(i, _)| {
+                    let before = code[..i].chars().next_back();
+                    let after = code[i + name.len()..].trim_start().chars().next();
+                    !before.is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '"'))
+                        && matches!(after, Some('(' | '[' | '{'))
+                })
+            })
+            .then_some(number)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Result;
+
+    #[test]
+    fn error_site_lines() {
+        // `emit` is inserted to not be detected as a site of this file
+        let content = [
+            "bail!(\"a\");",
+            "// emit!(",
+            &format!("  manyhow::{}! {{e, \"b\"}}", "emit"),
+            "rebail!()",
+            "let name = \"emit!\";",
+            "collect_errors!(e, a);",
+            "try_emit!(e, a, 0);",
+            "spanned_error!(a, \"f\");",
+            "bail_if!(a, \"e\");",
+            "ensure_or!(e, a, continue, \"d\");",
+            "emit_handle!(e, \"c\");",
+            "ensure_eq!(a, b);",
+            "ensure_ne!(a, b);",
+            "ensure_matches!(a, Some(_));",
+        ]
+        .join("\n");
+        assert_eq!(super::error_site_lines(&content).collect::<Vec<_>>(), [
+            1, 3, 6, 7, 8, 9, 10, 11, 12, 13, 14
+        ]);
+    }
+
+    #[test]
+    fn report() {
+        fn check(fire: bool) -> Result {
+            if !fire {
+                bail!("not fired");
+            }
+            Err(error_message!("fired").into())
+        }
+        assert!(check(true).is_err());
+        assert!(check(true).is_err());
+
+        let report = super::report("src/coverage.rs").unwrap();
+        let sites: Vec<_> = report.sites().iter().map(|site| site.hits).collect();
+        assert_eq!(sites, [0, 2]);
+        assert_eq!(report.uncovered().count(), 1);
+        assert!(
+            report.to_string().starts_with("error sites: 1/2 fired\n"),
+            "{report}"
+        );
+    }
+}
@@ -0,0 +1,285 @@
+//! Tracing and timing of expansions, enabled by the `MANYHOW_DEBUG` and
+//! `MANYHOW_TIMINGS` environment variables, and sorting of the emitted errors
+//! with `MANYHOW_DETERMINISTIC`, see [Debugging](crate#debugging).
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use proc_macro2::TokenStream;
+
+use crate::AnyTokenStream;
+
+/// The debug modes, read from the environment once per process.
+struct Config {
+    /// `MANYHOW_DEBUG`
+    debug: Option<OsString>,
+    /// `MANYHOW_TIMINGS`
+    timings: Option<OsString>,
+    /// `MANYHOW_DETERMINISTIC`
+    #[cfg(feature = "syn2")]
+    deterministic: bool,
+    /// `MANYHOW_DIAGNOSTICS`
+    #[cfg(feature = "syn2")]
+    diagnostics: Option<OsString>,
+    /// `MANYHOW_FIX_DIR`
+    #[cfg(feature = "syn2")]
+    fixes: Option<OsString>,
+    /// `MANYHOW_PROVENANCE`
+    provenance: bool,
+}
+
+impl Config {
+    fn get() -> &'static Self {
+        static CONFIG: OnceLock<Config> = OnceLock::new();
+        CONFIG.get_or_init(|| Self {
+            debug: std::env::var_os("MANYHOW_DEBUG"),
+            timings: std::env::var_os("MANYHOW_TIMINGS"),
+            #[cfg(feature = "syn2")]
+            deterministic: std::env::var_os("MANYHOW_DETERMINISTIC").is_some(),
+            #[cfg(feature = "syn2")]
+            diagnostics: std::env::var_os("MANYHOW_DIAGNOSTICS"),
+            #[cfg(feature = "syn2")]
+            fixes: std::env::var_os("MANYHOW_FIX_DIR"),
+            provenance: std::env::var_os("MANYHOW_PROVENANCE").is_some(),
+        })
+    }
+
+    /// Whether any mode needs to observe the expansion.
+    fn traces(&self) -> bool {
+        #[cfg(feature = "syn2")]
+        if self.deterministic || self.diagnostics.is_some() || self.fixes.is_some() {
+            return true;
+        }
+        self.debug.is_some() || self.timings.is_some()
+    }
+}
+
+/// Whether `MANYHOW_PROVENANCE` is set.
+pub(crate) fn provenance() -> bool {
+    Config::get().provenance
+}
+
+/// Trace of a single macro expansion, doing nothing if all debug modes are
+/// off.
+#[doc(hidden)]
+pub struct Trace(Option<Box<Active>>);
+
+struct Active {
+    name: &'static str,
+    config: &'static Config,
+    /// The inputs, if `MANYHOW_DEBUG` is set
+    inputs: Vec<(&'static str, TokenStream)>,
+    /// The start of the expansion, if `MANYHOW_TIMINGS` is set
+    start: Option<Instant>,
+}
+
+impl Trace {
+    /// Starts tracing the expansion of the macro `name`, `inputs` are only
+    /// evaluated if `MANYHOW_DEBUG` is set.
+    #[inline]
+    pub fn start<I: IntoIterator<Item = (&'static str, TokenStream)>>(
+        name: &'static str,
+        inputs: impl FnOnce() -> I,
+    ) -> Self {
+        let config = Config::get();
+        if !config.traces() {
+            return Self(None);
+        }
+        let inputs = if config.debug.is_some() {
+            inputs().into_iter().collect()
+        } else {
+            Vec::new()
+        };
+        Self::start_dyn(name, config, inputs)
+    }
+
+    /// Not generic, to only be instantiated once, instead of for every macro.
+    fn start_dyn(
+        name: &'static str,
+        config: &'static Config,
+        inputs: Vec<(&'static str, TokenStream)>,
+    ) -> Self {
+        #[cfg(feature = "syn2")]
+        if config.diagnostics.is_some() || config.fixes.is_some() {
+            crate::diagnostics::start();
+        }
+        Self(Some(Box::new(Active {
+            name,
+            config,
+            inputs,
+            start: config.timings.is_some().then(Instant::now),
+        })))
+    }
+
+    /// Records the timing, sorts the errors and writes the diagnostics, fixes
+    /// and the trace with the final `output`, if enabled.
+    #[inline]
+    pub fn finish<T: AnyTokenStream>(self, output: T) -> T {
+        match self.0 {
+            Some(active) => active.finish(output.into()).into(),
+            None => output,
+        }
+    }
+}
+
+impl Active {
+    /// Not generic, see [`Trace::start_dyn`].
+    fn finish(self, output: TokenStream) -> TokenStream {
+        let Self {
+            name,
+            config,
+            inputs,
+            start,
+        } = self;
+        #[cfg(feature = "syn2")]
+        let output = {
+            let mut output = output;
+            if config.deterministic {
+                output = crate::error::sort_compile_errors(output);
+            }
+            if config.diagnostics.is_some() || config.fixes.is_some() {
+                let diagnostics = crate::diagnostics::finish(output.clone());
+                if let Some(target) = &config.diagnostics {
+                    let lines = crate::diagnostics::diagnostic_lines(name, &diagnostics);
+                    let _ = crate::diagnostics::write(target, "jsonl", &lines);
+                }
+                if let Some(target) = &config.fixes {
+                    let lines = crate::diagnostics::fix_lines(name, &diagnostics);
+                    let _ = crate::diagnostics::write(target, "fixes.jsonl", &lines);
+                }
+            }
+            output
+        };
+        // Reporting should never fail the macro
+        if let (Some(target), Some(start)) = (&config.timings, start) {
+            let _ = record_timing(target, name, start.elapsed());
+        }
+        let Some(target) = &config.debug else {
+            return output;
+        };
+        let mut report = format!("// manyhow: {name}\n");
+        for (label, input) in &inputs {
+            report.push_str("// ");
+            report.push_str(label);
+            report.push_str(":\n");
+            report.push_str(&format_tokens(input));
+        }
+        report.push_str("// output:\n");
+        report.push_str(&format_output(&output));
+        let _ = write(target, &report);
+        output
+    }
+}
+
+#[derive(Default)]
+struct Timing {
+    count: u32,
+    total: Duration,
+    max: Duration,
+}
+
+/// Timings of all expansions in this process, i.e., of the crate being
+/// compiled.
+static TIMINGS: Mutex<BTreeMap<&'static str, Timing>> = Mutex::new(BTreeMap::new());
+
+/// Records `duration` for the macro `name` and rewrites the report of the
+/// current crate in the directory `target`.
+fn record_timing(target: &OsString, name: &'static str, duration: Duration) -> std::io::Result<()> {
+    let mut timings = TIMINGS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let timing = timings.entry(name).or_default();
+    timing.count += 1;
+    timing.total += duration;
+    timing.max = timing.max.max(duration);
+
+    let report: String = std::iter::once("macro\tcount\ttotal_ms\tmax_ms\n".to_owned())
+        .chain(timings.iter().map(|(name, timing)| {
+            format!(
+                "{name}\t{}\t{:.3}\t{:.3}\n",
+                timing.count,
+                timing.total.as_secs_f64() * 1000.,
+                timing.max.as_secs_f64() * 1000.,
+            )
+        }))
+        .collect();
+    std::fs::create_dir_all(target)?;
+    std::fs::write(crate_file(target, "tsv"), report)
+}
+
+/// Returns `<target>/<crate name>.<extension>`.
+pub(crate) fn crate_file(target: &OsString, extension: &str) -> PathBuf {
+    let krate = std::env::var("CARGO_CRATE_NAME").unwrap_or_else(|_| "unknown".into());
+    Path::new(target).join(format!("{krate}.{extension}"))
+}
+
+fn write(target: &OsString, report: &str) -> std::io::Result<()> {
+    if target == "1" {
+        eprintln!("{report}");
+        return Ok(());
+    }
+    let path = if Path::new(target).is_dir() {
+        crate_file(target, "log")
+    } else {
+        target.into()
+    };
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{report}"))
+}
+
+/// Formats `tokens` with `prettyplease` if enabled and they form a valid file.
+pub(crate) fn format_tokens(tokens: &TokenStream) -> String {
+    #[cfg(feature = "prettyplease")]
+    if let Ok(file) = syn2::parse2(tokens.clone()) {
+        return prettyplease::unparse(&file);
+    }
+    format!("{tokens}\n")
+}
+
+/// Formats `output`, rendering the emitted errors as comments.
+fn format_output(output: &TokenStream) -> String {
+    #[cfg(feature = "syn2")]
+    {
+        let (output, errors) = crate::error::split_compile_errors(output.clone());
+        let mut formatted = format_tokens(&output);
+        push_error_comments(&mut formatted, errors.into_iter().map(|(_, msg)| msg));
+        formatted
+    }
+    #[cfg(not(feature = "syn2"))]
+    format_tokens(output)
+}
+
+/// Appends `errors` as `// error: ...` comments.
+#[cfg(feature = "syn2")]
+pub(crate) fn push_error_comments(text: &mut String, errors: impl IntoIterator<Item = String>) {
+    for msg in errors {
+        let mut label = "error: ";
+        for line in msg.lines() {
+            text.push_str(format!("// {label}{line}").trim_end());
+            text.push('\n');
+            label = "";
+        }
+    }
+}
+
+#[cfg(all(test, feature = "syn2", not(feature = "prettyplease")))]
+mod test {
+    use quote::quote;
+
+    #[test]
+    fn format_output() {
+        assert_eq!(
+            super::format_output(&quote! {
+                struct A;
+                ::core::compile_error! { "error\n\n  = help: help" }
+            }),
+            "struct A ;\n// error: error\n//\n//   = help: help\n"
+        );
+    }
+}
@@ -0,0 +1,816 @@
+use std::fmt::{self, Display};
+use std::iter::Peekable;
+use std::ops::Range;
+
+use proc_macro2::{token_stream, Delimiter, Literal, Span, TokenStream, TokenTree};
+use serde::de::{
+    self, DeserializeOwned, DeserializeSeed, EnumAccess, MapAccess, SeqAccess, VariantAccess,
+    Visitor,
+};
+
+use crate::{ErrorMessage, LitStrMap, ManyhowParse, SpanRanged};
+
+/// Deserializes `tokens` into `T` using [`serde`].
+///
+/// The tokens are a comma separated list of `key = value` or `key: value`
+/// pairs, keys without a value are `true`. Values can be
+/// - literals: `"string"`, `'c'`, `1`, `-1.5`, `true`, `false`
+/// - lists: `[a, b]` or tuples: `(a, b)`
+/// - objects: `{ key = value }`
+/// - enum variants: `Variant`, `Variant(value)` and `Variant { key = value }`
+/// - `None`, and any other identifier which is deserialized as a string
+///
+/// Errors are spanned at the offending tokens.
+///
+/// ```
+/// use manyhow::deserialize_tokens;
+/// use quote::quote;
+/// # use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize, Debug)]
+/// struct Config {
+///     name: String,
+///     count: u8,
+///     verbose: bool,
+///     mode: Mode,
+///     tags: Vec<String>,
+/// }
+///
+/// #[derive(Deserialize, PartialEq, Debug)]
+/// enum Mode {
+///     Fast,
+///     Retry(u8),
+/// }
+///
+/// let config: Config = deserialize_tokens(quote! {
+///     name = "config", count = 3, verbose, mode = Retry(2), tags = [a, "b"]
+/// })
+/// .unwrap();
+/// assert_eq!(config.name, "config");
+/// assert_eq!(config.count, 3);
+/// assert!(config.verbose);
+/// assert_eq!(config.mode, Mode::Retry(2));
+/// assert_eq!(config.tags, ["a", "b"]);
+///
+/// let error = deserialize_tokens::<Config>(quote!(name = 1)).unwrap_err();
+/// assert_eq!(
+///     error.to_string(),
+///     "invalid type: integer `1`, expected a string"
+/// );
+/// ```
+///
+/// # Errors
+/// Errors if `tokens` do not have the above syntax or cannot be deserialized
+/// into `T`.
+pub fn deserialize_tokens<T: DeserializeOwned>(tokens: TokenStream) -> Result<T, ErrorMessage> {
+    let value = Value::parse_top_level(tokens)?;
+    let span = value.span.clone();
+    T::deserialize(value).map_err(|error| ErrorMessage::new(error.span.unwrap_or(span), error.msg))
+}
+
+/// Input deserialized from tokens using [`deserialize_tokens`], e.g., for
+/// attribute arguments.
+///
+/// ```
+/// use manyhow::{attribute, Deserialized};
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+/// # use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct Args {
+///     name: String,
+/// }
+///
+/// let output: TokenStream = attribute!(
+///     quote!(name = "hello"),
+///     quote!(),
+///     |Deserialized(args): Deserialized<Args>, _: TokenStream| -> TokenStream {
+///         let name = args.name;
+///         quote!(#name)
+///     }
+/// );
+/// assert_eq!(output.to_string(), "\"hello\"");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Deserialized<T>(pub T);
+
+impl<T: DeserializeOwned> ManyhowParse for Deserialized<T> {
+    fn manyhow_parse(input: TokenStream, _attr: bool) -> crate::Result<Self> {
+        Ok(Self(deserialize_tokens(input)?))
+    }
+}
+
+/// Deserializes the JSON in a string `literal` into `T`.
+///
+/// Errors are spanned inside the literal, see [`LitStrMap`].
+///
+/// ```
+/// use manyhow::deserialize_json;
+/// use proc_macro2::Literal;
+///
+/// let value: Vec<u8> = deserialize_json(&Literal::string("[1, 2]")).unwrap();
+/// assert_eq!(value, [1, 2]);
+///
+/// let error = deserialize_json::<Vec<u8>>(&Literal::string("[1, 2")).unwrap_err();
+/// assert_eq!(error.to_string(), "EOF while parsing a list");
+/// ```
+///
+/// # Errors
+/// Errors if `literal` is not a string literal or its value cannot be
+/// deserialized into `T`.
+#[cfg(feature = "serde_json")]
+pub fn deserialize_json<T: DeserializeOwned>(literal: &Literal) -> Result<T, ErrorMessage> {
+    let map = lit_str_map(literal)?;
+    serde_json::from_str(map.value()).map_err(|error| map.error(&error))
+}
+
+/// Deserializes the TOML in a string `literal` into `T`.
+///
+/// Errors are spanned inside the literal, see [`LitStrMap`].
+///
+/// ```
+/// use manyhow::deserialize_toml;
+/// use proc_macro2::Literal;
+/// # use serde_derive::Deserialize;
+///
+/// #[derive(Deserialize, Debug)]
+/// struct Config {
+///     name: String,
+/// }
+///
+/// let config: Config = deserialize_toml(&Literal::string("name = 'config'")).unwrap();
+/// assert_eq!(config.name, "config");
+///
+/// let error = deserialize_toml::<Config>(&Literal::string("name = 1")).unwrap_err();
+/// assert_eq!(
+///     error.to_string(),
+///     "invalid type: integer `1`, expected a string"
+/// );
+/// ```
+///
+/// # Errors
+/// Errors if `literal` is not a string literal or its value cannot be
+/// deserialized into `T`.
+#[cfg(feature = "toml")]
+pub fn deserialize_toml<T: DeserializeOwned>(literal: &Literal) -> Result<T, ErrorMessage> {
+    let map = lit_str_map(literal)?;
+    toml::from_str(map.value()).map_err(|error| map.error(&error))
+}
+
+/// Deserializes the YAML in a string `literal` into `T`.
+///
+/// Errors are spanned inside the literal, see [`LitStrMap`].
+///
+/// ```
+/// use manyhow::deserialize_yaml;
+/// use proc_macro2::Literal;
+///
+/// let value: Vec<u8> = deserialize_yaml(&Literal::string("[1, 2]")).unwrap();
+/// assert_eq!(value, [1, 2]);
+///
+/// let error = deserialize_yaml::<Vec<u8>>(&Literal::string("- 1\n- a")).unwrap_err();
+/// assert_eq!(
+///     error.to_string(),
+///     ".[1]: invalid type: string \"a\", expected u8"
+/// );
+/// ```
+///
+/// # Errors
+/// Errors if `literal` is not a string literal or its value cannot be
+/// deserialized into `T`.
+#[cfg(feature = "serde_yaml")]
+pub fn deserialize_yaml<T: DeserializeOwned>(literal: &Literal) -> Result<T, ErrorMessage> {
+    let map = lit_str_map(literal)?;
+    serde_yaml::from_str(map.value()).map_err(|error| map.error(&error))
+}
+
+#[cfg(any(feature = "serde_json", feature = "toml", feature = "serde_yaml"))]
+fn lit_str_map(literal: &Literal) -> Result<LitStrMap, ErrorMessage> {
+    LitStrMap::new(literal)
+        .ok_or_else(|| ErrorMessage::new(literal.span(), "expected string literal"))
+}
+
+#[cfg(feature = "serde_json")]
+impl crate::LocatedError for serde_json::Error {
+    fn location(&self) -> Option<crate::ErrorLocation> {
+        (self.line() > 0).then(|| crate::ErrorLocation::LineColumn {
+            line: self.line(),
+            column: self.column(),
+        })
+    }
+
+    fn message(&self) -> String {
+        let message = self.to_string();
+        message
+            .strip_suffix(&format!(
+                " at line {} column {}",
+                self.line(),
+                self.column()
+            ))
+            .map_or(message.clone(), ToOwned::to_owned)
+    }
+}
+
+#[cfg(feature = "serde_yaml")]
+impl crate::LocatedError for serde_yaml::Error {
+    fn location(&self) -> Option<crate::ErrorLocation> {
+        self.location()
+            .map(|location| crate::ErrorLocation::LineColumn {
+                line: location.line(),
+                column: location.column(),
+            })
+    }
+
+    fn message(&self) -> String {
+        let message = self.to_string();
+        message
+            .split_once(" at line ")
+            .map_or(message.clone(), |(message, _)| message.to_owned())
+    }
+}
+
+#[cfg(feature = "toml")]
+impl crate::LocatedError for toml::de::Error {
+    fn location(&self) -> Option<crate::ErrorLocation> {
+        self.span().map(Into::into)
+    }
+
+    fn message(&self) -> String {
+        toml::de::Error::message(self).to_owned()
+    }
+}
+
+#[derive(Debug)]
+struct Value {
+    kind: Kind,
+    span: Range<Span>,
+}
+
+#[derive(Debug)]
+enum Kind {
+    Bool(bool),
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+    String(String),
+    Char(char),
+    /// `None`
+    None,
+    /// `()`
+    Unit,
+    Seq(Vec<Value>),
+    Map(Vec<(Value, Value)>),
+    /// `Variant(...)` or `Variant {...}`
+    Variant(Box<Value>, Box<Value>),
+}
+
+type Tokens = Peekable<token_stream::IntoIter>;
+
+impl Value {
+    fn new(kind: Kind, span: impl SpanRanged) -> Self {
+        Self {
+            kind,
+            span: span.span_range(),
+        }
+    }
+
+    fn parse_top_level(tokens: TokenStream) -> Result<Self, ErrorMessage> {
+        let span = tokens_span(&tokens);
+        let mut tokens = tokens.into_iter().peekable();
+        let mut lookahead = tokens.clone();
+        let is_map = match (lookahead.next(), lookahead.next()) {
+            (None, _) => true,
+            (Some(TokenTree::Ident(_) | TokenTree::Literal(_)), Some(TokenTree::Punct(p))) => {
+                matches!(p.as_char(), '=' | ':' | ',')
+            }
+            _ => false,
+        };
+        if is_map {
+            Ok(Self::new(Kind::Map(parse_map(tokens)?), span))
+        } else {
+            let value = parse_value(&mut tokens)?;
+            if let Some(tt) = tokens.next() {
+                return Err(ErrorMessage::new(tt.span(), "unexpected token"));
+            }
+            Ok(value)
+        }
+    }
+
+    fn unexpected(&self) -> de::Unexpected<'_> {
+        match &self.kind {
+            Kind::Bool(value) => de::Unexpected::Bool(*value),
+            Kind::Unsigned(value) => de::Unexpected::Unsigned(*value),
+            Kind::Signed(value) => de::Unexpected::Signed(*value),
+            Kind::Float(value) => de::Unexpected::Float(*value),
+            Kind::String(value) => de::Unexpected::Str(value),
+            Kind::Char(value) => de::Unexpected::Char(*value),
+            Kind::None => de::Unexpected::Option,
+            Kind::Unit => de::Unexpected::Unit,
+            Kind::Seq(_) => de::Unexpected::Seq,
+            Kind::Map(_) => de::Unexpected::Map,
+            Kind::Variant(..) => de::Unexpected::Enum,
+        }
+    }
+}
+
+fn tokens_span(tokens: &TokenStream) -> Range<Span> {
+    let mut tokens = tokens.clone().into_iter();
+    let first = tokens.next().map_or_else(Span::call_site, |tt| tt.span());
+    first..tokens.last().map_or(first, |tt| tt.span())
+}
+
+fn is_punct(tt: Option<&TokenTree>, c: char) -> bool {
+    matches!(tt, Some(TokenTree::Punct(p)) if p.as_char() == c)
+}
+
+fn parse_list(tokens: TokenStream) -> Result<Vec<Value>, ErrorMessage> {
+    let mut tokens = tokens.into_iter().peekable();
+    let mut values = Vec::new();
+    while tokens.peek().is_some() {
+        values.push(parse_value(&mut tokens)?);
+        expect_comma(&mut tokens)?;
+    }
+    Ok(values)
+}
+
+fn parse_map(tokens: Tokens) -> Result<Vec<(Value, Value)>, ErrorMessage> {
+    let mut tokens = tokens;
+    let mut entries = Vec::new();
+    while let Some(tt) = tokens.next() {
+        let key = match tt {
+            TokenTree::Ident(ident) => {
+                let key = ident.to_string();
+                let key = key
+                    .strip_prefix("r#")
+                    .map_or(key.clone(), ToOwned::to_owned);
+                Value::new(Kind::String(key), ident.span())
+            }
+            TokenTree::Literal(lit) => match LitStrMap::new(&lit) {
+                Some(map) => Value::new(Kind::String(map.value().to_owned()), lit.span()),
+                None => return Err(ErrorMessage::new(lit.span(), "expected key")),
+            },
+            tt => return Err(ErrorMessage::new(tt.span(), "expected key")),
+        };
+        let value = if is_punct(tokens.peek(), '=') || is_punct(tokens.peek(), ':') {
+            let separator = tokens.next().expect("peeked");
+            if tokens.peek().is_none() || is_punct(tokens.peek(), ',') {
+                return Err(ErrorMessage::new(
+                    separator.span(),
+                    format_args!("expected value after `{separator}`"),
+                ));
+            }
+            parse_value(&mut tokens)?
+        } else {
+            Value::new(Kind::Bool(true), key.span.clone())
+        };
+        entries.push((key, value));
+        expect_comma(&mut tokens)?;
+    }
+    Ok(entries)
+}
+
+fn expect_comma(tokens: &mut Tokens) -> Result<(), ErrorMessage> {
+    match tokens.next() {
+        None => Ok(()),
+        Some(TokenTree::Punct(p)) if p.as_char() == ',' => Ok(()),
+        Some(tt) => Err(ErrorMessage::new(tt.span(), "expected `,`")),
+    }
+}
+
+fn parse_value(tokens: &mut Tokens) -> Result<Value, ErrorMessage> {
+    let Some(tt) = tokens.next() else {
+        return Err(ErrorMessage::call_site(
+            "unexpected end of input, expected value",
+        ));
+    };
+    Ok(match tt {
+        TokenTree::Ident(ident) => {
+            let name = ident.to_string();
+            let span = ident.span();
+            match name.as_str() {
+                "true" => Value::new(Kind::Bool(true), span),
+                "false" => Value::new(Kind::Bool(false), span),
+                "None" => Value::new(Kind::None, span),
+                _ => {
+                    let name = Value::new(Kind::String(name), span);
+                    match tokens.peek() {
+                        Some(TokenTree::Group(group))
+                            if matches!(
+                                group.delimiter(),
+                                Delimiter::Parenthesis | Delimiter::Brace
+                            ) =>
+                        {
+                            let Some(TokenTree::Group(group)) = tokens.next() else {
+                                unreachable!("peeked")
+                            };
+                            let content =
+                                parse_group(group.delimiter(), group.stream(), group.span())?;
+                            Value::new(
+                                Kind::Variant(Box::new(name), Box::new(content)),
+                                span..group.span(),
+                            )
+                        }
+                        _ => name,
+                    }
+                }
+            }
+        }
+        TokenTree::Literal(lit) => parse_literal(&lit, false, lit.span())?,
+        TokenTree::Punct(minus) if minus.as_char() == '-' => match tokens.next() {
+            Some(TokenTree::Literal(lit)) => parse_literal(&lit, true, minus.span()..lit.span())?,
+            _ => return Err(ErrorMessage::new(minus.span(), "expected number after `-`")),
+        },
+        TokenTree::Group(group) if group.delimiter() == Delimiter::None => {
+            let mut tokens = group.stream().into_iter().peekable();
+            let value = parse_value(&mut tokens)?;
+            if let Some(tt) = tokens.next() {
+                return Err(ErrorMessage::new(tt.span(), "unexpected token"));
+            }
+            value
+        }
+        TokenTree::Group(group) => parse_group(group.delimiter(), group.stream(), group.span())?,
+        TokenTree::Punct(punct) => return Err(ErrorMessage::new(punct.span(), "expected value")),
+    })
+}
+
+fn parse_group(
+    delimiter: Delimiter,
+    stream: TokenStream,
+    span: Span,
+) -> Result<Value, ErrorMessage> {
+    Ok(match delimiter {
+        Delimiter::Parenthesis if stream.is_empty() => Value::new(Kind::Unit, span),
+        Delimiter::Parenthesis | Delimiter::Bracket => {
+            Value::new(Kind::Seq(parse_list(stream)?), span)
+        }
+        Delimiter::Brace => Value::new(Kind::Map(parse_map(stream.into_iter().peekable())?), span),
+        Delimiter::None => unreachable!("none delimited groups are unwrapped"),
+    })
+}
+
+const INT_SUFFIXES: [&str; 12] = [
+    "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+];
+
+fn parse_literal(
+    lit: &Literal,
+    negative: bool,
+    span: impl SpanRanged,
+) -> Result<Value, ErrorMessage> {
+    if let Some(map) = LitStrMap::new(lit) {
+        if !negative {
+            return Ok(Value::new(Kind::String(map.value().to_owned()), span));
+        }
+    }
+    let source = lit.to_string();
+    if !negative {
+        if let Some(c) = source
+            .strip_prefix('\'')
+            .and_then(|c| c.strip_suffix('\''))
+            .and_then(|c| LitStrMap::new(&format!("\"{c}\"").parse().ok()?))
+            .and_then(|map| map.value().parse().ok())
+        {
+            return Ok(Value::new(Kind::Char(c), span));
+        }
+    }
+    let error = || ErrorMessage::new(lit.span(), "unsupported literal");
+    let number = source.replace('_', "");
+    let (number, radix) = match number.get(..2) {
+        Some("0x") => (&number[2..], 16),
+        Some("0o") => (&number[2..], 8),
+        Some("0b") => (&number[2..], 2),
+        _ => (number.as_str(), 10),
+    };
+    let int = INT_SUFFIXES
+        .iter()
+        .find_map(|suffix| number.strip_suffix(suffix))
+        .unwrap_or(number);
+    if let Ok(value) = u64::from_str_radix(int, radix) {
+        return Ok(if negative {
+            Value::new(
+                Kind::Signed(
+                    0_i64
+                        .checked_sub_unsigned(value)
+                        .ok_or_else(|| ErrorMessage::new(lit.span(), "integer out of range"))?,
+                ),
+                span,
+            )
+        } else {
+            Value::new(Kind::Unsigned(value), span)
+        });
+    }
+    if radix != 10 {
+        return Err(error());
+    }
+    let float = number
+        .strip_suffix("f32")
+        .or_else(|| number.strip_suffix("f64"))
+        .unwrap_or(number);
+    let value: f64 = float.parse().map_err(|_| error())?;
+    Ok(Value::new(
+        Kind::Float(if negative { -value } else { value }),
+        span,
+    ))
+}
+
+#[derive(Debug)]
+struct Error {
+    msg: String,
+    span: Option<Range<Span>>,
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.msg.fmt(f)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl de::Error for Error {
+    fn custom<T: Display>(msg: T) -> Self {
+        Self {
+            msg: msg.to_string(),
+            span: None,
+        }
+    }
+}
+
+/// Attaches `span` to errors that do not have a span yet.
+fn spanned<T>(span: &Range<Span>, result: Result<T, Error>) -> Result<T, Error> {
+    result.map_err(|mut error| {
+        error.span.get_or_insert_with(|| span.clone());
+        error
+    })
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let span = self.span;
+        spanned(&span, match self.kind {
+            Kind::Bool(value) => visitor.visit_bool(value),
+            Kind::Unsigned(value) => visitor.visit_u64(value),
+            Kind::Signed(value) => visitor.visit_i64(value),
+            Kind::Float(value) => visitor.visit_f64(value),
+            Kind::String(value) => visitor.visit_string(value),
+            Kind::Char(value) => visitor.visit_char(value),
+            Kind::None => visitor.visit_none(),
+            Kind::Unit => visitor.visit_unit(),
+            Kind::Seq(values) => visitor.visit_seq(Seq(values.into_iter())),
+            Kind::Map(entries) => visitor.visit_map(Map {
+                entries: entries.into_iter(),
+                value: None,
+            }),
+            Kind::Variant(..) => Err(de::Error::invalid_type(de::Unexpected::Enum, &visitor)),
+        })
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        match self.kind {
+            Kind::None => spanned(&self.span, visitor.visit_none()),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        let span = self.span.clone();
+        match self.kind {
+            Kind::String(_) => spanned(&span, visitor.visit_enum(Enum(self, None))),
+            Kind::Variant(name, content) => {
+                spanned(&span, visitor.visit_enum(Enum(*name, Some(*content))))
+            }
+            _ => Err(Error {
+                msg: <Error as de::Error>::invalid_type(self.unexpected(), &visitor).msg,
+                span: Some(span),
+            }),
+        }
+    }
+}
+
+struct Seq(std::vec::IntoIter<Value>);
+
+impl<'de> SeqAccess<'de> for Seq {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Error> {
+        self.0
+            .next()
+            .map(|value| seed.deserialize(value))
+            .transpose()
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+struct Map {
+    entries: std::vec::IntoIter<(Value, Value)>,
+    value: Option<Value>,
+}
+
+impl<'de> MapAccess<'de> for Map {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        let Some((key, value)) = self.entries.next() else {
+            return Ok(None);
+        };
+        self.value = Some(value);
+        seed.deserialize(key).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        seed.deserialize(self.value.take().expect("value is set by next_key_seed"))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+struct Enum(Value, Option<Value>);
+
+impl<'de> EnumAccess<'de> for Enum {
+    type Error = Error;
+    type Variant = Variant;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Variant), Error> {
+        let span = self.0.span.clone();
+        Ok((seed.deserialize(self.0)?, Variant(self.1, span)))
+    }
+}
+
+struct Variant(Option<Value>, Range<Span>);
+
+impl Variant {
+    fn content(self, expected: &str) -> Result<Value, Error> {
+        self.0.ok_or_else(|| Error {
+            msg: format!("expected {expected} variant"),
+            span: Some(self.1),
+        })
+    }
+}
+
+impl<'de> VariantAccess<'de> for Variant {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<(), Error> {
+        match self.0 {
+            None => Ok(()),
+            Some(content) => Err(Error {
+                msg: "expected unit variant".into(),
+                span: Some(content.span),
+            }),
+        }
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, Error> {
+        let content = self.content("newtype")?;
+        match content.kind {
+            Kind::Seq(values) if values.len() == 1 => {
+                seed.deserialize(values.into_iter().next().expect("len is 1"))
+            }
+            _ => seed.deserialize(content),
+        }
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_seq(self.content("tuple")?, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        de::Deserializer::deserialize_map(self.content("struct")?, visitor)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use quote::quote;
+    use serde_derive::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(deny_unknown_fields)]
+    struct Config {
+        name: String,
+        #[serde(default)]
+        flag: bool,
+        numbers: Vec<i64>,
+        float: Option<f32>,
+        tuple: (char, ()),
+        map: BTreeMap<String, Mode>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Mode {
+        Unit,
+        Newtype(u8),
+        Tuple(u8, u8),
+        Struct { a: u8 },
+    }
+
+    #[test]
+    fn deserialize() {
+        let config: Config = deserialize_tokens(quote! {
+            name: "name", flag, numbers = [1, -2, 0x10_u8], float = -1.5, tuple = ('c', ()),
+            map = { a: Unit, "b": Newtype(1), c: Tuple(1, 2), d: Struct { a: 1 } }
+        })
+        .unwrap();
+        assert_eq!(config, Config {
+            name: "name".into(),
+            flag: true,
+            numbers: vec![1, -2, 16],
+            float: Some(-1.5),
+            tuple: ('c', ()),
+            map: [
+                ("a".into(), Mode::Unit),
+                ("b".into(), Mode::Newtype(1)),
+                ("c".into(), Mode::Tuple(1, 2)),
+                ("d".into(), Mode::Struct { a: 1 }),
+            ]
+            .into_iter()
+            .collect()
+        });
+
+        let value: Option<Vec<u8>> = deserialize_tokens(quote!(None)).unwrap();
+        assert_eq!(value, None);
+        let value: Mode = deserialize_tokens(quote!(Newtype(1))).unwrap();
+        assert_eq!(value, Mode::Newtype(1));
+    }
+
+    #[test]
+    fn errors() {
+        let error = |tokens| {
+            deserialize_tokens::<Config>(tokens)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error(quote!(name = 1)),
+            "invalid type: integer `1`, expected a string"
+        );
+        assert_eq!(
+            error(quote!(nmae = 1)),
+            "unknown field `nmae`, expected one of `name`, `flag`, `numbers`, `float`, `tuple`, \
+             `map`"
+        );
+        assert_eq!(error(quote!(name = "")), "missing field `numbers`");
+        assert_eq!(error(quote!(name = "" numbers)), "expected `,`");
+        assert_eq!(error(quote!(name =)), "expected value after `=`");
+        assert_eq!(error(quote!(name = b"")), "unsupported literal");
+        assert_eq!(error(quote!(numbers = [-a])), "expected number after `-`");
+        assert_eq!(
+            error(quote!(name = "", numbers = [], tuple = ('c', ()), map = { a: Unit(1) })),
+            "expected unit variant"
+        );
+    }
+
+    #[test]
+    fn error_span() {
+        use crate::ToTokensError;
+
+        let tokens: TokenStream = "name = \"\",\n  numbers = [1, 2, \"3\"]".parse().unwrap();
+        let error = deserialize_tokens::<Config>(tokens).unwrap_err();
+        let error = error.to_token_stream().into_iter().collect::<Vec<_>>();
+        let (start, end) = (error[0].span(), error.last().unwrap().span());
+        assert_eq!(start.start().line, 2);
+        assert_eq!(start.start().column, 19);
+        assert_eq!(end.end().column, 22);
+    }
+}
@@ -0,0 +1,372 @@
+//! Structured diagnostics and suggested fixes for tooling, enabled by the
+//! `MANYHOW_DIAGNOSTICS` and `MANYHOW_FIX_DIR` environment variables, see
+//! [Structured diagnostics](crate#structured-diagnostics).
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::ffi::OsString;
+use std::fmt::Write as _;
+use std::io::Write as _;
+use std::ops::Range;
+use std::path::PathBuf;
+
+use proc_macro2::{Span, TokenStream};
+
+use crate::error::{compile_errors, proc_macro_span};
+use crate::to_tokens_span_range;
+
+/// Version of the schema, incremented on breaking changes.
+const VERSION: u32 = 1;
+
+thread_local! {
+    /// Diagnostics recorded by [`ErrorMessage`](crate::ErrorMessage)s during
+    /// the current expansion, `None` if not enabled.
+    static RECORDED: RefCell<Option<Vec<Recorded>>> = const { RefCell::new(None) };
+}
+
+/// A recorded diagnostic and the messages of the `compile_error!`s it emits.
+struct Recorded {
+    messages: Vec<String>,
+    diagnostic: Diagnostic,
+}
+
+/// A diagnostic of the schema, see [Structured
+/// diagnostics](crate#structured-diagnostics).
+#[derive(Debug)]
+pub(crate) struct Diagnostic {
+    pub code: Option<String>,
+    pub severity: Cow<'static, str>,
+    pub message: String,
+    pub span: Option<Range<Span>>,
+    pub children: Vec<Diagnostic>,
+    /// message, replacement and span
+    pub suggestions: Vec<(String, String, Range<Span>)>,
+}
+
+impl Diagnostic {
+    pub(crate) fn error(message: impl Into<String>, span: Option<Range<Span>>) -> Self {
+        Self {
+            code: None,
+            severity: "error".into(),
+            message: message.into(),
+            span,
+            children: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    fn push_json(&self, json: &mut String) {
+        json.push_str("\"code\":");
+        push_optional_string(json, self.code.as_deref());
+        json.push_str(",\"severity\":");
+        push_string(json, &self.severity);
+        json.push_str(",\"message\":");
+        push_string(json, &self.message);
+        json.push_str(",\"span\":");
+        push_span(json, self.span.as_ref());
+        json.push_str(",\"children\":[");
+        for (i, child) in self.children.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push('{');
+            child.push_json(json);
+            json.push('}');
+        }
+        json.push_str("],\"suggestions\":[");
+        for (i, (message, replacement, span)) in self.suggestions.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"message\":");
+            push_string(json, message);
+            json.push_str(",\"replacement\":");
+            push_string(json, replacement);
+            json.push_str(",\"span\":");
+            push_span(json, Some(span));
+            json.push('}');
+        }
+        json.push(']');
+    }
+}
+
+/// Whether diagnostics are recorded, i.e., `MANYHOW_DIAGNOSTICS` or
+/// `MANYHOW_FIX_DIR` is set.
+pub(crate) fn is_recording() -> bool {
+    RECORDED.with_borrow(Option::is_some)
+}
+
+/// Records `diagnostic`, emitted as `compile_error!`s with `messages`.
+pub(crate) fn record(messages: Vec<String>, diagnostic: Diagnostic) {
+    RECORDED.with_borrow_mut(|recorded| {
+        if let Some(recorded) = recorded {
+            recorded.push(Recorded {
+                messages,
+                diagnostic,
+            });
+        }
+    });
+}
+
+/// Starts recording the diagnostics of an expansion.
+pub(crate) fn start() {
+    RECORDED.set(Some(Vec::new()));
+}
+
+/// Stops recording and returns the diagnostics for the errors in `output`.
+///
+/// Errors not recorded, e.g., created by `syn`, are included with their
+/// message and span.
+pub(crate) fn finish(output: TokenStream) -> Vec<Diagnostic> {
+    let mut recorded = RECORDED.take().unwrap_or_default();
+    // `compile_error!`s of spanned attachments of recorded diagnostics
+    let mut covered: Vec<String> = Vec::new();
+    let mut diagnostics = Vec::new();
+    for (invocation, msg) in compile_errors(output) {
+        if let Some(i) = covered.iter().position(|covered| *covered == msg) {
+            covered.remove(i);
+            continue;
+        }
+        let diagnostic = if let Some(i) = recorded
+            .iter()
+            .position(|recorded| recorded.messages.first() == Some(&msg))
+        {
+            let recorded = recorded.remove(i);
+            covered.extend(recorded.messages.into_iter().skip(1));
+            recorded.diagnostic
+        } else {
+            let span = to_tokens_span_range(invocation);
+            Diagnostic::error(msg, Some(span.start..span.end))
+        };
+        diagnostics.push(diagnostic);
+    }
+    diagnostics
+}
+
+/// Renders `diagnostics` of the macro `name` as JSON lines.
+pub(crate) fn diagnostic_lines(name: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut lines = String::new();
+    for diagnostic in diagnostics {
+        push_header(&mut lines, name);
+        diagnostic.push_json(&mut lines);
+        lines.push_str("}\n");
+    }
+    lines
+}
+
+/// Renders the suggestions in `diagnostics` of the macro `name` as JSON lines
+/// of fixes, skipping suggestions without a known location.
+pub(crate) fn fix_lines(name: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut lines = String::new();
+    for diagnostic in diagnostics {
+        for (message, replacement, span) in &diagnostic.suggestions {
+            if let Some(location) = Location::of(span) {
+                push_fix(&mut lines, name, message, replacement, &location);
+            }
+        }
+    }
+    lines
+}
+
+fn push_fix(lines: &mut String, name: &str, message: &str, replacement: &str, location: &Location) {
+    let original = location
+        .path
+        .as_ref()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|content| snippet(&content, location.start, location.end));
+    push_header(lines, name);
+    lines.push_str("\"message\":");
+    push_string(lines, message);
+    lines.push(',');
+    location.push_fields(lines);
+    lines.push_str(",\"original\":");
+    push_optional_string(lines, original.as_deref());
+    lines.push_str(",\"replacement\":");
+    push_string(lines, replacement);
+    lines.push_str("}\n");
+}
+
+/// Returns the text of `content` between the 1-based line and column
+/// positions `start` and `end`.
+fn snippet(content: &str, start: (usize, usize), end: (usize, usize)) -> Option<String> {
+    let offset = |(line, column): (usize, usize)| {
+        let line_start = if line == 1 {
+            0
+        } else {
+            content.match_indices('\n').nth(line.checked_sub(2)?)?.0 + 1
+        };
+        let line = content[line_start..].split('\n').next()?;
+        let column = line
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain([line.len()])
+            .nth(column.checked_sub(1)?)?;
+        Some(line_start + column)
+    };
+    content.get(offset(start)?..offset(end)?).map(Into::into)
+}
+
+/// Appends `lines` to `<target>/<crate name>.<extension>`.
+pub(crate) fn write(target: &OsString, extension: &str, lines: &str) -> std::io::Result<()> {
+    if lines.is_empty() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(target)?;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(crate::debug::crate_file(target, extension))?
+        .write_all(lines.as_bytes())
+}
+
+fn push_header(json: &mut String, name: &str) {
+    let _ = write!(json, "{{\"version\":{VERSION},\"macro\":");
+    push_string(json, name);
+    json.push(',');
+}
+
+/// Location of a span in the source.
+struct Location {
+    file: String,
+    /// The file on disk, if it is a real file.
+    path: Option<PathBuf>,
+    start: (usize, usize),
+    end: (usize, usize),
+}
+
+impl Location {
+    /// Returns the location of `span`, `None` outside of proc macros.
+    fn of(span: &Range<Span>) -> Option<Self> {
+        let start = proc_macro_span(span.start)?.start();
+        let end = proc_macro_span(span.end)?.end();
+        Some(Self {
+            file: start.file(),
+            path: start.local_file(),
+            start: (start.line(), start.column()),
+            end: (end.line(), end.column()),
+        })
+    }
+
+    /// Appends the `file`, `start` and `end` fields.
+    fn push_fields(&self, json: &mut String) {
+        json.push_str("\"file\":");
+        push_string(json, &self.file);
+        let _ = write!(
+            json,
+            ",\"start\":{{\"line\":{},\"column\":{}}},\"end\":{{\"line\":{},\"column\":{}}}",
+            self.start.0, self.start.1, self.end.0, self.end.1
+        );
+    }
+}
+
+fn push_span(json: &mut String, span: Option<&Range<Span>>) {
+    match span.and_then(Location::of) {
+        Some(location) => {
+            json.push('{');
+            location.push_fields(json);
+            json.push('}');
+        }
+        None => json.push_str("null"),
+    }
+}
+
+fn push_optional_string(json: &mut String, string: Option<&str>) {
+    match string {
+        Some(string) => push_string(json, string),
+        None => json.push_str("null"),
+    }
+}
+
+fn push_string(json: &mut String, string: &str) {
+    json.push('"');
+    for char in string.chars() {
+        match char {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            char if char.is_control() => {
+                let _ = write!(json, "\\u{:04x}", char as u32);
+            }
+            char => json.push(char),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use proc_macro2::Span;
+    use quote::quote;
+
+    use crate::{ErrorMessage, ToTokensError};
+
+    #[test]
+    fn finish() {
+        super::start();
+        let error = ErrorMessage::call_site("unknown option `colour`")
+            .code("my_macro::unknown_option")
+            .note("options are\n\t\"color\"")
+            .suggestion(Span::call_site(), "a similar option exists", "color")
+            .spanned_attachment(Span::call_site(), "note", "defined here");
+        let mut output = quote!(
+            struct A;
+        );
+        error.to_tokens(&mut output);
+        syn2::Error::new(Span::call_site(), "from syn").to_tokens(&mut output);
+
+        assert_eq!(
+            super::diagnostic_lines("my_macro", &super::finish(output)),
+            concat!(
+                r#"{"version":1,"macro":"my_macro","code":"my_macro::unknown_option","severity":"error","#,
+                r#""message":"unknown option `colour`","span":null,"children":["#,
+                r#"{"code":null,"severity":"note","message":"options are\n\t\"color\"","span":null,"children":[],"suggestions":[]},"#,
+                r#"{"code":null,"severity":"note","message":"defined here","span":null,"children":[],"suggestions":[]}"#,
+                r#"],"suggestions":[{"message":"a similar option exists","replacement":"color","span":null}]}"#,
+                "\n",
+                r#"{"version":1,"macro":"my_macro","code":null,"severity":"error","message":"from syn","span":null,"children":[],"suggestions":[]}"#,
+                "\n",
+            )
+        );
+        assert!(!super::is_recording());
+    }
+    #[test]
+    fn snippet() {
+        let content = "fn main() {\n    let colour = 1;\n}\n";
+        assert_eq!(
+            super::snippet(content, (2, 9), (2, 15)).as_deref(),
+            Some("colour")
+        );
+        assert_eq!(
+            super::snippet(content, (1, 11), (2, 8)).as_deref(),
+            Some("{\n    let")
+        );
+        assert_eq!(super::snippet(content, (5, 1), (5, 2)), None);
+    }
+
+    #[test]
+    fn push_fix() {
+        let location = super::Location {
+            file: "src/main.rs".into(),
+            path: None,
+            start: (2, 9),
+            end: (2, 15),
+        };
+        let mut lines = String::new();
+        super::push_fix(
+            &mut lines,
+            "my_macro",
+            "a similar option exists",
+            "color",
+            &location,
+        );
+        assert_eq!(
+            lines,
+            concat!(
+                r#"{"version":1,"macro":"my_macro","message":"a similar option exists","file":"src/main.rs","#,
+                r#""start":{"line":2,"column":9},"end":{"line":2,"column":15},"original":null,"replacement":"color"}"#,
+                "\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,422 @@
+//! Builders for dummy code, emitted in place of the real output when a macro
+//! errors.
+//!
+//! See [`dummy: &mut Dummy`](crate#dummy-mut-dummy).
+use std::mem;
+use std::ops::{Deref, DerefMut};
+
+use proc_macro2::{Delimiter, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+#[cfg(feature = "syn2")]
+use syn2::parse::{ParseStream, Parser};
+#[cfg(feature = "syn2")]
+use syn2::{braced, DeriveInput, Path};
+
+use crate::AnyTokenStream;
+
+/// Dummy code of a macro handler, emitted instead of the output on error.
+///
+/// Takes the place of a `&mut TokenStream` dummy parameter, making explicit
+/// whether the dummy is replaced or extended. The underlying [`TokenStream`]
+/// is still accessible through [`Deref`] and [`DerefMut`].
+///
+/// When the dummy is initialized with the input/item (e.g., through
+/// `#[as_dummy]` or `#[manyhow(item_as_dummy)]`), this *seed* is replaced
+/// instead of extended by the first [`append`](Self::append), so an explicit
+/// dummy never defines the same items as the seed. Call
+/// [`keep_seed`](Self::keep_seed) to extend the seed instead.
+///
+/// ```
+/// use manyhow::{function, Dummy, Result};
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+///
+/// let output: TokenStream = function!(
+///     #[as_dummy]
+///     quote!(
+///         struct Struct;
+///     ),
+///     |_: TokenStream, dummy: &mut Dummy| -> Result {
+///         dummy.set_if_empty(quote!(
+///             struct Unused;
+///         ));
+///         assert!(dummy.is_seed());
+///         dummy.append(quote!(
+///             struct Struct {}
+///         ));
+///         dummy.append(quote!(
+///             fn function() {}
+///         ));
+///         manyhow::bail!("error")
+///     }
+/// );
+/// assert_eq!(
+///     output.to_string(),
+///     quote! {
+///         struct Struct {}
+///         fn function() {}
+///         ::core::compile_error! { "error" }
+///     }
+///     .to_string()
+/// );
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Dummy {
+    tokens: TokenStream,
+    seed: bool,
+}
+
+impl Dummy {
+    /// Replaces the dummy with `tokens`.
+    pub fn set(&mut self, tokens: impl ToTokens) {
+        self.tokens = tokens.into_token_stream();
+        self.seed = false;
+    }
+
+    /// Appends `tokens` to the dummy, replacing the seed unless
+    /// [`keep_seed`](Self::keep_seed) was called.
+    pub fn append(&mut self, tokens: impl ToTokens) {
+        if self.seed {
+            self.set(tokens);
+        } else {
+            tokens.to_tokens(&mut self.tokens);
+        }
+    }
+
+    /// Replaces the dummy with `tokens`, only if it is empty, i.e., it was
+    /// neither initialized with the input nor set before.
+    pub fn set_if_empty(&mut self, tokens: impl ToTokens) {
+        if self.tokens.is_empty() {
+            self.set(tokens);
+        }
+    }
+
+    /// Removes all tokens from the dummy.
+    pub fn clear(&mut self) {
+        self.set(TokenStream::new());
+    }
+
+    /// Removes all tokens from the dummy, returning them.
+    #[must_use]
+    pub fn take(&mut self) -> TokenStream {
+        self.seed = false;
+        mem::take(&mut self.tokens)
+    }
+
+    /// Returns whether the dummy only contains the input/item it was
+    /// initialized with.
+    #[must_use]
+    pub fn is_seed(&self) -> bool {
+        self.seed
+    }
+
+    /// Keeps the seed, making [`append`](Self::append) extend it instead of
+    /// replacing it.
+    pub fn keep_seed(&mut self) {
+        self.seed = false;
+    }
+}
+
+impl Deref for Dummy {
+    type Target = TokenStream;
+
+    fn deref(&self) -> &Self::Target {
+        &self.tokens
+    }
+}
+
+/// Modifying the [`TokenStream`] directly keeps the seed.
+impl DerefMut for Dummy {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.seed = false;
+        &mut self.tokens
+    }
+}
+
+/// Initializes the dummy with a seed, unless `value` is empty.
+impl From<TokenStream> for Dummy {
+    fn from(value: TokenStream) -> Self {
+        Self {
+            seed: !value.is_empty(),
+            tokens: value,
+        }
+    }
+}
+
+impl From<Dummy> for TokenStream {
+    fn from(value: Dummy) -> Self {
+        value.tokens
+    }
+}
+
+impl ToTokens for Dummy {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.tokens.to_tokens(tokens);
+    }
+}
+
+impl AnyTokenStream for Dummy {}
+
+/// Generates a unit struct named `ident`, e.g., as dummy for a macro
+/// generating a type.
+///
+/// ```
+/// # use quote::quote;
+/// assert_eq!(
+///     manyhow::dummy::unit_struct(quote!(Name)).to_string(),
+///     quote!(
+///         struct Name;
+///     )
+///     .to_string()
+/// );
+/// ```
+#[must_use]
+pub fn unit_struct(ident: impl ToTokens) -> TokenStream {
+    quote!(struct #ident;)
+}
+
+/// Generates a function with the signature `sig` and a `todo!()` body, e.g.,
+/// as dummy for an attribute macro on a function.
+///
+/// A trailing `;` or body in `sig` is ignored, so the item of an attribute
+/// macro can be passed directly.
+///
+/// ```
+/// # use quote::quote;
+/// assert_eq!(
+///     manyhow::dummy::fn_stub(quote!(
+///         pub fn name(a: u8) -> u8 {
+///             a
+///         }
+///     ))
+///     .to_string(),
+///     quote! {
+///         #[allow(unused_variables)]
+///         pub fn name(a: u8) -> u8 { ::core::todo!() }
+///     }
+///     .to_string()
+/// );
+/// ```
+#[must_use]
+pub fn fn_stub(sig: impl ToTokens) -> TokenStream {
+    let mut sig: Vec<TokenTree> = sig.into_token_stream().into_iter().collect();
+    if matches!(sig.last(), Some(TokenTree::Punct(semi)) if semi.as_char() == ';')
+        || matches!(sig.last(), Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace)
+    {
+        sig.pop();
+    }
+    quote! {
+        #[allow(unused_variables)]
+        #(#sig)* { ::core::todo!() }
+    }
+}
+
+/// Generates an empty `impl Trait for Type`, e.g., as dummy for traits
+/// without required items.
+///
+/// ```
+/// # use quote::quote;
+/// assert_eq!(
+///     manyhow::dummy::empty_impl(quote!(Default), quote!(Struct)).to_string(),
+///     quote!(impl Default for Struct {}).to_string()
+/// );
+/// ```
+#[must_use]
+pub fn empty_impl(trait_: impl ToTokens, ty: impl ToTokens) -> TokenStream {
+    quote!(impl #trait_ for #ty {})
+}
+
+/// Generates `unreachable!()`, an expression usable as any type, e.g., as
+/// dummy for a function like macro in expression position.
+#[must_use]
+pub fn expr_unreachable() -> TokenStream {
+    quote!(::core::unreachable!())
+}
+
+/// Generates an `impl Trait for Type` for the type of a derive `input`, so
+/// that a failed derive does not cascade into errors wherever the trait is
+/// used.
+///
+/// `skeleton` is the trait path, optionally followed by the trait items in
+/// braces. Function signatures (ending in `;`) get a `todo!()` body, all other
+/// items, e.g., `type Output = ();` or functions with a body, are copied
+/// verbatim. The generics of `input` are used for the impl, without adding
+/// any bounds.
+///
+/// This is used by the `derive_dummy(...)` option of
+/// [`#[manyhow]`](crate::manyhow) and [`derive!`](crate::derive!).
+///
+/// ```
+/// use manyhow::dummy::derive_stub;
+/// use quote::quote;
+/// # use syn2 as syn;
+///
+/// let input: syn::DeriveInput = syn::parse_quote!(struct Struct<T>(T););
+/// assert_eq!(
+///     derive_stub(
+///         quote!(my_crate::Trait {
+///             type Output = ();
+///             fn method(&self) -> Self::Output;
+///         }),
+///         &input
+///     )
+///     .to_string(),
+///     quote! {
+///         #[automatically_derived]
+///         #[allow(unused_variables)]
+///         impl<T> my_crate::Trait for Struct<T> {
+///             type Output = ();
+///             fn method(&self) -> Self::Output { ::core::todo!() }
+///         }
+///     }
+///     .to_string()
+/// );
+/// ```
+///
+/// # Panics
+/// Panics if `skeleton` is not a path, optionally followed by `{...}`.
+#[cfg(feature = "syn2")]
+#[must_use]
+pub fn derive_stub(skeleton: impl ToTokens, input: &DeriveInput) -> TokenStream {
+    let (path, items) = (|input: ParseStream| {
+        let path: Path = input.parse()?;
+        let items = if input.is_empty() {
+            TokenStream::new()
+        } else {
+            let items;
+            braced!(items in input);
+            items.parse()?
+        };
+        Ok((path, items))
+    })
+    .parse2(skeleton.into_token_stream())
+    .expect("skeleton should be a trait path, optionally followed by `{...}`");
+    let items = stub_items(items);
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    quote! {
+        #[automatically_derived]
+        #[allow(unused_variables)]
+        impl #impl_generics #path for #ident #ty_generics #where_clause {
+            #items
+        }
+    }
+}
+
+/// Adds `todo!()` bodies to all function signatures in `items`.
+#[cfg(feature = "syn2")]
+fn stub_items(items: TokenStream) -> TokenStream {
+    let mut output = TokenStream::new();
+    let mut item = TokenStream::new();
+    for tt in items {
+        match tt {
+            TokenTree::Punct(semi) if semi.as_char() == ';' => {
+                let is_fn = item
+                    .clone()
+                    .into_iter()
+                    .any(|tt| matches!(tt, TokenTree::Ident(ident) if ident == "fn"));
+                output.extend(item);
+                if is_fn {
+                    quote!({ ::core::todo!() }).to_tokens(&mut output);
+                } else {
+                    semi.to_tokens(&mut output);
+                }
+                item = TokenStream::new();
+            }
+            TokenTree::Group(body) if body.delimiter() == Delimiter::Brace => {
+                output.extend(item);
+                body.to_tokens(&mut output);
+                item = TokenStream::new();
+            }
+            tt => item.extend([tt]),
+        }
+    }
+    output.extend(item);
+    output
+}
+
+/// [`derive_stub`] for the `derive_dummy` options, producing no dummy when
+/// `item` cannot be parsed.
+#[cfg(feature = "syn2")]
+#[doc(hidden)]
+pub fn derive_stub_tokens(skeleton: TokenStream, item: impl AnyTokenStream) -> TokenStream {
+    syn2::parse2(item.into())
+        .map(|input| derive_stub(skeleton, &input))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod test {
+    use quote::quote;
+
+    use super::Dummy;
+
+    #[test]
+    fn seed() {
+        let mut dummy = Dummy::from(quote!(seed));
+        assert!(dummy.is_seed());
+        dummy.append(quote!(a));
+        dummy.append(quote!(b));
+        assert_eq!(dummy.to_string(), "a b");
+
+        let mut dummy = Dummy::from(quote!(seed));
+        dummy.keep_seed();
+        dummy.append(quote!(a));
+        assert_eq!(dummy.to_string(), "seed a");
+
+        let mut dummy = Dummy::from(quote!(seed));
+        dummy.extend(quote!(a));
+        dummy.append(quote!(b));
+        assert_eq!(dummy.to_string(), "seed a b");
+
+        let mut dummy = Dummy::default();
+        assert!(!dummy.is_seed());
+        dummy.append(quote!(a));
+        dummy.append(quote!(b));
+        assert_eq!(dummy.to_string(), "a b");
+    }
+
+    #[test]
+    #[cfg(feature = "syn2")]
+    fn derive_stub() {
+        let input = syn2::parse_quote!(
+            enum Enum<'a, T: Clone>
+            where
+                T: Copy,
+            {
+                A(&'a T),
+            }
+        );
+        assert_eq!(
+            super::derive_stub(quote!(Trait), &input).to_string(),
+            quote! {
+                #[automatically_derived]
+                #[allow(unused_variables)]
+                impl<'a, T: Clone> Trait for Enum<'a, T> where T: Copy, {}
+            }
+            .to_string()
+        );
+        assert_eq!(
+            super::derive_stub(
+                quote!(Trait {
+                    const A: u8 = 1;
+                    fn a() -> u8 { 1 }
+                    unsafe fn b<T>(value: T) where T: Copy;
+                }),
+                &input
+            )
+            .to_string(),
+            quote! {
+                #[automatically_derived]
+                #[allow(unused_variables)]
+                impl<'a, T: Clone> Trait for Enum<'a, T> where T: Copy, {
+                    const A: u8 = 1;
+                    fn a() -> u8 { 1 }
+                    unsafe fn b<T>(value: T) where T: Copy { ::core::todo!() }
+                }
+            }
+            .to_string()
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Non-generic entry points for proc macros of the common shape
+//! `fn(TokenStream) -> Result<TokenStream, Error>`.
+//!
+//! [`function()`](crate::function()), [`attribute()`](crate::attribute()),
+//! [`derive()`](crate::derive()) and
+//! [`#[manyhow]`](crate#using-the-manyhow-macro) are generic over the handler,
+//! so their glue is instantiated for every macro. The functions in this module
+//! take the handler as a function pointer and are compiled once as part of
+//! `manyhow`, which reduces the compile time of crates with many entry points.
+//! In exchange, the handlers can neither take parsed inputs nor a
+//! [`Dummy`](crate::Dummy) or [`Emitter`](crate::Emitter).
+//!
+//! ```
+//! # extern crate proc_macro;
+//! use manyhow::entry::{self, Config};
+//! use manyhow::{bail, Result};
+//! use proc_macro2::TokenStream;
+//! # use quote::quote;
+//!
+//! const MY_MACRO: Config = Config::new(concat!(env!("CARGO_PKG_NAME"), "::my_macro")).as_dummy();
+//!
+//! fn my_macro_impl(input: TokenStream) -> Result {
+//!     if input.is_empty() {
+//!         bail!("expected input");
+//!     }
+//!     Ok(input)
+//! }
+//!
+//! # let _ = quote! {
+//! #[proc_macro]
+//! # };
+//! pub fn my_macro(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+//!     entry::function(input.into(), &MY_MACRO, my_macro_impl).into()
+//! }
+//! ```
+use proc_macro2::TokenStream;
+
+use crate::debug::Trace;
+use crate::{Result, __private};
+
+/// Handler of a function-like or derive macro.
+pub type Handler = fn(TokenStream) -> Result;
+
+/// Handler of an attribute macro, taking the input and the item.
+pub type AttributeHandler = fn(TokenStream, TokenStream) -> Result;
+
+/// Configuration of an entry point, usually a `const` per macro.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[must_use]
+pub struct Config {
+    name: &'static str,
+    as_dummy: bool,
+}
+
+impl Config {
+    /// Creates the configuration of the macro `name`, used for the
+    /// [debug output](crate#debugging) and the [provenance](crate#provenance)
+    /// marker, e.g., `concat!(env!("CARGO_PKG_NAME"), "::my_macro")`.
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            as_dummy: false,
+        }
+    }
+
+    /// Uses the input of a function-like macro, or the item of an attribute
+    /// macro, as dummy if the handler errors, like `#[as_dummy]` or
+    /// `item_as_dummy`.
+    pub const fn as_dummy(mut self) -> Self {
+        self.as_dummy = true;
+        self
+    }
+}
+
+/// Handles a function-like proc macro with `handler`, see
+/// [`function()`](crate::function()).
+#[must_use]
+pub fn function(input: TokenStream, config: &Config, handler: Handler) -> TokenStream {
+    let trace = Trace::start(config.name, || [("input", input.clone())]);
+    let output = crate::function_expansion(input, config.as_dummy, handler);
+    trace.finish(output.into())
+}
+
+/// Handles an attribute proc macro with `handler`, see
+/// [`attribute()`](crate::attribute()).
+#[must_use]
+pub fn attribute(
+    input: TokenStream,
+    item: TokenStream,
+    config: &Config,
+    handler: AttributeHandler,
+) -> TokenStream {
+    let trace = Trace::start(config.name, || {
+        [("input", input.clone()), ("item", item.clone())]
+    });
+    let output = crate::attribute_expansion(input, item, config.as_dummy, handler);
+    trace.finish(__private::provenance(output.into(), config.name))
+}
+
+/// Handles a derive proc macro with `handler`, see
+/// [`derive()`](crate::derive()).
+///
+/// [`Config::as_dummy`] has no effect, as the item of a derive macro is
+/// always emitted by the compiler.
+#[must_use]
+pub fn derive(item: TokenStream, config: &Config, handler: Handler) -> TokenStream {
+    let trace = Trace::start(config.name, || [("item", item.clone())]);
+    let output = crate::derive_expansion(item, handler);
+    trace.finish(__private::provenance(output.into(), config.name))
+}
+
+#[cfg(test)]
+mod test {
+    use quote::quote;
+
+    use super::*;
+
+    const CONFIG: Config = Config::new("entry");
+
+    #[allow(clippy::needless_pass_by_value)]
+    fn reject_empty(input: TokenStream) -> Result {
+        if input.is_empty() {
+            bail!("expected input");
+        }
+        Ok(quote!(output #input))
+    }
+
+    #[test]
+    fn function() {
+        assert_eq!(
+            super::function(quote!(input), &CONFIG, reject_empty).to_string(),
+            "output input"
+        );
+        assert_eq!(
+            super::function(quote!(), &CONFIG.as_dummy(), reject_empty).to_string(),
+            quote!(::core::compile_error! { "expected input" }).to_string()
+        );
+        assert_eq!(
+            super::function(quote!(input), &CONFIG.as_dummy(), |_| {
+                Err(error_message!("error").into())
+            })
+            .to_string(),
+            quote!(input::core::compile_error! { "error" }).to_string()
+        );
+    }
+
+    #[test]
+    fn attribute() {
+        let handler: AttributeHandler = |input, _| reject_empty(input);
+        assert_eq!(
+            super::attribute(quote!(), quote!(item), &CONFIG.as_dummy(), handler).to_string(),
+            quote!(item::core::compile_error! { "expected input" }).to_string()
+        );
+        assert_eq!(
+            super::attribute(quote!(), quote!(item), &CONFIG, handler).to_string(),
+            quote!(::core::compile_error! { "expected input" }).to_string()
+        );
+    }
+
+    #[test]
+    fn derive() {
+        assert_eq!(
+            super::derive(
+                quote!(
+                    struct A;
+                ),
+                &CONFIG,
+                reject_empty
+            )
+            .to_string(),
+            "output struct A ;"
+        );
+    }
+}
@@ -1,16 +1,25 @@
 #![allow(clippy::missing_errors_doc)]
+use std::any::Any;
+use std::borrow::Cow;
+use std::cell::OnceCell;
 use std::convert::Infallible;
-use std::fmt::{Debug, Display};
+use std::fmt::{Arguments, Debug, Display};
 use std::mem;
 use std::ops::{Add, AddAssign, Range};
 
 #[cfg(feature = "darling")]
 use darling_core::Error as DarlingError;
-use proc_macro2::{Span, TokenStream};
+#[cfg(feature = "darling_0_21")]
+use darling_core_0_21::Error as Darling021Error;
+#[cfg(feature = "litrs")]
+use litrs::{InvalidToken as LitrsInvalidToken, ParseError as LitrsParseError};
+#[cfg(feature = "proc-macro-error2")]
+use proc_macro_error2::Diagnostic;
+use proc_macro2::{Ident, Span, TokenStream, TokenTree};
 use quote::{quote_spanned, ToTokens};
 #[cfg(feature = "syn1")]
 use syn1::Error as Syn1Error;
-#[cfg(feature = "syn2")]
+#[cfg(feature = "syn2-error")]
 use syn2::Error as Syn2Error;
 
 #[cfg(doc)]
@@ -28,14 +37,110 @@ pub struct SilentError;
 /// This crates Error type
 #[derive(Debug)]
 #[must_use]
-pub struct Error(Vec<Box<dyn ToTokensError>>);
+pub struct Error(Errors);
+
+/// Errors of an [`Error`] or [`Emitter`], storing a single [`ErrorMessage`]
+/// inline and other single errors without allocating a `Vec`, as most macros
+/// fail with only one error.
+#[derive(Default)]
+enum Errors {
+    #[default]
+    None,
+    Message(ErrorMessage),
+    One(Box<dyn ToTokensError>),
+    Many(Vec<Box<dyn ToTokensError>>),
+}
+
+impl Errors {
+    /// Pushes `error`, storing it inline if it is the first error and an
+    /// [`ErrorMessage`].
+    fn push_error<T: ToTokensError + 'static>(&mut self, error: T) {
+        let mut error = Some(error);
+        if let (Errors::None, Some(message)) = (
+            &*self,
+            (&mut error as &mut dyn Any).downcast_mut::<Option<ErrorMessage>>(),
+        ) {
+            *self = Errors::Message(message.take().expect("error is set"));
+            return;
+        }
+        self.push(Box::new(error.expect("error is set")));
+    }
+
+    fn push(&mut self, error: Box<dyn ToTokensError>) {
+        *self = match mem::take(self) {
+            Errors::None => Errors::One(error),
+            Errors::Message(first) => Errors::Many(vec![Box::new(first), error]),
+            Errors::One(first) => Errors::Many(vec![first, error]),
+            Errors::Many(mut errors) => {
+                errors.push(error);
+                Errors::Many(errors)
+            }
+        };
+    }
+
+    fn iter(&self) -> impl Iterator<Item = &dyn ToTokensError> {
+        let (message, boxed): (Option<&dyn ToTokensError>, &[Box<dyn ToTokensError>]) = match self
+        {
+            Errors::None => (None, &[]),
+            Errors::Message(message) => (Some(message), &[]),
+            Errors::One(error) => (None, std::slice::from_ref(error)),
+            Errors::Many(errors) => (None, errors),
+        };
+        message
+            .into_iter()
+            .chain(boxed.iter().map(|error| &**error))
+    }
+
+    fn get_mut(&mut self, index: usize) -> Option<&mut (dyn ToTokensError + 'static)> {
+        match self {
+            Errors::Message(message) if index == 0 => Some(message),
+            Errors::One(error) if index == 0 => Some(&mut **error),
+            Errors::Many(errors) => errors.get_mut(index).map(|error| &mut **error),
+            _ => None,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Errors::None => 0,
+            Errors::Message(_) | Errors::One(_) => 1,
+            Errors::Many(errors) => errors.len(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Debug for Errors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl Extend<Box<dyn ToTokensError>> for Errors {
+    fn extend<T: IntoIterator<Item = Box<dyn ToTokensError>>>(&mut self, iter: T) {
+        for error in iter {
+            self.push(error);
+        }
+    }
+}
+
+impl FromIterator<Box<dyn ToTokensError>> for Errors {
+    fn from_iter<T: IntoIterator<Item = Box<dyn ToTokensError>>>(iter: T) -> Self {
+        let mut errors = Errors::None;
+        errors.extend(iter);
+        errors
+    }
+}
 #[cfg(feature = "syn1")]
 impl From<Syn1Error> for Error {
     fn from(error: Syn1Error) -> Self {
         Self::from(error)
     }
 }
-#[cfg(feature = "syn2")]
+#[cfg(feature = "syn2-error")]
 impl From<Syn2Error> for Error {
     fn from(error: Syn2Error) -> Self {
         Self::from(error)
@@ -47,6 +152,24 @@ impl From<DarlingError> for Error {
         Self::from(error)
     }
 }
+#[cfg(feature = "darling_0_21")]
+impl From<Darling021Error> for Error {
+    fn from(error: Darling021Error) -> Self {
+        Self::from(error)
+    }
+}
+#[cfg(feature = "proc-macro-error2")]
+impl From<Diagnostic> for Error {
+    fn from(error: Diagnostic) -> Self {
+        Self::from(error)
+    }
+}
+#[cfg(feature = "litrs")]
+impl From<LitrsInvalidToken> for Error {
+    fn from(error: LitrsInvalidToken) -> Self {
+        Self::from(error)
+    }
+}
 impl From<ErrorMessage> for Error {
     fn from(error: ErrorMessage) -> Self {
         Self::from(error)
@@ -54,7 +177,7 @@ impl From<ErrorMessage> for Error {
 }
 impl From<SilentError> for Error {
     fn from(_: SilentError) -> Self {
-        Self(Vec::new())
+        Self(Errors::None)
     }
 }
 
@@ -76,7 +199,9 @@ impl Error {
     /// Mimics [`From<impl ToTokensError> for Error`](From) implementation to
     /// not conflict std's `From<T> for T`
     pub fn from(error: impl ToTokensError + 'static) -> Self {
-        Self(vec![Box::new(error)])
+        let mut errors = Errors::None;
+        errors.push_error(error);
+        Self(errors)
     }
 
     /// Pushes an additional `Error`
@@ -92,16 +217,166 @@ impl Error {
     /// #     { ::core::compile_error!{"Hello Rust!"} ::core::compile_error!{"Hello 🦀!"} });
     /// ```
     pub fn push(&mut self, error: impl ToTokensError + 'static) {
-        self.0.push(Box::new(error));
+        self.0.push_error(error);
+    }
+}
+
+#[cfg(feature = "syn2")]
+impl Error {
+    /// Converts a [`syn::Error`](Syn2Error) into an `Error`, keeping each of
+    /// its combined messages as a separate error.
+    ///
+    /// ```
+    /// use manyhow::Error;
+    /// use proc_macro2::Span;
+    /// # use syn2 as syn;
+    ///
+    /// let mut error = syn::Error::new(Span::call_site(), "first");
+    /// error.combine(syn::Error::new(Span::call_site(), "second"));
+    /// let error = Error::from_syn_split(error);
+    /// let messages: Vec<_> = error
+    ///     .into_syn()
+    ///     .unwrap()
+    ///     .into_iter()
+    ///     .map(|e| e.to_string())
+    ///     .collect();
+    /// assert_eq!(messages, ["first", "second"]);
+    /// ```
+    pub fn from_syn_split(error: Syn2Error) -> Self {
+        Self(
+            error
+                .into_iter()
+                .map(|error| Box::new(error) as Box<dyn ToTokensError>)
+                .collect(),
+        )
+    }
+
+    /// Converts into a [`syn::Error`](Syn2Error), combining the messages of
+    /// every error, see [`ToTokensError::to_syn`].
+    ///
+    /// Returns `None` if no error has a message, e.g., for a [`SilentError`].
+    /// Other tokens, like fallbacks of [`ErrorMessage`]s, are dropped.
+    #[must_use]
+    pub fn into_syn(self) -> Option<Syn2Error> {
+        self.to_syn()
+    }
+}
+
+/// Combines `errors` into one [`syn::Error`](Syn2Error), `None` if empty.
+#[cfg(feature = "syn2")]
+fn combine_syn(errors: impl IntoIterator<Item = Syn2Error>) -> Option<Syn2Error> {
+    errors.into_iter().reduce(|mut error, other| {
+        error.combine(other);
+        error
+    })
+}
+
+/// Finds all `compile_error!` invocations in `tokens`, returning the tokens of
+/// each invocation together with its message.
+#[cfg(feature = "syn2")]
+pub(crate) fn compile_errors(tokens: TokenStream) -> Vec<(TokenStream, String)> {
+    split_compile_errors(tokens).1
+}
+
+/// Moves all `compile_error!` invocations in `tokens` to the end, sorted by
+/// their location and message, see [Debugging](crate#debugging).
+///
+/// Locations are only available inside of proc macros, otherwise the errors
+/// are sorted by message.
+#[cfg(feature = "syn2")]
+pub(crate) fn sort_compile_errors(tokens: TokenStream) -> TokenStream {
+    let (mut rest, errors) = split_compile_errors(tokens);
+    let mut errors: Vec<_> = errors
+        .into_iter()
+        .map(|(invocation, msg)| {
+            let location = invocation
+                .clone()
+                .into_iter()
+                .next()
+                .and_then(|tt| span_location(tt.span()));
+            ((location, msg), invocation)
+        })
+        .collect();
+    // stable, so errors at the same location with the same message keep their
+    // order
+    errors.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (_, invocation) in errors {
+        let braced = matches!(
+            invocation.clone().into_iter().last(),
+            Some(TokenTree::Group(group)) if group.delimiter() == proc_macro2::Delimiter::Brace
+        );
+        rest.extend(invocation);
+        if !braced {
+            rest.extend(quote::quote!(;));
+        }
+    }
+    rest
+}
+
+/// Like [`compile_errors()`], but also finding the invocations nested in
+/// groups.
+#[cfg(feature = "syn2")]
+fn nested_compile_errors(tokens: TokenStream) -> Vec<(TokenStream, String)> {
+    let (rest, mut errors) = split_compile_errors(tokens);
+    for tt in rest {
+        if let TokenTree::Group(group) = tt {
+            errors.extend(nested_compile_errors(group.stream()));
+        }
     }
+    errors
+}
+
+/// Like [`compile_errors()`], but also returning the remaining tokens.
+#[cfg(feature = "syn2")]
+pub(crate) fn split_compile_errors(
+    tokens: TokenStream,
+) -> (TokenStream, Vec<(TokenStream, String)>) {
+    let is_colon = |tt: &TokenTree| matches!(tt, TokenTree::Punct(p) if p.as_char() == ':');
+    let tokens: Vec<_> = tokens.into_iter().collect();
+    let mut errors = Vec::new();
+    let mut rest = TokenStream::new();
+    let mut end = 0;
+    for (i, window) in tokens.windows(3).enumerate() {
+        let [
+            TokenTree::Ident(ident),
+            TokenTree::Punct(bang),
+            TokenTree::Group(group),
+        ] = window
+        else {
+            continue;
+        };
+        if ident != "compile_error" || bang.as_char() != '!' {
+            continue;
+        }
+        // include the path, e.g., `::core::`
+        let mut start = i;
+        while start >= 2 && is_colon(&tokens[start - 1]) && is_colon(&tokens[start - 2]) {
+            start -= 2;
+            if start >= 1 && matches!(tokens[start - 1], TokenTree::Ident(_)) {
+                start -= 1;
+            } else {
+                break;
+            }
+        }
+        let msg = syn2::parse2::<syn2::LitStr>(group.stream())
+            .map_or_else(|_| group.stream().to_string(), |msg| msg.value());
+        errors.push((tokens[start..i + 3].iter().cloned().collect(), msg));
+        rest.extend(tokens[end.min(start)..start].iter().cloned());
+        end = i + 3;
+        // `compile_error!(..);`
+        if matches!(tokens.get(end), Some(TokenTree::Punct(p)) if p.as_char() == ';') {
+            end += 1;
+        }
+    }
+    rest.extend(tokens[end.min(tokens.len())..].iter().cloned());
+    (rest, errors)
 }
 
 impl<I: ToTokensError + 'static> Extend<I> for Error {
     fn extend<T: IntoIterator<Item = I>>(&mut self, iter: T) {
-        self.0.extend(
-            iter.into_iter()
-                .map(|i| Box::new(i) as Box<dyn ToTokensError>),
-        );
+        for error in iter {
+            self.0.push_error(error);
+        }
     }
 }
 
@@ -112,26 +387,115 @@ impl<I: ToTokensError + 'static> Extend<I> for Error {
 ///
 /// Implements `ToTokensError` and can therefore be used with
 /// [`MacroOutput`]s.
+///
+/// The error is emitted with a single joined span when
+/// [`span_joined()`](SpanRanged::span_joined) succeeds, e.g., on nightly.
 #[derive(Debug)]
 #[must_use]
 pub struct ErrorMessage {
     span: Range<Span>,
-    msg: String,
-    attachments: Vec<(&'static str, String)>,
+    /// Borrowed for static messages, e.g., of `bail!("literal")`, to not
+    /// allocate.
+    msg: Text,
+    /// Label, message and span, attachments with a span are emitted as
+    /// separate errors.
+    attachments: Vec<(Text, AttachmentMsg, Option<Range<Span>>)>,
+    fallback: TokenStream,
+    hygiene: Hygiene,
+    /// Boxed, as it is rarely used, to keep `ErrorMessage` small.
+    structured: Option<Box<Structured>>,
+}
+
+/// Text of messages, not allocated if static.
+type Text = Cow<'static, str>;
+
+/// Message of an attachment, either text or rendered on first use, e.g., of
+/// [`ErrorMessage::attachment_with`].
+enum AttachmentMsg {
+    Text(Text),
+    Lazy {
+        render: Box<dyn Fn() -> String>,
+        rendered: OnceCell<String>,
+    },
+}
+
+impl AttachmentMsg {
+    fn lazy<D: Display>(render: impl Fn() -> D + 'static) -> Self {
+        AttachmentMsg::Lazy {
+            render: Box::new(move || render().to_string()),
+            rendered: OnceCell::new(),
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            AttachmentMsg::Text(text) => text,
+            AttachmentMsg::Lazy { render, rendered } => rendered.get_or_init(render),
+        }
+    }
+}
+
+impl Debug for AttachmentMsg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttachmentMsg::Text(text) => Debug::fmt(text, f),
+            AttachmentMsg::Lazy { rendered, .. } => match rendered.get() {
+                Some(rendered) => Debug::fmt(rendered, f),
+                None => f.write_str("<lazy>"),
+            },
+        }
+    }
+}
+
+impl Display for AttachmentMsg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Information only part of the [structured
+/// diagnostics](crate#structured-diagnostics).
+#[derive(Debug, Default)]
+struct Structured {
+    code: Option<String>,
+    /// Index of the `help` attachment, message and replacement.
+    suggestions: Vec<(usize, String, String)>,
+}
+
+/// Hygiene of the `compile_error!` invocation emitted for an
+/// [`ErrorMessage`], set with [`ErrorMessage::with_hygiene`].
+///
+/// The location of the tokens is always the error's span, only their name
+/// resolution behavior is affected.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Hygiene {
+    /// Inherit the hygiene of the error's span.
+    #[default]
+    Inherit,
+    /// Resolve at [`Span::call_site()`].
+    CallSite,
+    /// Resolve at [`Span::mixed_site()`].
+    MixedSite,
 }
 impl Display for ErrorMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.msg.trim_end())?;
-        if !self.attachments.is_empty() {
+        let mut attachments = self
+            .attachments
+            .iter()
+            .filter(|(_, _, span)| span.is_none())
+            .peekable();
+        if attachments.peek().is_some() {
             write!(f, "\n\n")?;
         }
-        for (label, attachment) in &self.attachments {
-            let mut attachment = attachment.lines();
-            writeln!(
-                f,
-                "  = {label}: {}",
-                attachment.next().expect("should return at least one line")
-            )?;
+        for (label, attachment, _) in attachments {
+            let mut attachment = attachment.as_str().lines();
+            match attachment.next() {
+                Some(line) => writeln!(f, "  = {label}: {line}")?,
+                // empty attachments have no lines
+                None => writeln!(f, "  = {label}:")?,
+            }
             for line in attachment {
                 // `labels` should always be one char per cell
                 writeln!(f, "    {1:2$}  {}", line, "", label.len())?;
@@ -142,25 +506,49 @@ impl Display for ErrorMessage {
 }
 impl ToTokensError for ErrorMessage {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        let msg = self.to_string();
-        let msg = quote_spanned!(self.span.end => {#msg});
-        quote_spanned! {self.span.start =>
-            ::core::compile_error! #msg
+        #[cfg(feature = "syn2")]
+        if crate::diagnostics::is_recording() {
+            self.record_diagnostic();
+        }
+        // extending moves the tokens, `to_tokens()` would clone them
+        tokens.extend(self.compile_error());
+        for (label, msg, span) in &self.attachments {
+            if let Some(span) = span {
+                tokens.extend(self.compile_error_at(span, &format!("{label}: {msg}")));
+            }
         }
-        .to_tokens(tokens);
+        self.fallback.to_tokens(tokens);
+    }
+
+    #[cfg(feature = "syn2")]
+    fn to_syn(&self) -> Option<Syn2Error> {
+        Some(self.syn2_error())
+    }
+
+    fn __as_error_message_mut(&mut self) -> Option<&mut ErrorMessage> {
+        Some(self)
     }
 }
 
 #[cfg(feature = "syn1")]
 impl From<ErrorMessage> for Syn1Error {
     fn from(value: ErrorMessage) -> Self {
-        Self::new_spanned(value.to_token_stream(), value)
+        let mut error = Self::new_spanned(value.compile_error(), &value);
+        for (label, msg, span) in &value.attachments {
+            if let Some(span) = span {
+                error.combine(Self::new_spanned(
+                    value.compile_error_at(span, ""),
+                    format!("{label}: {msg}"),
+                ));
+            }
+        }
+        error
     }
 }
-#[cfg(feature = "syn2")]
+#[cfg(feature = "syn2-error")]
 impl From<ErrorMessage> for Syn2Error {
     fn from(value: ErrorMessage) -> Self {
-        Self::new_spanned(value.to_token_stream(), value)
+        value.syn2_error()
     }
 }
 
@@ -183,23 +571,90 @@ impl ErrorMessage {
     /// If your type implements [`ToTokens`] use [`ErrorMessage::spanned`]
     /// instead.
     pub fn new(span: impl SpanRanged, msg: impl Display) -> Self {
-        Self {
-            span: span.span_range(),
-            msg: msg.to_string(),
-            attachments: Vec::new(),
-        }
+        Self::with_msg(span.span_range(), msg.to_string().into())
     }
 
     /// Creates an error message pointing to the complete token stream `tokens`
     /// expands to
     pub fn spanned(tokens: impl ToTokens, msg: impl Display) -> Self {
+        Self::with_msg(to_tokens_span_range(tokens), msg.to_string().into())
+    }
+
+    fn with_msg(span: Range<Span>, msg: Text) -> Self {
         Self {
-            span: to_tokens_span_range(tokens),
-            msg: msg.to_string(),
+            span,
+            msg,
             attachments: Vec::new(),
+            fallback: TokenStream::new(),
+            hygiene: Hygiene::default(),
+            structured: None,
         }
     }
 
+    /// Used by [`error_message!`](crate::error_message!), not allocating the
+    /// message if it has no formatting arguments.
+    #[doc(hidden)]
+    pub fn __new_args(span: impl SpanRanged, msg: Arguments) -> Self {
+        Self::new_text(span, crate::__private::args_text(msg))
+    }
+
+    #[doc(hidden)]
+    pub fn __call_site_args(msg: Arguments) -> Self {
+        Self::call_site_text(crate::__private::args_text(msg))
+    }
+
+    /// Creates a new error message at the specified span, like
+    /// [`ErrorMessage::new`], but taking the message as text, which is not
+    /// allocated if it is `&'static str`.
+    ///
+    /// ```
+    /// use manyhow::ErrorMessage;
+    /// use proc_macro2::Span;
+    ///
+    /// let error = ErrorMessage::new_text(Span::call_site(), "static message")
+    ///     .attachment_text("help", format!("owned {}", "help"));
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "static message\n\n  = help: owned help\n"
+    /// );
+    /// ```
+    pub fn new_text(span: impl SpanRanged, msg: impl Into<Cow<'static, str>>) -> Self {
+        Self::with_msg(span.span_range(), msg.into())
+    }
+
+    /// Creates an error message pointing to the complete token stream `tokens`
+    /// expands to, like [`ErrorMessage::spanned`], but taking the message as
+    /// text.
+    pub fn spanned_text(tokens: impl ToTokens, msg: impl Into<Cow<'static, str>>) -> Self {
+        Self::with_msg(to_tokens_span_range(tokens), msg.into())
+    }
+
+    /// Creates a new error message at [`Span::call_site`], like
+    /// [`ErrorMessage::call_site`], but taking the message as text.
+    pub fn call_site_text(msg: impl Into<Cow<'static, str>>) -> Self {
+        Self::new_text(Span::call_site(), msg)
+    }
+
+    /// Creates an error message from a [`litrs::ParseError`]
+    ///
+    /// As `litrs` only knows the literal's source text, the `span` of the
+    /// literal needs to be supplied.
+    ///
+    /// ```
+    /// # use proc_macro2::Span;
+    /// use manyhow::ErrorMessage;
+    ///
+    /// let error = litrs::StringLit::parse(r#""\q""#).unwrap_err();
+    /// assert_eq!(
+    ///     ErrorMessage::from_litrs(Span::call_site(), &error).to_string(),
+    ///     "unknown escape (at 1..3)"
+    /// );
+    /// ```
+    #[cfg(feature = "litrs")]
+    pub fn from_litrs(span: impl SpanRanged, error: &LitrsParseError) -> Self {
+        Self::new(span, error)
+    }
+
     /// Creates a new error message at [`Span::call_site`] prefer
     /// [`ErrorMessage::new`] or [`ErrorMessage::spanned`] with the correct span
     /// for a more helpful output.
@@ -209,8 +664,137 @@ impl ErrorMessage {
 
     /// Attaches an additional message to `self` reusing the same
     /// span, and the specified `label`.
-    pub fn attachment(mut self, label: &'static str, msg: impl Display) -> Self {
-        self.attachments.push((label, msg.to_string()));
+    pub fn attachment(self, label: &'static str, msg: impl Display) -> Self {
+        self.attachment_text(label, msg.to_string())
+    }
+
+    /// Attaches multiple messages with their labels, e.g., hints collected at
+    /// runtime.
+    ///
+    /// ```
+    /// # use manyhow::ErrorMessage;
+    /// let notes = ["first", "second"];
+    /// let error = ErrorMessage::call_site("error").attachments(notes.map(|note| ("note", note)));
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "error\n\n  = note: first\n  = note: second\n"
+    /// );
+    /// ```
+    pub fn attachments(
+        self,
+        attachments: impl IntoIterator<Item = (impl Into<Cow<'static, str>>, impl Display)>,
+    ) -> Self {
+        attachments.into_iter().fold(self, |error, (label, msg)| {
+            error.attachment_text(label, msg.to_string())
+        })
+    }
+
+    /// Attaches an additional message like [`attachment()`](Self::attachment),
+    /// but taking the `label` and message as text, which is not allocated if
+    /// it is `&'static str`.
+    pub fn attachment_text(
+        mut self,
+        label: impl Into<Cow<'static, str>>,
+        msg: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.attachments
+            .push((label.into(), AttachmentMsg::Text(msg.into()), None));
+        self
+    }
+
+    /// Attaches an additional message like [`attachment()`](Self::attachment),
+    /// but only rendering it when the error is rendered, e.g., for hints that
+    /// are expensive to format.
+    ///
+    /// The closure is called at most once.
+    ///
+    /// ```
+    /// # use manyhow::ErrorMessage;
+    /// # let candidates = ["a", "b"];
+    /// let error = ErrorMessage::call_site("unknown option")
+    ///     .attachment_with("help", move || format!("expected one of {}", candidates.join(", ")));
+    /// assert_eq!(
+    ///     error.to_string(),
+    ///     "unknown option\n\n  = help: expected one of a, b\n"
+    /// );
+    /// ```
+    pub fn attachment_with<D: Display>(
+        mut self,
+        label: &'static str,
+        msg: impl Fn() -> D + 'static,
+    ) -> Self {
+        self.attachments
+            .push((label.into(), AttachmentMsg::lazy(msg), None));
+        self
+    }
+
+    /// Attaches an additional message with the specified `label` at a
+    /// different `span`, e.g., pointing to a previous definition.
+    ///
+    /// The message is emitted as a separate `compile_error!` at `span`,
+    /// prefixed with `label`, and is not part of this message's [`Display`]
+    /// output.
+    ///
+    /// ```
+    /// use manyhow::{ErrorMessage, ToTokensError};
+    /// use proc_macro2::Span;
+    /// use quote::quote;
+    ///
+    /// let error = ErrorMessage::new(Span::call_site(), "duplicate key").spanned_attachment(
+    ///     Span::call_site(),
+    ///     "note",
+    ///     "first defined here",
+    /// );
+    /// assert_eq!(
+    ///     error.to_token_stream().to_string(),
+    ///     quote! {
+    ///         ::core::compile_error! { "duplicate key" }
+    ///         ::core::compile_error! { "note: first defined here" }
+    ///     }
+    ///     .to_string()
+    /// );
+    /// ```
+    pub fn spanned_attachment(
+        self,
+        span: impl SpanRanged,
+        label: &'static str,
+        msg: impl Display,
+    ) -> Self {
+        self.spanned_attachment_text(span, label, msg.to_string())
+    }
+
+    /// Attaches an additional message at a different `span` like
+    /// [`spanned_attachment()`](Self::spanned_attachment), but taking the
+    /// `label` and message as text.
+    pub fn spanned_attachment_text(
+        mut self,
+        span: impl SpanRanged,
+        label: impl Into<Cow<'static, str>>,
+        msg: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        self.attachments.push((
+            label.into(),
+            AttachmentMsg::Text(msg.into()),
+            Some(span.span_range()),
+        ));
+        self
+    }
+
+    /// Attaches an additional message at a different `span` like
+    /// [`spanned_attachment()`](Self::spanned_attachment), but only rendering
+    /// it when the error is rendered, like
+    /// [`attachment_with()`](Self::attachment_with).
+    pub fn spanned_attachment_with<D: Display>(
+        mut self,
+        span: impl SpanRanged,
+        label: &'static str,
+        msg: impl Fn() -> D + 'static,
+    ) -> Self {
+        self.attachments.push((
+            label.into(),
+            AttachmentMsg::lazy(msg),
+            Some(span.span_range()),
+        ));
         self
     }
 
@@ -219,20 +803,283 @@ impl ErrorMessage {
         self.attachment("error", msg)
     }
 
+    /// Attaches a new lazily rendered `error` message to `self`, see
+    /// [`attachment_with()`](Self::attachment_with)
+    pub fn error_with<D: Display>(self, msg: impl Fn() -> D + 'static) -> Self {
+        self.attachment_with("error", msg)
+    }
+
     /// Attaches a new `warning` message to `self` reusing the same span
     pub fn warning(self, msg: impl Display) -> Self {
         self.attachment("warning", msg)
     }
 
+    /// Attaches a new lazily rendered `warning` message to `self`, see
+    /// [`attachment_with()`](Self::attachment_with)
+    pub fn warning_with<D: Display>(self, msg: impl Fn() -> D + 'static) -> Self {
+        self.attachment_with("warning", msg)
+    }
+
     /// Attaches a new `note` message to `self` reusing the same span
     pub fn note(self, msg: impl Display) -> Self {
         self.attachment("note", msg)
     }
 
+    /// Attaches a new lazily rendered `note` message to `self`, see
+    /// [`attachment_with()`](Self::attachment_with)
+    pub fn note_with<D: Display>(self, msg: impl Fn() -> D + 'static) -> Self {
+        self.attachment_with("note", msg)
+    }
+
+    /// Attaches the source location of the error as a `note`, i.e., `-->
+    /// file:line:column`, e.g., for errors in code from other files included
+    /// with `include!`-like macros.
+    ///
+    /// The location is only available inside of proc macros, otherwise `self`
+    /// is returned unchanged.
+    ///
+    /// ```
+    /// use manyhow::ErrorMessage;
+    ///
+    /// let error = ErrorMessage::call_site("unknown key").at_location();
+    /// // Not called inside of a proc macro
+    /// assert_eq!(error.to_string(), "unknown key");
+    /// ```
+    pub fn at_location(self) -> Self {
+        match source_location(self.span.start) {
+            Some(location) => self.note(format_args!("--> {location}")),
+            None => self,
+        }
+    }
+
     /// Attaches a new `help` message to `self` reusing the same span
     pub fn help(self, msg: impl Display) -> Self {
         self.attachment("help", msg)
     }
+
+    /// Attaches a new lazily rendered `help` message to `self`, see
+    /// [`attachment_with()`](Self::attachment_with)
+    pub fn help_with<D: Display>(self, msg: impl Fn() -> D + 'static) -> Self {
+        self.attachment_with("help", msg)
+    }
+
+    /// Attaches a `help` message at `span`, suggesting to replace it with
+    /// `replacement`.
+    ///
+    /// `proc_macro` does not expose machine-applicable suggestions, not even on
+    /// nightly, therefore the suggestion is emitted as a `help`
+    /// [spanned attachment](Self::spanned_attachment) and cannot be applied by
+    /// `cargo fix` or rust-analyzer. Tools can instead apply the replacement
+    /// from the [structured diagnostics](crate#structured-diagnostics) or the
+    /// [fix mode](crate#fix-mode).
+    ///
+    /// ```
+    /// use manyhow::{ErrorMessage, ToTokensError};
+    /// use proc_macro2::Span;
+    /// use quote::quote;
+    ///
+    /// let error = ErrorMessage::new(Span::call_site(), "unknown option `colour`").suggestion(
+    ///     Span::call_site(),
+    ///     "a similar option exists",
+    ///     "color",
+    /// );
+    /// assert_eq!(
+    ///     error.to_token_stream().to_string(),
+    ///     quote! {
+    ///         ::core::compile_error! { "unknown option `colour`" }
+    ///         ::core::compile_error! { "help: a similar option exists: `color`" }
+    ///     }
+    ///     .to_string()
+    /// );
+    /// ```
+    pub fn suggestion(
+        mut self,
+        span: impl SpanRanged,
+        msg: impl Display,
+        replacement: impl Display,
+    ) -> Self {
+        let (msg, replacement) = (msg.to_string(), replacement.to_string());
+        self.structured.get_or_insert_default().suggestions.push((
+            self.attachments.len(),
+            msg.clone(),
+            replacement.clone(),
+        ));
+        self.spanned_attachment(span, "help", format_args!("{msg}: `{replacement}`"))
+    }
+
+    /// Sets a `code` identifying the kind of error, e.g.,
+    /// `my_macro::unknown_option`.
+    ///
+    /// As `compile_error!` cannot carry codes, the code is only part of the
+    /// [structured diagnostics](crate#structured-diagnostics).
+    pub fn code(mut self, code: impl Display) -> Self {
+        self.structured.get_or_insert_default().code = Some(code.to_string());
+        self
+    }
+
+    /// Attaches fallback `tokens` to `self`, they are emitted together with
+    /// the error, i.e., only if this error ends up in the macro output.
+    ///
+    /// This allows e.g., validators to supply a stub for each failing item
+    /// instead of one [dummy](crate#dummy-mut-dummy) for the whole macro.
+    ///
+    /// ```
+    /// use manyhow::{ErrorMessage, ToTokensError};
+    /// use proc_macro2::Span;
+    /// use quote::quote;
+    ///
+    /// let error = ErrorMessage::new(Span::call_site(), "unsupported field").with_fallback(quote!(
+    ///     fn field(&self) -> u8 {
+    ///         ::core::todo!()
+    ///     }
+    /// ));
+    /// assert_eq!(
+    ///     error.to_token_stream().to_string(),
+    ///     quote! {
+    ///         ::core::compile_error! { "unsupported field" }
+    ///         fn field(&self) -> u8 { ::core::todo!() }
+    ///     }
+    ///     .to_string()
+    /// );
+    /// ```
+    pub fn with_fallback(mut self, tokens: impl ToTokens) -> Self {
+        tokens.to_tokens(&mut self.fallback);
+        self
+    }
+
+    /// Resolves the emitted `compile_error!` at `span`, keeping its location,
+    /// e.g., to avoid `::core` resolving in the hygiene context of a user's
+    /// `macro_rules!`. [more](SpanRanged::span_range_resolved_at)
+    ///
+    /// ```
+    /// use manyhow::ErrorMessage;
+    /// use proc_macro2::Span;
+    /// # let user_token = Span::mixed_site();
+    ///
+    /// let error = ErrorMessage::new(user_token, "error").resolved_at(Span::call_site());
+    /// ```
+    pub fn resolved_at(mut self, span: Span) -> Self {
+        self.span = self.span.span_range_resolved_at(span);
+        self
+    }
+
+    /// Sets the [`Hygiene`] of the emitted `compile_error!` invocation.
+    ///
+    /// ```
+    /// use manyhow::{ErrorMessage, Hygiene};
+    ///
+    /// let error = ErrorMessage::call_site("error").with_hygiene(Hygiene::MixedSite);
+    /// assert_eq!(error.hygiene(), Hygiene::MixedSite);
+    /// ```
+    pub fn with_hygiene(mut self, hygiene: Hygiene) -> Self {
+        self.hygiene = hygiene;
+        self
+    }
+
+    /// Returns the [`Hygiene`] of the emitted `compile_error!` invocation.
+    #[must_use]
+    pub fn hygiene(&self) -> Hygiene {
+        self.hygiene
+    }
+
+    /// Records `self` for the [structured
+    /// diagnostics](crate#structured-diagnostics).
+    #[cfg(feature = "syn2")]
+    fn record_diagnostic(&self) {
+        use crate::diagnostics::Diagnostic;
+
+        let mut messages = vec![self.to_string()];
+        let mut diagnostic = Diagnostic::error(self.msg.trim_end(), Some(self.span.clone()));
+        let structured = self.structured.as_deref();
+        diagnostic.code = structured.and_then(|structured| structured.code.clone());
+        for (i, (label, msg, span)) in self.attachments.iter().enumerate() {
+            if let Some(span) = span {
+                messages.push(format!("{label}: {msg}"));
+                if let Some((_, msg, replacement)) = structured.and_then(|structured| {
+                    structured
+                        .suggestions
+                        .iter()
+                        .find(|(index, ..)| *index == i)
+                }) {
+                    diagnostic
+                        .suggestions
+                        .push((msg.clone(), replacement.clone(), span.clone()));
+                    continue;
+                }
+            }
+            let mut child = Diagnostic::error(msg.as_str(), span.clone());
+            child.severity.clone_from(label);
+            diagnostic.children.push(child);
+        }
+        crate::diagnostics::record(messages, diagnostic);
+    }
+
+    /// Converts to a [`syn::Error`](Syn2Error), with the spanned attachments as
+    /// separate messages.
+    #[cfg(feature = "syn2-error")]
+    fn syn2_error(&self) -> Syn2Error {
+        let mut error = Syn2Error::new_spanned(self.compile_error(), self);
+        for (label, msg, span) in &self.attachments {
+            if let Some(span) = span {
+                error.combine(Syn2Error::new_spanned(
+                    self.compile_error_at(span, ""),
+                    format!("{label}: {msg}"),
+                ));
+            }
+        }
+        error
+    }
+
+    fn compile_error(&self) -> TokenStream {
+        if self.attachments.iter().any(|(_, _, span)| span.is_none()) {
+            self.compile_error_at(&self.span, &self.to_string())
+        } else {
+            // equivalent to `to_string()` without attachments
+            self.compile_error_at(&self.span, self.msg.trim_end())
+        }
+    }
+
+    fn compile_error_at(&self, span: &Range<Span>, msg: &str) -> TokenStream {
+        // Spanning the start and end separately only approximates the range
+        let span = span
+            .span_joined()
+            .map_or_else(|| span.clone(), |span| span..span);
+        let span = match self.hygiene {
+            Hygiene::Inherit => span,
+            Hygiene::CallSite => span.span_range_resolved_at(Span::call_site()),
+            Hygiene::MixedSite => span.span_range_resolved_at(Span::mixed_site()),
+        };
+        let msg = quote_spanned!(span.end => {#msg});
+        quote_spanned! {span.start =>
+            ::core::compile_error! #msg
+        }
+    }
+}
+
+/// Returns `file:line:column` of `span`, `None` outside of proc macros.
+fn source_location(span: Span) -> Option<String> {
+    span_location(span).map(|(file, line, column)| format!("{file}:{line}:{column}"))
+}
+
+/// Returns file, line and column of `span`, `None` outside of proc macros.
+fn span_location(span: Span) -> Option<(String, usize, usize)> {
+    let span = proc_macro_span(span)?;
+    Some((span.file(), span.line(), span.column()))
+}
+
+/// Converts `span` to a [`proc_macro::Span`], `None` outside of proc macros.
+pub(crate) fn proc_macro_span(span: Span) -> Option<proc_macro::Span> {
+    if !proc_macro::is_available() {
+        return None;
+    }
+    // `proc_macro2::Span` does not expose the location without `span-locations`
+    let tokens = TokenStream::from(TokenTree::from(Ident::new("location", span)));
+    Some(
+        proc_macro::TokenStream::from(tokens)
+            .into_iter()
+            .next()?
+            .span(),
+    )
 }
 
 /// Exposes [`ErrorMessage::attachment`] as a trait to allow
@@ -245,15 +1092,122 @@ pub trait Attachment: Sized {
 }
 
 impl Attachment for ErrorMessage {
-    fn attachment(mut self, label: &'static str, msg: impl Display) -> Self {
-        self.attachments.push((label, msg.to_string()));
+    fn attachment(self, label: &'static str, msg: impl Display) -> Self {
+        self.attachment_text(label, msg.to_string())
+    }
+}
+
+/// Exposes [`ErrorMessage::spanned_attachment`] as a trait to allow
+/// [`ResultExt::note_span`].
+pub trait SpannedAttachment: Sized {
+    /// Attaches an additional message to `self` at a different `span`, with
+    /// the specified `label`.
+    #[must_use]
+    fn spanned_attachment(
+        self,
+        span: impl SpanRanged,
+        label: &'static str,
+        msg: impl Display,
+    ) -> Self;
+}
+
+impl SpannedAttachment for ErrorMessage {
+    fn spanned_attachment(
+        self,
+        span: impl SpanRanged,
+        label: &'static str,
+        msg: impl Display,
+    ) -> Self {
+        self.spanned_attachment_text(span, label, msg.to_string())
+    }
+}
+
+#[cfg(feature = "syn1")]
+impl SpannedAttachment for Syn1Error {
+    fn spanned_attachment(
+        mut self,
+        span: impl SpanRanged,
+        label: &'static str,
+        msg: impl Display,
+    ) -> Self {
+        let tokens = span_range_tokens(&span.span_range());
+        self.combine(Self::new_spanned(tokens, format!("{label}: {msg}")));
         self
     }
 }
 
+#[cfg(feature = "syn2-error")]
+impl SpannedAttachment for Syn2Error {
+    fn spanned_attachment(
+        mut self,
+        span: impl SpanRanged,
+        label: &'static str,
+        msg: impl Display,
+    ) -> Self {
+        let tokens = span_range_tokens(&span.span_range());
+        self.combine(Self::new_spanned(tokens, format!("{label}: {msg}")));
+        self
+    }
+}
+
+/// Exposes moving an error to a different span as a trait to allow
+/// [`ResultExt::with_span`].
+pub trait WithSpan: Sized {
+    /// Replaces the span of `self` with `span`.
+    ///
+    /// Attachments with their own span, see
+    /// [`ErrorMessage::spanned_attachment`], keep it.
+    #[must_use]
+    fn with_span(self, span: impl SpanRanged) -> Self;
+}
+
+impl WithSpan for ErrorMessage {
+    fn with_span(mut self, span: impl SpanRanged) -> Self {
+        self.span = span.span_range();
+        self
+    }
+}
+
+/// Tokens spanning from `span.start` to `span.end`, to create `syn::Error`s
+/// with a span range.
+#[cfg(any(feature = "syn1", feature = "syn2-error"))]
+fn span_range_tokens(span: &Range<Span>) -> TokenStream {
+    let mut tokens = quote_spanned!(span.start => start);
+    tokens.extend(quote_spanned!(span.end => end));
+    tokens
+}
+
+#[cfg(feature = "syn1")]
+impl WithSpan for Syn1Error {
+    fn with_span(self, span: impl SpanRanged) -> Self {
+        let tokens = span_range_tokens(&span.span_range());
+        self.into_iter()
+            .map(|error| Self::new_spanned(&tokens, error))
+            .reduce(|mut error, other| {
+                error.combine(other);
+                error
+            })
+            .expect("syn::Error contains at least one message")
+    }
+}
+
+#[cfg(feature = "syn2-error")]
+impl WithSpan for Syn2Error {
+    fn with_span(self, span: impl SpanRanged) -> Self {
+        let tokens = span_range_tokens(&span.span_range());
+        self.into_iter()
+            .map(|error| Self::new_spanned(&tokens, error))
+            .reduce(|mut error, other| {
+                error.combine(other);
+                error
+            })
+            .expect("syn::Error contains at least one message")
+    }
+}
+
 /// Allows emitting errors without returning.
 #[derive(Default, Debug)]
-pub struct Emitter(Vec<Box<dyn ToTokensError>>);
+pub struct Emitter(Errors);
 
 impl<T: ToTokensError + 'static> AddAssign<T> for Emitter {
     fn add_assign(&mut self, rhs: T) {
@@ -266,11 +1220,11 @@ impl Emitter {
     /// be converted with [`Emitter::into_result()`].
     #[must_use]
     pub fn new() -> Self {
-        Emitter(Vec::new())
+        Emitter(Errors::None)
     }
 
     pub(crate) fn to_tokens(&self, tokens: &mut TokenStream) {
-        for error in &self.0 {
+        for error in self.0.iter() {
             error.to_tokens(tokens);
         }
     }
@@ -286,7 +1240,18 @@ impl Emitter {
     /// # proc_macro_utils::assert_tokens!(emitter.into_result().unwrap_err().into_token_stream(), { ::core::compile_error!{"Hello World!"} });
     /// ```
     pub fn emit(&mut self, error: impl ToTokensError + 'static) {
-        self.0.push(Box::new(error));
+        self.0.push_error(error);
+    }
+
+    /// Emits an [`ErrorMessage`], returning an [`EmittedMessage`] to add
+    /// attachments to it afterwards, see [`emit_handle!`](crate::emit_handle).
+    pub fn emit_message(&mut self, message: ErrorMessage) -> EmittedMessage<'_> {
+        self.0.push_error(message);
+        let index = self.0.len() - 1;
+        EmittedMessage {
+            emitter: self,
+            index,
+        }
     }
 
     /// Checks if any errors were emitted
@@ -297,7 +1262,7 @@ impl Emitter {
 
     /// Removes all emitted errors
     pub fn clear(&mut self) {
-        self.0.clear();
+        self.0 = Errors::None;
     }
 
     /// Returns emitted errors if not [`Self::is_empty`].
@@ -314,12 +1279,149 @@ impl Emitter {
     }
 }
 
+/// Handle to an [`ErrorMessage`] emitted with
+/// [`emit_handle!`](crate::emit_handle) or [`Emitter::emit_message`], allowing
+/// to add attachments after emitting it.
+///
+/// The message is pushed to the emitter immediately, the handle only refers to
+/// it.
+///
+/// ```
+/// # use manyhow::{emit_handle, Emitter};
+/// # let deprecated = true;
+/// let mut emitter = Emitter::new();
+/// let mut error = emit_handle!(emitter, "unknown attribute");
+/// if deprecated {
+///     error.note("it was removed in 2.0");
+/// }
+/// # #[cfg(feature = "syn2")]
+/// assert_eq!(emitter.messages(), [
+///     "unknown attribute\n\n  = note: it was removed in 2.0\n"
+/// ]);
+/// ```
+#[derive(Debug)]
+pub struct EmittedMessage<'a> {
+    emitter: &'a mut Emitter,
+    index: usize,
+}
+
+impl EmittedMessage<'_> {
+    fn map(&mut self, f: impl FnOnce(ErrorMessage) -> ErrorMessage) -> &mut Self {
+        let message = self
+            .emitter
+            .0
+            .get_mut(self.index)
+            .and_then(ToTokensError::__as_error_message_mut)
+            .expect("emitted message should be an `ErrorMessage`");
+        *message = f(mem::replace(message, ErrorMessage::call_site_text("")));
+        self
+    }
+
+    /// Attaches an additional message to the emitted message, see
+    /// [`ErrorMessage::attachment`].
+    pub fn attachment(&mut self, label: &'static str, msg: impl Display) -> &mut Self {
+        self.map(|message| message.attachment(label, msg))
+    }
+
+    /// Attaches a new `error` message to the emitted message.
+    pub fn error(&mut self, msg: impl Display) -> &mut Self {
+        self.attachment("error", msg)
+    }
+
+    /// Attaches a new `warning` message to the emitted message.
+    pub fn warning(&mut self, msg: impl Display) -> &mut Self {
+        self.attachment("warning", msg)
+    }
+
+    /// Attaches a new `note` message to the emitted message.
+    pub fn note(&mut self, msg: impl Display) -> &mut Self {
+        self.attachment("note", msg)
+    }
+
+    /// Attaches a new `help` message to the emitted message.
+    pub fn help(&mut self, msg: impl Display) -> &mut Self {
+        self.attachment("help", msg)
+    }
+}
+
+/// Assertions for unit tests of functions taking an `&mut Emitter`.
+///
+/// ```
+/// use manyhow::{emit, Emitter};
+///
+/// fn validate(name: &str, emitter: &mut Emitter) {
+///     if name.is_empty() {
+///         emit!(emitter, "name must not be empty");
+///     }
+///     if name.starts_with('_') {
+///         emit!(emitter, "name must not start with `_`"; help = "remove the `_`");
+///     }
+/// }
+///
+/// let mut emitter = Emitter::new();
+/// validate("_name", &mut emitter);
+/// emitter
+///     .assert_error_count(1)
+///     .assert_contains("must not start with");
+/// assert_eq!(emitter.messages(), [
+///     "name must not start with `_`\n\n  = help: remove the `_`\n"
+/// ]);
+/// ```
+#[cfg(feature = "syn2")]
+impl Emitter {
+    /// Returns the messages of all emitted errors, as rendered by the
+    /// compiler.
+    ///
+    /// Attachments like [`help`](ErrorMessage::help) are part of the message,
+    /// [spanned attachments](ErrorMessage::spanned_attachment) are separate
+    /// messages.
+    #[must_use]
+    pub fn messages(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .filter_map(ToTokensError::to_syn)
+            .flatten()
+            .map(|error| error.to_string())
+            .collect()
+    }
+
+    /// Asserts that exactly `count` errors were emitted.
+    ///
+    /// # Panics
+    /// Panics if the number of emitted errors differs.
+    #[track_caller]
+    #[allow(clippy::must_use_candidate)] // returns `self` only for chaining
+    pub fn assert_error_count(&self, count: usize) -> &Self {
+        assert!(
+            self.0.len() == count,
+            "expected {count} errors, but {} were emitted: {:?}",
+            self.0.len(),
+            self.messages()
+        );
+        self
+    }
+
+    /// Asserts that the message of an emitted error contains `substring`.
+    ///
+    /// # Panics
+    /// Panics if no message contains `substring`.
+    #[track_caller]
+    #[allow(clippy::must_use_candidate)] // returns `self` only for chaining
+    pub fn assert_contains(&self, substring: &str) -> &Self {
+        let messages = self.messages();
+        assert!(
+            messages.iter().any(|msg| msg.contains(substring)),
+            "no emitted error contains {substring:?}: {messages:?}"
+        );
+        self
+    }
+}
+
 impl<I: ToTokensError + 'static> Extend<I> for Emitter {
     fn extend<T: IntoIterator<Item = I>>(&mut self, iter: T) {
-        self.0.extend(
-            iter.into_iter()
-                .map(|i| Box::new(i) as Box<dyn ToTokensError>),
-        );
+        for error in iter {
+            self.0.push_error(error);
+        }
     }
 }
 
@@ -343,6 +1445,46 @@ pub trait ToTokensError: Debug {
     {
         self.to_token_stream()
     }
+    /// Converts into a [`syn::Error`](Syn2Error), combining all messages of
+    /// this error, or `None` if it has no message, e.g., a [`SilentError`].
+    ///
+    /// The default implementation converts every `compile_error!` emitted by
+    /// [`to_tokens`](Self::to_tokens), other tokens are dropped. The errors
+    /// of this crate and of `syn` are converted directly.
+    ///
+    /// ```
+    /// use manyhow::{error_message, ToTokensError};
+    /// use proc_macro2::TokenStream;
+    /// use quote::quote;
+    ///
+    /// #[derive(Debug)]
+    /// struct Nested;
+    ///
+    /// impl ToTokensError for Nested {
+    ///     fn to_tokens(&self, tokens: &mut TokenStream) {
+    ///         tokens.extend(quote!(const _: () = { ::core::compile_error!("nested"); };));
+    ///     }
+    /// }
+    ///
+    /// assert_eq!(Nested.to_syn().unwrap().to_string(), "nested");
+    /// assert_eq!(
+    ///     error_message!("message"; help = "help").to_syn().unwrap().to_string(),
+    ///     "message\n\n  = help: help\n"
+    /// );
+    /// ```
+    #[cfg(feature = "syn2")]
+    fn to_syn(&self) -> Option<Syn2Error> {
+        combine_syn(
+            nested_compile_errors(self.to_token_stream())
+                .into_iter()
+                .map(|(invocation, msg)| Syn2Error::new_spanned(invocation, msg)),
+        )
+    }
+    /// Used by [`EmittedMessage`] to access the emitted message.
+    #[doc(hidden)]
+    fn __as_error_message_mut(&mut self) -> Option<&mut ErrorMessage> {
+        None
+    }
 }
 
 /// Allows to call `.join(..)` on any `impl ToTokensError`
@@ -390,12 +1532,25 @@ impl ToTokensError for Syn1Error {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         self.to_compile_error().to_tokens(tokens);
     }
+
+    #[cfg(feature = "syn2")]
+    fn to_syn(&self) -> Option<Syn2Error> {
+        combine_syn(
+            self.into_iter()
+                .map(|error| Syn2Error::new_spanned(error.to_compile_error(), error)),
+        )
+    }
 }
-#[cfg(feature = "syn")]
+#[cfg(feature = "syn2-error")]
 impl ToTokensError for Syn2Error {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         self.to_compile_error().to_tokens(tokens);
     }
+
+    #[cfg(feature = "syn2")]
+    fn to_syn(&self) -> Option<Syn2Error> {
+        Some(self.clone())
+    }
 }
 #[cfg(feature = "darling")]
 impl ToTokensError for DarlingError {
@@ -403,15 +1558,43 @@ impl ToTokensError for DarlingError {
         self.clone().write_errors().to_tokens(tokens);
     }
 }
+#[cfg(feature = "darling_0_21")]
+impl ToTokensError for Darling021Error {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.clone().write_errors().to_tokens(tokens);
+    }
+}
+#[cfg(feature = "proc-macro-error2")]
+impl ToTokensError for Diagnostic {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        ToTokens::to_tokens(self, tokens);
+    }
+}
+#[cfg(feature = "litrs")]
+impl ToTokensError for LitrsInvalidToken {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.to_compile_error2().to_tokens(tokens);
+    }
+}
 impl ToTokensError for Error {
     fn to_tokens(&self, tokens: &mut TokenStream) {
-        for error in &self.0 {
+        for error in self.0.iter() {
             error.to_tokens(tokens);
         }
     }
+
+    #[cfg(feature = "syn2")]
+    fn to_syn(&self) -> Option<Syn2Error> {
+        combine_syn(self.0.iter().filter_map(ToTokensError::to_syn))
+    }
 }
 impl ToTokensError for SilentError {
     fn to_tokens(&self, _: &mut TokenStream) {}
+
+    #[cfg(feature = "syn2")]
+    fn to_syn(&self) -> Option<Syn2Error> {
+        None
+    }
 }
 
 /// Some utilities on [`Result<T, impl ToTokensError>`](ToTokensError)
@@ -427,6 +1610,43 @@ pub trait ResultExt<T, E>: Sized {
         self,
         error: impl FnOnce() -> C,
     ) -> Result<T, Error>;
+    /// If self is error, emits it and returns `dummy` instead, e.g., to
+    /// keep producing best-effort output when a sub-step fails
+    ///
+    /// ```
+    /// # use manyhow::{Emitter, ResultExt};
+    /// # use proc_macro2::TokenStream;
+    /// # use quote::quote;
+    /// # use syn2 as syn;
+    /// let mut emitter = Emitter::new();
+    /// let field = syn::parse2::<syn::Ident>(quote!(1))
+    ///     .map(|ident| quote!(fn #ident() {}))
+    ///     .ok_or_dummy(quote!(fn stub() {}), &mut emitter);
+    /// assert_eq!(field.to_string(), quote!(fn stub() {}).to_string());
+    /// assert!(!emitter.is_empty());
+    /// ```
+    fn ok_or_dummy(self, dummy: impl Into<T>, emitter: &mut Emitter) -> T
+    where
+        E: ToTokensError + 'static,
+        Self: Into<Result<T, E>>,
+    {
+        self.into().unwrap_or_else(|error| {
+            emitter.emit(error);
+            dummy.into()
+        })
+    }
+
+    /// If self is error, emits it and returns [`T::default()`](Default)
+    /// instead, like [`ok_or_dummy()`](Self::ok_or_dummy)
+    fn unwrap_or_emit(self, emitter: &mut Emitter) -> T
+    where
+        T: Default,
+        E: ToTokensError + 'static,
+        Self: Into<Result<T, E>>,
+    {
+        self.ok_or_dummy(T::default(), emitter)
+    }
+
     /// If self is error, extend error message
     ///
     /// Only works if `E` implements [`Attachment`] which is the case for
@@ -436,6 +1656,63 @@ pub trait ResultExt<T, E>: Sized {
     where
         E: Attachment;
 
+    /// Attaches a new `note` message to `self` at a different `span`, e.g.,
+    /// pointing to a relevant definition
+    ///
+    /// Only works if `E` implements [`SpannedAttachment`] which is the case for
+    /// [`ErrorMessage`] and `syn::Error`
+    ///
+    /// ```
+    /// # use manyhow::{ErrorMessage, ResultExt, ToTokensError};
+    /// # use proc_macro2::Span;
+    /// # use quote::quote;
+    /// # let (span, definition_span) = (Span::call_site(), Span::call_site());
+    /// let result: Result<(), _> = Err(ErrorMessage::new(span, "duplicate key"));
+    /// let error = result.note_span(definition_span, "first defined here").unwrap_err();
+    /// assert_eq!(
+    ///     error.to_token_stream().to_string(),
+    ///     quote! {
+    ///         ::core::compile_error! { "duplicate key" }
+    ///         ::core::compile_error! { "note: first defined here" }
+    ///     }
+    ///     .to_string()
+    /// );
+    /// ```
+    #[must_use]
+    fn note_span(self, span: impl SpanRanged, msg: impl Display) -> Self
+    where
+        E: SpannedAttachment,
+        Self: From<Result<T, E>> + Into<Result<T, E>>,
+    {
+        Self::from(
+            self.into()
+                .map_err(|e| e.spanned_attachment(span, "note", msg)),
+        )
+    }
+
+    /// If self is error, moves it to `span`, e.g., to report errors of parsing
+    /// a sub-stream at the original attribute
+    ///
+    /// Only works if `E` implements [`WithSpan`] which is the case for
+    /// [`ErrorMessage`] and `syn::Error`
+    ///
+    /// ```
+    /// # use manyhow::ResultExt;
+    /// # use proc_macro2::{Span, TokenStream};
+    /// # use syn2 as syn;
+    /// # let (attribute_span, sub_stream) = (Span::call_site(), TokenStream::new());
+    /// let result = syn::parse2::<syn::Ident>(sub_stream).with_span(attribute_span);
+    /// # assert!(result.is_err());
+    /// ```
+    #[must_use]
+    fn with_span(self, span: impl SpanRanged) -> Self
+    where
+        E: WithSpan,
+        Self: From<Result<T, E>> + Into<Result<T, E>>,
+    {
+        Self::from(self.into().map_err(|e| e.with_span(span)))
+    }
+
     /// Attaches a new `error` message to `self` reusing the same span
     #[must_use]
     fn error(self, msg: impl Display) -> Self
@@ -493,6 +1770,85 @@ impl<T, E: ToTokensError + 'static> ResultExt<T, E> for Result<T, E> {
     }
 }
 
+/// Some utilities on [`Option<T>`], turning `None` into spanned errors
+///
+/// ```
+/// # use manyhow::{ErrorMessage, OptionExt, ResultExt};
+/// # use proc_macro2::Ident;
+/// fn first_ident(input: &[Ident]) -> Result<&Ident, ErrorMessage> {
+///     input
+///         .first()
+///         .ok_or_call_site("expected an identifier")
+///         .help("pass the name of the item")
+/// }
+/// # assert_eq!(
+/// #     first_ident(&[]).unwrap_err().to_string(),
+/// #     "expected an identifier\n\n  = help: pass the name of the item\n"
+/// # );
+/// ```
+pub trait OptionExt<T>: Sized {
+    /// If self is `None`, returns an error at `span`
+    fn ok_or_error(self, span: impl SpanRanged, msg: impl Display) -> Result<T, ErrorMessage>;
+
+    /// If self is `None`, returns an error at [`Span::call_site`]
+    fn ok_or_call_site(self, msg: impl Display) -> Result<T, ErrorMessage> {
+        self.ok_or_error(Span::call_site(), msg)
+    }
+
+    /// If self is `None`, returns `error`
+    fn context(self, error: impl ToTokensError + 'static) -> Result<T, Error> {
+        self.context_with(|| error)
+    }
+
+    /// If self is `None`, returns an error, closure is only executed if the
+    /// `Option` is `None`
+    fn context_with<C: ToTokensError + 'static>(
+        self,
+        error: impl FnOnce() -> C,
+    ) -> Result<T, Error>;
+}
+
+impl<T> OptionExt<T> for Option<T> {
+    fn ok_or_error(self, span: impl SpanRanged, msg: impl Display) -> Result<T, ErrorMessage> {
+        self.ok_or_else(|| ErrorMessage::new(span, msg))
+    }
+
+    fn context_with<C: ToTokensError + 'static>(
+        self,
+        error: impl FnOnce() -> C,
+    ) -> Result<T, Error> {
+        self.ok_or_else(|| Error::from(error()))
+    }
+}
+
+/// Some utilities on iterators of [`Result<T, impl ToTokensError>`](ToTokensError)
+pub trait ResultIteratorExt<T, E>: Iterator<Item = Result<T, E>> + Sized {
+    /// Collects all values like [`Iterator::collect`], but instead of stopping
+    /// at the first error, every error is combined into a single [`Error`]
+    ///
+    /// ```
+    /// # use manyhow::{Error, ResultIteratorExt};
+    /// # use syn2 as syn;
+    /// let idents: Result<Vec<syn::Ident>, Error> =
+    ///     ["a", "1", "2"].map(syn::parse_str::<syn::Ident>).into_iter().collect_all();
+    /// let errors = idents.unwrap_err().into_syn().unwrap();
+    /// assert_eq!(errors.into_iter().count(), 2);
+    /// ```
+    fn collect_all<C: FromIterator<T>>(self) -> Result<C, Error>;
+}
+
+impl<T, E: ToTokensError + 'static, I: Iterator<Item = Result<T, E>>> ResultIteratorExt<T, E>
+    for I
+{
+    fn collect_all<C: FromIterator<T>>(self) -> Result<C, Error> {
+        let mut emitter = Emitter::new();
+        let values = self
+            .filter_map(|result| result.map_err(|error| emitter.emit(error)).ok())
+            .collect();
+        emitter.into_result().map(|()| values)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use proc_macro_utils::assert_tokens;
@@ -512,4 +1868,337 @@ mod test {
             }
         }}
     }
+
+    #[test]
+    fn static_message() {
+        let error = error_message!("static"; note = "attached");
+        assert!(matches!(error.msg, Cow::Borrowed("static")));
+        assert!(matches!(&error.attachments[..], [(
+            Cow::Borrowed("note"),
+            AttachmentMsg::Text(Cow::Borrowed("attached")),
+            None
+        )]));
+        assert_tokens!(error.into_token_stream(), {
+            ::core::compile_error! {"static\n\n  = note: attached\n"}
+        });
+
+        let value = 1;
+        let error = error_message!("value: {value}"; help = "help");
+        assert!(matches!(error.msg, Cow::Owned(_)));
+        assert_eq!(error.to_string(), "value: 1\n\n  = help: help\n");
+    }
+
+    #[test]
+    fn error_message_code() {
+        let code = |error: ErrorMessage| error.structured.and_then(|s| s.code);
+        let error = error_message!("test {}", 1; code = "E012"; note = "note");
+        assert_eq!(error.to_string(), "test 1\n\n  = note: note\n");
+        assert_eq!(code(error).as_deref(), Some("E012"));
+        let kind = "unknown_option";
+        let error = error_message!("test"; note = "note"; code = format_args!("my_macro::{kind}"),);
+        assert_eq!(code(error).as_deref(), Some("my_macro::unknown_option"));
+    }
+
+    #[test]
+    fn empty_lazy_attachment() {
+        let error = error_message!("a"; help = || String::new(); note = || "note");
+        assert_eq!(error.to_string(), "a\n\n  = help:\n  = note: note\n");
+        let error = ErrorMessage::call_site("a").note_with(String::new);
+        assert_eq!(error.to_string(), "a\n\n  = note:\n");
+    }
+
+    #[test]
+    fn errors() {
+        let mut error = Error::from(ErrorMessage::call_site("first"));
+        assert!(matches!(error.0, Errors::Message(_)));
+        error += ErrorMessage::call_site("second");
+        error.extend([ErrorMessage::call_site("third")]);
+        assert_eq!(error.0.len(), 3);
+        assert_tokens!(error.into_token_stream(), {
+            ::core::compile_error! {"first"}
+            ::core::compile_error! {"second"}
+            ::core::compile_error! {"third"}
+        });
+
+        let mut emitter = Emitter::new();
+        assert!(emitter.is_empty());
+        emitter.emit(SilentError);
+        assert!(matches!(emitter.0, Errors::One(_)));
+        assert!(!emitter.is_empty());
+        emitter.clear();
+        assert!(emitter.into_result().is_ok());
+
+        emitter.emit_message(ErrorMessage::call_site("first")).help("help");
+        assert!(matches!(emitter.0, Errors::Message(_)));
+        emitter.emit_message(ErrorMessage::call_site("second")).note("note");
+        assert_tokens!(emitter.into_result().unwrap_err().into_token_stream(), {
+            ::core::compile_error! {"first\n\n  = help: help\n"}
+            ::core::compile_error! {"second\n\n  = note: note\n"}
+        });
+    }
+
+    #[test]
+    fn option_ext() {
+        assert_eq!(Some(1).ok_or_call_site("unused").unwrap(), 1);
+        let error = None::<()>.ok_or_error(Span::call_site(), "missing");
+        assert_tokens!(error.unwrap_err().into_token_stream(), {
+            ::core::compile_error! {"missing"}
+        });
+        let error = None::<()>.context(SilentError);
+        assert!(error.unwrap_err().into_token_stream().is_empty());
+        assert!(Some(()).context_with(|| -> ErrorMessage { unreachable!() }).is_ok());
+    }
+
+    #[test]
+    fn ok_or_dummy() {
+        let mut emitter = Emitter::new();
+        assert_eq!(Ok::<_, ErrorMessage>(1).ok_or_dummy(0, &mut emitter), 1);
+        assert_eq!(Ok::<_, ErrorMessage>(1).unwrap_or_emit(&mut emitter), 1);
+        assert!(emitter.is_empty());
+        let dummy: String =
+            Err(ErrorMessage::call_site("first")).ok_or_dummy("dummy", &mut emitter);
+        assert_eq!(dummy, "dummy");
+        let value: u8 = Err(ErrorMessage::call_site("second")).unwrap_or_emit(&mut emitter);
+        assert_eq!(value, 0);
+        assert_tokens!(emitter.into_result().unwrap_err().into_token_stream(), {
+            ::core::compile_error! {"first"}
+            ::core::compile_error! {"second"}
+        });
+    }
+
+    #[test]
+    fn result_ext_downstream() {
+        /// Implementing only the required methods keeps compiling.
+        struct Wrapper(Result<u8, ErrorMessage>);
+        impl From<Result<u8, ErrorMessage>> for Wrapper {
+            fn from(value: Result<u8, ErrorMessage>) -> Self {
+                Self(value)
+            }
+        }
+        impl From<Wrapper> for Result<u8, ErrorMessage> {
+            fn from(value: Wrapper) -> Self {
+                value.0
+            }
+        }
+        impl ResultExt<u8, ErrorMessage> for Wrapper {
+            fn context_with<C: ToTokensError + 'static>(
+                self,
+                error: impl FnOnce() -> C,
+            ) -> Result<u8, Error> {
+                self.0.context_with(error)
+            }
+
+            fn attachment(self, label: &'static str, msg: impl Display) -> Self {
+                Self(self.0.attachment(label, msg))
+            }
+        }
+
+        let mut emitter = Emitter::new();
+        let error = Wrapper(Err(ErrorMessage::call_site("error")))
+            .with_span(Span::call_site())
+            .note_span(Span::call_site(), "note");
+        assert_eq!(error.unwrap_or_emit(&mut emitter), 0);
+        assert_tokens!(emitter.into_result().unwrap_err().into_token_stream(), {
+            ::core::compile_error! {"error"}
+            ::core::compile_error! {"note: note"}
+        });
+    }
+
+    #[test]
+    fn collect_all() {
+        let values: Vec<_> = [Ok::<_, ErrorMessage>(1), Ok(2)]
+            .into_iter()
+            .collect_all()
+            .unwrap();
+        assert_eq!(values, [1, 2]);
+        let values: Result<Vec<()>, _> = [
+            Err(ErrorMessage::call_site("first")),
+            Ok(()),
+            Err(ErrorMessage::call_site("second")),
+        ]
+        .into_iter()
+        .collect_all();
+        assert_tokens!(values.unwrap_err().into_token_stream(), {
+            ::core::compile_error! {"first"}
+            ::core::compile_error! {"second"}
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "proc-macro-error2")]
+    fn proc_macro_error2() {
+        use proc_macro_error2::{DiagnosticExt, Level, SpanRange};
+
+        let range = SpanRange::single_span(Span::call_site());
+        let mut emitter = Emitter::new();
+        emitter.emit(Diagnostic::spanned_range(
+            range,
+            Level::Error,
+            "error".into(),
+        ));
+        emitter.emit(ErrorMessage::new(range, "other error"));
+        assert_tokens! {emitter.into_result().unwrap_err().into_token_stream(), {
+            compile_error! { "error" }
+            ::core::compile_error! { "other error" }
+        }}
+    }
+
+    #[test]
+    #[cfg(feature = "syn2")]
+    #[should_panic = "no emitted error contains \"other\": [\"error\"]"]
+    fn emitter_assert_contains() {
+        let mut emitter = Emitter::new();
+        emitter.emit(ErrorMessage::call_site("error"));
+        emitter.assert_contains("err").assert_contains("other");
+    }
+
+    #[test]
+    #[cfg(feature = "syn2")]
+    fn sort_compile_errors() {
+        assert_tokens! {super::sort_compile_errors(quote::quote! {
+            ::core::compile_error!("b");
+            struct A;
+            ::core::compile_error! { "a" }
+            compile_error! { "b" }
+            struct B;
+        }), {
+            struct A;
+            struct B;
+            ::core::compile_error! { "a" }
+            ::core::compile_error!("b");
+            compile_error! { "b" }
+        }}
+    }
+
+    #[test]
+    #[cfg(all(feature = "darling", feature = "darling_0_21"))]
+    fn darling_majors() {
+        let mut accumulator = darling_core_0_21::error::Accumulator::default();
+        accumulator.push(Darling021Error::custom("0.21"));
+        let mut emitter = Emitter::new();
+        emitter.extend(accumulator.into_inner());
+        emitter.emit(DarlingError::custom("0.20"));
+        let messages: Vec<_> = emitter
+            .into_result()
+            .unwrap_err()
+            .into_syn()
+            .unwrap()
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect();
+        assert_eq!(messages, ["0.21", "0.20"]);
+    }
+
+    #[test]
+    #[cfg(feature = "syn2")]
+    fn syn_round_trip() {
+        let tokens: Vec<_> = "a b c d"
+            .parse::<TokenStream>()
+            .unwrap()
+            .into_iter()
+            .collect();
+        let mut error = Syn2Error::new_spanned(quote::quote!(#(#tokens)*), "first");
+        error.combine(Syn2Error::new(tokens[2].span(), "second"));
+        let mut error = Error::from_syn_split(error);
+        assert_eq!(error.0.len(), 2);
+        error.push(ErrorMessage::new(tokens[1].span(), "third").help("help"));
+        error.push(SilentError);
+
+        let error = error.into_syn().unwrap();
+        let errors: Vec<_> = error
+            .into_iter()
+            .map(|error| {
+                let range = error.span_range();
+                (
+                    error.to_string(),
+                    range.start.start().column,
+                    range.end.start().column,
+                )
+            })
+            .collect();
+        assert_eq!(errors, [
+            ("first".to_owned(), 0, 6),
+            ("second".to_owned(), 4, 4),
+            ("third\n\n  = help: help\n".to_owned(), 2, 2),
+        ]);
+
+        assert!(Error::from(SilentError).into_syn().is_none());
+    }
+
+    #[test]
+    #[cfg(feature = "syn2")]
+    fn into_syn_structural() {
+        #[derive(Debug)]
+        struct Nested;
+        impl ToTokensError for Nested {
+            fn to_tokens(&self, tokens: &mut TokenStream) {
+                tokens.extend(quote::quote!(mod m { ::core::compile_error!("nested"); }));
+            }
+        }
+
+        let mut error = Error::from(Nested);
+        // fallbacks are not converted, even if they contain `compile_error!`s
+        error.push(
+            ErrorMessage::call_site("message")
+                .with_fallback(quote::quote!(fn f() { compile_error!("fallback") })),
+        );
+        let messages: Vec<_> = error
+            .into_syn()
+            .unwrap()
+            .into_iter()
+            .map(|error| error.to_string())
+            .collect();
+        assert_eq!(messages, ["nested", "message"]);
+    }
+
+    #[test]
+    fn joined_span() {
+        let tokens: Vec<_> = "a b c"
+            .parse::<TokenStream>()
+            .unwrap()
+            .into_iter()
+            .collect();
+        let error = ErrorMessage::new(tokens[0].span()..tokens[2].span(), "error");
+        for tt in error.to_token_stream() {
+            assert_eq!(tt.span().start().column, 0);
+            assert_eq!(tt.span().end().column, 5);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "syn2")]
+    fn with_span() {
+        let tokens: Vec<_> = "a b c"
+            .parse::<TokenStream>()
+            .unwrap()
+            .into_iter()
+            .collect();
+        let columns = |error: Syn2Error| {
+            let range = error.span_range();
+            (range.start.start().column, range.end.end().column)
+        };
+
+        let error = ErrorMessage::new(tokens[0].span(), "error").with_span(tokens[1].span()..tokens[2].span());
+        assert_eq!(columns(error.into()), (2, 5));
+
+        let mut error = Syn2Error::new(tokens[0].span(), "first");
+        error.combine(Syn2Error::new(tokens[1].span(), "second"));
+        let result: Result<(), _> = Err(error).with_span(tokens[2].span());
+        let errors: Vec<_> = result.unwrap_err().into_iter().map(columns).collect();
+        assert_eq!(errors, [(4, 5), (4, 5)]);
+    }
+
+    #[test]
+    #[cfg(feature = "syn2")]
+    fn note_span() {
+        let tokens: Vec<_> = "a b".parse::<TokenStream>().unwrap().into_iter().collect();
+        let result: Result<(), _> = Err(Syn2Error::new(tokens[0].span(), "error"));
+        let errors: Vec<_> = result
+            .note_span(tokens[1].span(), "note")
+            .unwrap_err()
+            .into_iter()
+            .map(|error| (error.to_string(), error.span().start().column))
+            .collect();
+        assert_eq!(errors, [("error".to_owned(), 0), ("note: note".to_owned(), 2)]);
+    }
 }
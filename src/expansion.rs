@@ -0,0 +1,338 @@
+use proc_macro2::TokenStream;
+
+use crate::{Emitter, Error, ToTokensError};
+#[cfg(doc)]
+use crate::{attribute, derive, function};
+
+/// Result of a macro handler, with the output, errors and dummy as separate
+/// values.
+///
+/// Returned by [`function_expansion()`](crate::function_expansion),
+/// [`attribute_expansion()`](crate::attribute_expansion) and
+/// [`derive_expansion()`](crate::derive_expansion), allowing tests to inspect
+/// e.g., the final dummy of a handler. Converting it into a [`TokenStream`]
+/// merges the parts the same way as [`function()`], [`attribute()`] and
+/// [`derive()`].
+///
+/// ```
+/// use manyhow::{function_expansion, Dummy, Result};
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+///
+/// let expansion = function_expansion(
+///     quote!(input),
+///     true,
+///     |_: TokenStream, dummy: &mut Dummy| -> Result {
+///         dummy.set(quote!(dummy));
+///         manyhow::bail!("error")
+///     },
+/// );
+/// assert!(expansion.output.is_none());
+/// assert_eq!(
+///     expansion.errors.to_string(),
+///     quote!(::core::compile_error! { "error" }).to_string()
+/// );
+/// assert_eq!(expansion.dummy.to_string(), "dummy");
+/// ```
+#[derive(Clone, Debug, Default)]
+#[non_exhaustive]
+pub struct Expansion {
+    /// Output of the handler, `None` if it returned an error.
+    pub output: Option<TokenStream>,
+    /// Errors returned and emitted by the handler.
+    pub errors: TokenStream,
+    /// Final dummy, used in place of the output if the handler returned an
+    /// error.
+    ///
+    /// The input of the `*_as_dummy` option is only part of it, if the
+    /// handler takes the dummy or returned an error.
+    pub dummy: TokenStream,
+}
+
+impl Expansion {
+    pub(crate) fn new(
+        output: Result<TokenStream, Error>,
+        dummy: TokenStream,
+        emitter: Option<&Emitter>,
+    ) -> Self {
+        let mut errors = TokenStream::new();
+        let output = output.map_err(|error| error.to_tokens(&mut errors)).ok();
+        if let Some(emitter) = emitter {
+            emitter.to_tokens(&mut errors);
+        }
+        Self {
+            output,
+            errors,
+            dummy,
+        }
+    }
+}
+
+#[cfg(feature = "syn2")]
+impl Expansion {
+    /// Returns the messages of all emitted errors.
+    ///
+    /// Attachments like [`help`](crate::ErrorMessage::help) are part of the
+    /// message, as they are rendered by the compiler.
+    #[must_use]
+    pub fn error_messages(&self) -> Vec<String> {
+        crate::error::compile_errors(self.errors.clone())
+            .into_iter()
+            .map(|(_, msg)| msg)
+            .collect()
+    }
+
+    /// Asserts that the handler succeeded with `output`.
+    ///
+    /// Errors emitted while still returning an output are ignored, see
+    /// [`assert_errors()`](Self::assert_errors).
+    ///
+    /// # Panics
+    /// Panics if the handler returned an error or the output differs.
+    ///
+    /// ```
+    /// use manyhow::{emit, function_expansion, Emitter, Result};
+    /// use proc_macro2::TokenStream;
+    /// use quote::quote;
+    ///
+    /// fn my_macro_impl(input: TokenStream, emitter: &mut Emitter) -> Result {
+    ///     if input.is_empty() {
+    ///         emit!(emitter, "expected input");
+    ///     }
+    ///     Ok(quote!(
+    ///         struct Output;
+    ///     ))
+    /// }
+    ///
+    /// function_expansion(quote!(input), false, my_macro_impl)
+    ///     .assert_output(quote!(
+    ///         struct Output;
+    ///     ))
+    ///     .assert_errors(&[]);
+    ///
+    /// function_expansion(quote!(), false, my_macro_impl).assert_errors(&["expected input"]);
+    /// ```
+    #[track_caller]
+    pub fn assert_output(&self, output: impl quote::ToTokens) -> &Self {
+        let Some(actual) = &self.output else {
+            panic!(
+                "expected output, but the handler errored with {:?}",
+                self.error_messages()
+            );
+        };
+        assert_eq!(
+            actual.to_string(),
+            output.to_token_stream().to_string(),
+            "output differs"
+        );
+        self
+    }
+
+    /// Asserts that exactly the `errors` were returned or emitted, in order.
+    ///
+    /// # Panics
+    /// Panics if the messages of the errors differ, see
+    /// [`error_messages()`](Self::error_messages).
+    #[track_caller]
+    #[allow(clippy::must_use_candidate)] // returns `self` only for chaining
+    pub fn assert_errors(&self, errors: &[&str]) -> &Self {
+        assert_eq!(self.error_messages(), errors, "errors differ");
+        self
+    }
+
+    /// Asserts that the handler errored, using `dummy` as its output.
+    ///
+    /// # Panics
+    /// Panics if the handler succeeded or the dummy differs.
+    #[track_caller]
+    pub fn assert_dummy(&self, dummy: impl quote::ToTokens) -> &Self {
+        assert!(
+            self.output.is_none(),
+            "expected the handler to error, but it succeeded"
+        );
+        assert_eq!(
+            self.dummy.to_string(),
+            dummy.to_token_stream().to_string(),
+            "dummy differs"
+        );
+        self
+    }
+
+    /// Asserts that the handler errored, with the dummy matching the golden
+    /// file at `path`, independently of the errors.
+    ///
+    /// The dummy keeps the code using the macro compiling while the errors are
+    /// fixed, comparing it to a golden file makes changes to it visible in
+    /// review. Relative paths are resolved relative to `CARGO_MANIFEST_DIR`.
+    ///
+    /// If the file does not exist, or `MANYHOW_GOLDEN` is set to `overwrite`,
+    /// the file is written instead.
+    ///
+    /// ```
+    /// use manyhow::{function_expansion, Dummy, Result};
+    /// use proc_macro2::TokenStream;
+    /// use quote::quote;
+    ///
+    /// fn my_macro_impl(input: TokenStream, dummy: &mut Dummy) -> Result {
+    ///     dummy.set(quote!(
+    ///         struct Output;
+    ///     ));
+    ///     manyhow::bail!(input, "unexpected input")
+    /// }
+    ///
+    /// # let dir = std::env::temp_dir().join("manyhow-doctest-golden");
+    /// # let _ = std::fs::remove_dir_all(&dir);
+    /// let golden = dir.join("unexpected_input.dummy.rs");
+    /// function_expansion(quote!(input), false, my_macro_impl).assert_dummy_golden(&golden);
+    /// assert_eq!(
+    ///     std::fs::read_to_string(golden).unwrap(),
+    ///     "struct Output ;\n"
+    /// );
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if the handler succeeded, the dummy differs, or the file cannot
+    /// be read or written.
+    #[track_caller]
+    pub fn assert_dummy_golden(&self, path: impl AsRef<std::path::Path>) -> &Self {
+        assert!(
+            self.output.is_none(),
+            "expected the handler to error, but it succeeded"
+        );
+        let path = std::env::var_os("CARGO_MANIFEST_DIR")
+            .map(std::path::PathBuf::from)
+            .unwrap_or_default()
+            .join(path);
+        let actual = format!("{}\n", self.dummy);
+        let overwrite =
+            std::env::var_os("MANYHOW_GOLDEN").is_some_and(|value| value == "overwrite");
+        if overwrite || !path.exists() {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).expect("golden file directory should be creatable");
+            }
+            std::fs::write(&path, actual).expect("golden file should be writable");
+            return self;
+        }
+        let expected = std::fs::read_to_string(&path).expect("golden file should be readable");
+        assert!(
+            expected == actual,
+            "dummy differs from `{}`, rerun with `MANYHOW_GOLDEN=overwrite` to \
+             update\nexpected:\n{expected}actual:\n{actual}",
+            path.display()
+        );
+        self
+    }
+
+    /// Renders the expansion as text for snapshot tests, e.g., with
+    /// [`insta`](https://docs.rs/insta) or compared to
+    /// [`macrotest`](https://docs.rs/macrotest)-style `.expanded.rs` files.
+    ///
+    /// The output, or the dummy if the handler returned an error, is followed
+    /// by the message of every emitted `compile_error!` as a comment. Together
+    /// with the implementation function created by
+    /// [`#[manyhow(impl_fn)]`](crate#without-macros), this allows testing
+    /// expansions without invoking the compiler.
+    ///
+    /// ```
+    /// use manyhow::{function_expansion, Dummy, Result};
+    /// use proc_macro2::TokenStream;
+    /// use quote::quote;
+    ///
+    /// // Created by `#[manyhow(impl_fn)]`
+    /// fn my_macro_impl(input: TokenStream, dummy: &mut Dummy) -> Result {
+    ///     dummy.set(quote!(struct Dummy;));
+    ///     manyhow::bail!(input, "unexpected input"; help = "remove it")
+    /// }
+    ///
+    /// let expansion = function_expansion(quote!(input), false, my_macro_impl);
+    /// assert_eq!(
+    ///     expansion.to_snapshot(),
+    ///     "// dummy\n\
+    ///      struct Dummy ;\n\
+    ///      // error: unexpected input\n\
+    ///      //\n\
+    ///      //   = help: remove it\n"
+    /// );
+    /// ```
+    #[must_use]
+    pub fn to_snapshot(&self) -> String {
+        self.render(|tokens| format!("{tokens}\n"))
+    }
+
+    /// Like [`to_snapshot()`](Self::to_snapshot), but formatting the output or
+    /// dummy with [`prettyplease`](https://docs.rs/prettyplease).
+    ///
+    /// Tokens that do not form a valid file, e.g., the expression returned by
+    /// a function like macro, are rendered unformatted.
+    ///
+    /// ```
+    /// use manyhow::function_expansion;
+    /// use proc_macro2::TokenStream;
+    /// use quote::quote;
+    ///
+    /// let expansion = function_expansion(
+    ///     quote!(
+    ///         fn f() {
+    ///             1
+    ///         }
+    ///     ),
+    ///     false,
+    ///     |input: TokenStream| input,
+    /// );
+    /// assert_eq!(expansion.to_pretty_snapshot(), "fn f() {\n    1\n}\n");
+    /// ```
+    #[cfg(feature = "prettyplease")]
+    #[must_use]
+    pub fn to_pretty_snapshot(&self) -> String {
+        self.render(crate::debug::format_tokens)
+    }
+
+    fn render(&self, format: impl Fn(&TokenStream) -> String) -> String {
+        let mut snapshot = match &self.output {
+            Some(output) => format(output),
+            None => format!("// dummy\n{}", format(&self.dummy)),
+        };
+        crate::debug::push_error_comments(&mut snapshot, self.error_messages());
+        snapshot
+    }
+}
+
+impl From<Expansion> for TokenStream {
+    fn from(value: Expansion) -> Self {
+        let mut tokens = value.output.unwrap_or(value.dummy);
+        crate::append_tokens(&mut tokens, value.errors);
+        tokens
+    }
+}
+
+#[cfg(all(test, feature = "syn2"))]
+mod test {
+    use quote::quote;
+
+    use crate::{function_expansion, Dummy, Result};
+
+    #[test]
+    #[should_panic = "dummy differs from"]
+    fn assert_dummy_golden() {
+        let dir = std::env::temp_dir().join(format!("manyhow-golden-{}", std::process::id()));
+        let golden = dir.join("dummy.rs");
+        let assert = |name: &str| {
+            let name = quote::format_ident!("{name}");
+            let dummy_tokens = quote!(struct #name;);
+            function_expansion(
+                quote!(),
+                false,
+                move |_: proc_macro2::TokenStream, dummy: &mut Dummy| -> Result {
+                    dummy.set(dummy_tokens.clone());
+                    crate::bail!("error");
+                },
+            )
+            .assert_dummy_golden(&golden);
+        };
+        assert("A");
+        assert("A");
+        let result = std::panic::catch_unwind(|| assert("B"));
+        std::fs::remove_dir_all(dir).unwrap();
+        std::panic::resume_unwind(result.unwrap_err());
+    }
+}
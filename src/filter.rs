@@ -0,0 +1,121 @@
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+
+use crate::AnyTokenStream;
+
+/// Removes all attributes named any of `names` from `tokens`, including
+/// attributes nested inside groups, e.g., on fields.
+///
+/// Attributes are matched on the first segment of their path, i.e., `doc`
+/// removes doc comments and `rustfmt` removes `#[rustfmt::skip]`.
+///
+/// This is used by the `#[strip_attrs(...)]` option of
+/// [`function!`](crate::function!), [`attribute!`](crate::attribute!) and
+/// [`derive!`](crate::derive!), as well as `#[manyhow(strip_attrs(...))]`, to
+/// keep unrelated attributes from breaking a strict parser, while the original
+/// tokens are still used as dummy.
+///
+/// It is also used by `#[as_dummy(...)]` and `#[manyhow(item_as_dummy(...))]`
+/// to remove the macro's helper attributes from the dummy.
+///
+/// ```
+/// use manyhow::strip_attributes;
+/// use quote::quote;
+///
+/// let item = quote! {
+///     /// Some docs
+///     #[derive(Debug)]
+///     #[serde(rename_all = "camelCase")]
+///     struct Struct {
+///         #[serde(skip)]
+///         field: u8,
+///     }
+/// };
+/// assert_eq!(
+///     strip_attributes(item, &["doc", "serde"]).to_string(),
+///     quote!(
+///         #[derive(Debug)]
+///         struct Struct {
+///             field: u8,
+///         }
+///     )
+///     .to_string()
+/// );
+/// ```
+#[must_use]
+pub fn strip_attributes<T: AnyTokenStream>(tokens: T, names: &[&str]) -> T {
+    if names.is_empty() {
+        return tokens;
+    }
+    strip(tokens.into(), names).into()
+}
+
+fn strip(tokens: TokenStream, names: &[&str]) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut output = TokenStream::new();
+    let mut i = 0;
+    while i < tokens.len() {
+        if let Some(len) = attribute_len(&tokens[i..], names) {
+            i += len;
+            continue;
+        }
+        output.extend([match &tokens[i] {
+            TokenTree::Group(group) => {
+                let mut stripped = Group::new(group.delimiter(), strip(group.stream(), names));
+                stripped.set_span(group.span());
+                stripped.into()
+            }
+            tt => tt.clone(),
+        }]);
+        i += 1;
+    }
+    output
+}
+
+/// Returns the number of tokens of the attribute at the start of `tokens`, if
+/// it is named any of `names`.
+fn attribute_len(tokens: &[TokenTree], names: &[&str]) -> Option<usize> {
+    let (TokenTree::Punct(pound), rest) = tokens.split_first()? else {
+        return None;
+    };
+    if pound.as_char() != '#' {
+        return None;
+    }
+    let inner = matches!(rest.first(), Some(TokenTree::Punct(bang)) if bang.as_char() == '!');
+    let Some(TokenTree::Group(group)) = rest.get(usize::from(inner)) else {
+        return None;
+    };
+    let Some(TokenTree::Ident(name)) = group.stream().into_iter().next() else {
+        return None;
+    };
+    (group.delimiter() == Delimiter::Bracket && names.iter().any(|n| name == n))
+        .then_some(2 + usize::from(inner))
+}
+
+#[cfg(test)]
+mod test {
+    use quote::quote;
+
+    #[test]
+    fn strip_attributes() {
+        let tokens = quote! {
+            #![doc = "inner"]
+            #[rustfmt::skip]
+            #[allow(unused)]
+            fn f() {
+                #![doc = "inner"]
+                #[rustfmt::skip]
+                let a = [1, 2];
+            }
+        };
+        assert_eq!(
+            super::strip_attributes(tokens, &["doc", "rustfmt"]).to_string(),
+            quote!(
+                #[allow(unused)]
+                fn f() {
+                    let a = [1, 2];
+                }
+            )
+            .to_string()
+        );
+    }
+}
@@ -0,0 +1,142 @@
+//! Reading files referenced by string literals, with errors spanned at the
+//! literal.
+//!
+//! Paths are resolved relative to `CARGO_MANIFEST_DIR`, i.e., the directory
+//! of the `Cargo.toml` of the crate invoking the macro. To rerun the macro
+//! when the file changes, see [`tracked::path()`](crate::tracked::path).
+//!
+//! ```
+//! # use syn2 as syn;
+//! use manyhow::{fs, Result};
+//! use quote::quote;
+//!
+//! fn include_upper(path: syn::LitStr) -> Result {
+//!     let content = fs::read_to_string(&path)?.to_uppercase();
+//!     Ok(quote!(#content))
+//! }
+//! # assert!(include_upper(syn::parse_quote!("Cargo.toml")).is_ok());
+//! let error = include_upper(syn::parse_quote!("missing.txt")).unwrap_err();
+//! ```
+use std::ops::Range;
+use std::path::PathBuf;
+
+use syn2::LitStr;
+
+use crate::{ErrorLocation, ErrorMessage, LocatedError, Result};
+
+/// Resolves `path` relative to `CARGO_MANIFEST_DIR`.
+#[must_use]
+pub fn resolve(path: &LitStr) -> PathBuf {
+    std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(path.value())
+}
+
+/// Reads the file at `path` as a string.
+///
+/// # Errors
+/// Errors, spanned at `path`, if the file cannot be read or is not valid
+/// UTF-8, noting the resolved path.
+pub fn read_to_string(path: &LitStr) -> Result<String> {
+    let resolved = resolve(path);
+    std::fs::read_to_string(&resolved).map_err(|error| io_error(path, &resolved, &error).into())
+}
+
+/// Reads the file at `path` as bytes.
+///
+/// # Errors
+/// Errors, spanned at `path`, if the file cannot be read, noting the resolved
+/// path.
+pub fn read(path: &LitStr) -> Result<Vec<u8>> {
+    let resolved = resolve(path);
+    std::fs::read(&resolved).map_err(|error| io_error(path, &resolved, &error).into())
+}
+
+/// Creates an [`ErrorMessage`] for an error of an external parser, e.g.,
+/// `serde_json`, in the `content` of the file at `path`.
+///
+/// As the error cannot be spanned inside the file, it is spanned at the `path`
+/// literal, noting the location as `--> path:line:column`.
+///
+/// ```
+/// # use syn2 as syn;
+/// use manyhow::{fs, ErrorLocation, LocatedError};
+/// # use std::fmt::{self, Display};
+///
+/// # #[derive(Debug)]
+/// struct ParseError(usize);
+/// # impl Display for ParseError {
+/// #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+/// #         f.write_str("unexpected token")
+/// #     }
+/// # }
+///
+/// impl LocatedError for ParseError {
+///     fn location(&self) -> Option<ErrorLocation> {
+///         Some(ErrorLocation::Offset(self.0))
+///     }
+/// }
+///
+/// let path: syn::LitStr = syn::parse_quote!("config.txt");
+/// let error = fs::located_error(&path, "a = 1\nb = ?", &ParseError(10));
+/// assert_eq!(
+///     error.to_string(),
+///     "unexpected token\n\n  = note: --> config.txt:2:5\n"
+/// );
+/// ```
+pub fn located_error(
+    path: &LitStr,
+    content: &str,
+    error: &(impl LocatedError + ?Sized),
+) -> ErrorMessage {
+    let offset = |offset: usize| {
+        let before = content.get(..offset).unwrap_or(content);
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rsplit_once('\n')
+            .map_or(before, |(_, line)| line)
+            .chars()
+            .count()
+            + 1;
+        (line, column)
+    };
+    let location = match error.location() {
+        Some(ErrorLocation::LineColumn { line, column }) => Some((line, column)),
+        Some(ErrorLocation::Offset(start) | ErrorLocation::Range(Range { start, .. })) => {
+            Some(offset(start))
+        }
+        None => None,
+    };
+    let message = ErrorMessage::new(path.span(), error.message());
+    match location {
+        Some((line, column)) => message.note(format_args!("--> {}:{line}:{column}", path.value())),
+        None => message,
+    }
+}
+
+fn io_error(path: &LitStr, resolved: &std::path::Path, error: &std::io::Error) -> ErrorMessage {
+    ErrorMessage::new(
+        path.span(),
+        format_args!("failed to read `{}`: {error}", path.value()),
+    )
+    .note(format_args!("resolved to `{}`", resolved.display()))
+}
+
+#[cfg(test)]
+mod test {
+    use syn2::parse_quote;
+
+    use crate::ToTokensError;
+
+    #[test]
+    fn read_to_string() {
+        let content = super::read_to_string(&parse_quote!("Cargo.toml")).unwrap();
+        assert!(content.contains("name = \"manyhow\""));
+
+        let error = super::read_to_string(&parse_quote!("missing.txt")).unwrap_err();
+        let error = error.to_token_stream().to_string();
+        assert!(error.contains("failed to read `missing.txt`"), "{error}");
+        assert!(error.contains("= note: resolved to"), "{error}");
+    }
+}
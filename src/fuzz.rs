@@ -0,0 +1,264 @@
+//! Helpers to fuzz macro implementations with malformed input, e.g., using
+//! [cargo-fuzz](https://github.com/rust-fuzz/cargo-fuzz).
+//!
+//! [`Tokens`] implements [`Arbitrary`], generating arbitrary but always
+//! lexable token streams, and [`check()`] drives a macro implementation with
+//! them, asserting that it holds the [`Invariants`].
+//!
+//! ```ignore
+//! #![no_main]
+//! use libfuzzer_sys::fuzz_target;
+//! use manyhow::fuzz::{check, Tokens};
+//! use manyhow::function;
+//!
+//! fuzz_target!(|input: Tokens| {
+//!     check(&input, |input| function!(input, my_macros_impl::my_macro));
+//! });
+//! ```
+//!
+//! The same can be done in regular tests, by generating the tokens from fixed
+//! data:
+//!
+//! ```
+//! use arbitrary::{Arbitrary, Unstructured};
+//! use manyhow::fuzz::{check, Tokens};
+//! use manyhow::{emit, function, Emitter};
+//! use proc_macro2::TokenStream;
+//!
+//! fn my_macro(input: TokenStream, emitter: &mut Emitter) -> TokenStream {
+//!     for token in input {
+//!         emit!(emitter, token, "unexpected `{token}`");
+//!     }
+//!     TokenStream::new()
+//! }
+//!
+//! let data: Vec<u8> = (0..1024u32).map(|i| (i * 37 % 251) as u8).collect();
+//! let mut data = Unstructured::new(&data);
+//! for _ in 0..100 {
+//!     let input = Tokens::arbitrary(&mut data).unwrap();
+//!     check(&input, |input| function!(input, my_macro));
+//! }
+//! ```
+use std::fmt::{self, Display, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use arbitrary::{Arbitrary, Result, Unstructured};
+use proc_macro2::TokenStream;
+
+use crate::error::compile_errors;
+use crate::to_tokens_span_range;
+
+const IDENTS: &[&str] = &[
+    "a", "foo", "Foo", "_", "r#type", "self", "Self", "crate", "super", "struct", "enum", "union",
+    "fn", "impl", "where", "pub", "mut", "dyn", "const", "let", "true", "false",
+];
+
+const PUNCTS: &[&str] = &[
+    "+", "-", "*", "/", "%", "^", "!", "&", "|", "&&", "||", "<<", ">>", "+=", "=", "==", "!=",
+    "<", ">", "<=", ">=", "@", ".", "..", "...", "..=", ",", ";", ":", "::", "->", "=>", "#", "$",
+    "?", "~",
+];
+
+/// Maximal nesting of groups.
+const MAX_DEPTH: usize = 4;
+
+/// Arbitrary tokens, that are always lexable, i.e., a valid [`TokenStream`].
+///
+/// The tokens are generated as source text and parsed on
+/// [`to_token_stream()`](Self::to_token_stream), so, with `proc-macro2`'s
+/// `span-locations` feature, they carry spans distinct from the call site.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tokens(String);
+
+impl Tokens {
+    /// Returns the source text of the tokens.
+    #[must_use]
+    pub fn source(&self) -> &str {
+        &self.0
+    }
+
+    /// Parses the tokens.
+    ///
+    /// # Panics
+    /// Panics if the tokens were not generated by [`Arbitrary`].
+    #[must_use]
+    pub fn to_token_stream(&self) -> TokenStream {
+        self.0.parse().expect("generated tokens should be lexable")
+    }
+}
+
+impl Display for Tokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'a> Arbitrary<'a> for Tokens {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let mut source = String::new();
+        push_tokens(u, &mut source, 0)?;
+        Ok(Self(source))
+    }
+}
+
+fn push_tokens(u: &mut Unstructured, source: &mut String, depth: usize) -> Result<()> {
+    for _ in 0..u.int_in_range(0..=8)? {
+        match u.int_in_range(0..=if depth < MAX_DEPTH { 5 } else { 4 })? {
+            0 => source.push_str(u.choose(IDENTS)?),
+            1 => source.push_str(u.choose(PUNCTS)?),
+            2 => {
+                let int: u64 = u.arbitrary()?;
+                source.push_str(&int.to_string());
+                source.push_str(u.choose(&["", "u8", "i32", "usize", ".5", ".5f64"])?);
+            }
+            3 => {
+                // `Debug` escapes like a Rust literal
+                let string: String = u.arbitrary()?;
+                let _ = write!(source, "{string:?}");
+            }
+            4 => {
+                let char: char = u.arbitrary()?;
+                let _ = write!(source, "{char:?}");
+            }
+            _ => {
+                let (open, close) = *u.choose(&[('(', ')'), ('[', ']'), ('{', '}')])?;
+                source.push(open);
+                push_tokens(u, source, depth + 1)?;
+                source.push(close);
+            }
+        }
+        source.push(' ');
+    }
+    Ok(())
+}
+
+/// Invariants of a macro implementation, checked by [`check()`].
+///
+/// - The implementation never panics.
+/// - When the input is not empty, all errors are spanned at the input, i.e.,
+///   not at the call site. This requires `proc-macro2`'s `span-locations`
+///   feature, e.g., as a dev-dependency, and is skipped otherwise.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Invariants {
+    spanned_errors: bool,
+}
+
+impl Default for Invariants {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Invariants {
+    /// Creates the default invariants.
+    pub fn new() -> Self {
+        Self {
+            spanned_errors: true,
+        }
+    }
+
+    /// Allows errors spanned at the call site, e.g., `syn`'s "unexpected end of
+    /// input".
+    pub fn allow_call_site_errors(mut self) -> Self {
+        self.spanned_errors = false;
+        self
+    }
+
+    /// Expands `input` with `expand`, asserting the invariants.
+    ///
+    /// # Panics
+    /// Panics, including the input, if an invariant is violated.
+    #[track_caller]
+    pub fn check(&self, input: &Tokens, expand: impl FnOnce(TokenStream) -> TokenStream) {
+        let tokens = input.to_token_stream();
+        let spans_known = tokens
+            .clone()
+            .into_iter()
+            .next()
+            .is_some_and(|tt| tt.span().source_text().is_some());
+        let output = match catch_unwind(AssertUnwindSafe(|| expand(tokens))) {
+            Ok(output) => output,
+            Err(payload) => {
+                let message = payload
+                    .downcast_ref::<&str>()
+                    .copied()
+                    .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+                    .unwrap_or("<unknown>");
+                panic!("macro panicked on input `{input}`: {message}");
+            }
+        };
+        if !(self.spanned_errors && spans_known) {
+            return;
+        }
+        for (invocation, msg) in compile_errors(output) {
+            let span = to_tokens_span_range(invocation).start;
+            assert!(
+                span.source_text().is_some(),
+                "error `{msg}` is spanned at the call site on input `{input}`"
+            );
+        }
+    }
+}
+
+/// Expands `input` with `expand`, asserting the default [`Invariants`].
+///
+/// # Panics
+/// Panics, including the input, if an invariant is violated.
+#[track_caller]
+pub fn check(input: &Tokens, expand: impl FnOnce(TokenStream) -> TokenStream) {
+    Invariants::new().check(input, expand);
+}
+
+#[cfg(test)]
+mod test {
+    use quote::quote;
+
+    use super::*;
+    use crate::{function, Emitter};
+
+    fn inputs() -> Vec<Tokens> {
+        let data: Vec<u8> = (0..8192u32).map(|i| (i * 37 % 251) as u8).collect();
+        let mut data = Unstructured::new(&data);
+        std::iter::from_fn(|| Tokens::arbitrary(&mut data).ok())
+            .take(100)
+            .collect()
+    }
+
+    #[test]
+    fn lexable() {
+        let inputs = inputs();
+        assert!(inputs.iter().any(|input| !input.source().is_empty()));
+        for input in inputs {
+            let _ = input.to_token_stream();
+        }
+    }
+
+    #[test]
+    fn spanned_errors() {
+        for input in inputs() {
+            check(&input, |input| {
+                function!(input, |input: TokenStream, emitter: &mut Emitter| {
+                    for token in input {
+                        emit!(emitter, token, "unexpected");
+                    }
+                    quote!()
+                })
+            });
+        }
+    }
+
+    #[test]
+    #[should_panic = "error `call site` is spanned at the call site on input `a`"]
+    fn call_site_error() {
+        check(&Tokens("a".into()), |_| {
+            quote!(::core::compile_error! { "call site" })
+        });
+    }
+
+    #[test]
+    #[should_panic = "macro panicked on input `a`: panic"]
+    fn panic() {
+        check(&Tokens("a".into()), |_| panic!("panic"));
+    }
+}
@@ -0,0 +1,209 @@
+use proc_macro2::TokenStream;
+use syn2::parse::{Parse, ParseStream, Parser};
+
+/// Errors if there are tokens remaining, spanning exactly the unexpected
+/// remainder.
+///
+/// Accepts either a [`ParseStream`], consuming all remaining tokens, or a
+/// [`TokenStream`].
+///
+/// ```
+/// use manyhow::reject_trailing;
+/// use quote::quote;
+/// # use syn2 as syn;
+/// use syn::parse::{ParseStream, Parser};
+///
+/// let parser = |input: ParseStream| {
+///     let ident: syn::Ident = input.parse()?;
+///     reject_trailing(input)?;
+///     Ok(ident)
+/// };
+/// assert_eq!(parser.parse2(quote!(a)).unwrap(), "a");
+/// assert_eq!(
+///     parser.parse2(quote!(a b c)).unwrap_err().to_string(),
+///     "unexpected token"
+/// );
+/// ```
+///
+/// # Errors
+/// Errors if `input` is not empty.
+pub fn reject_trailing(input: impl TrailingTokens) -> syn2::Result<()> {
+    let remaining = input.remaining()?;
+    if remaining.is_empty() {
+        Ok(())
+    } else {
+        Err(syn2::Error::new_spanned(remaining, "unexpected token"))
+    }
+}
+
+/// Input to [`reject_trailing`], either a [`ParseStream`] or a
+/// [`TokenStream`].
+pub trait TrailingTokens {
+    /// Returns the remaining tokens.
+    ///
+    /// # Errors
+    /// Errors if the tokens cannot be consumed.
+    fn remaining(self) -> syn2::Result<TokenStream>;
+}
+
+impl TrailingTokens for ParseStream<'_> {
+    fn remaining(self) -> syn2::Result<TokenStream> {
+        self.parse()
+    }
+}
+
+impl TrailingTokens for TokenStream {
+    fn remaining(self) -> syn2::Result<TokenStream> {
+        Ok(self)
+    }
+}
+
+/// Parses `tokens` with `parser`, [rejecting](reject_trailing) any tokens the
+/// parser did not consume.
+///
+/// This is also how typed inputs of handlers are parsed.
+///
+/// ```
+/// use manyhow::parse_complete;
+/// use quote::quote;
+/// # use syn2 as syn;
+///
+/// let parser = syn::punctuated::Punctuated::<syn::Ident, syn::Token![,]>::parse_separated_nonempty;
+/// assert_eq!(parse_complete(parser, quote!(a, b)).unwrap().len(), 2);
+/// assert_eq!(
+///     parse_complete(parser, quote!(a, b c d))
+///         .map(|_| ())
+///         .unwrap_err()
+///         .to_string(),
+///     "unexpected token"
+/// );
+/// ```
+///
+/// # Errors
+/// Errors if `parser` fails or does not consume all tokens.
+pub fn parse_complete<T>(
+    parser: impl FnOnce(ParseStream) -> syn2::Result<T>,
+    tokens: TokenStream,
+) -> syn2::Result<T> {
+    (|input: ParseStream| {
+        let value = parser(input)?;
+        reject_trailing(input)?;
+        Ok(value)
+    })
+    .parse2(tokens)
+}
+
+/// Input that captures how far parsing `T` succeeded, instead of failing.
+///
+/// When used as input of a handler, the handler is called even if parsing
+/// fails, allowing to emit more helpful diagnostics or produce a dummy based
+/// on a partially parsed input.
+///
+/// ```
+/// use manyhow::{function, PartialParse};
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+/// # use syn2 as syn;
+///
+/// let output: TokenStream = function!(
+///     quote!(1 2),
+///     |input: PartialParse<syn::LitInt>, dummy: &mut TokenStream| -> manyhow::Result {
+///         let value = input.value.as_ref().unwrap();
+///         assert_eq!(value.base10_digits(), "1");
+///         assert_eq!(input.remaining.to_string(), "2");
+///         *dummy = quote!(#value);
+///         Ok(input.into_result()?.into_token_stream())
+///     }
+/// );
+/// # use quote::ToTokens;
+/// assert_eq!(
+///     output.to_string(),
+///     quote!(1 ::core::compile_error! { "unexpected token" }).to_string()
+/// );
+/// ```
+#[derive(Debug)]
+pub struct PartialParse<T> {
+    /// The parsed value, `None` if parsing `T` failed.
+    pub value: Option<T>,
+    /// The error that occurred while parsing, either from parsing `T` or due
+    /// to unexpected tokens after `T`.
+    pub error: Option<syn2::Error>,
+    /// The tokens that were not consumed, i.e., the complete input if parsing
+    /// `T` failed or the tokens following `T`.
+    pub remaining: TokenStream,
+}
+
+impl<T> PartialParse<T> {
+    /// Converts into a [`Result`], failing if any error occurred, even if
+    /// [`Self::value`] was parsed successfully.
+    #[allow(clippy::missing_errors_doc)]
+    pub fn into_result(self) -> syn2::Result<T> {
+        match (self.value, self.error) {
+            (Some(value), None) => Ok(value),
+            (_, Some(error)) => Err(error),
+            (None, None) => unreachable!("either value or error should be present"),
+        }
+    }
+}
+
+impl<T: Parse> Parse for PartialParse<T> {
+    fn parse(input: ParseStream) -> syn2::Result<Self> {
+        let fork = input.fork();
+        match fork.parse() {
+            Ok(value) => {
+                use syn2::parse::discouraged::Speculative;
+                input.advance_to(&fork);
+                let remaining: TokenStream = input.parse()?;
+                Ok(Self {
+                    value: Some(value),
+                    error: reject_trailing(remaining.clone()).err(),
+                    remaining,
+                })
+            }
+            Err(error) => Ok(Self {
+                value: None,
+                error: Some(error),
+                remaining: input.parse()?,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use quote::quote;
+    use syn2::Ident;
+
+    use super::*;
+
+    #[test]
+    fn partial_parse() {
+        let parsed: PartialParse<Ident> = syn2::parse2(quote!(a)).unwrap();
+        assert_eq!(parsed.value.unwrap(), "a");
+        assert!(parsed.error.is_none());
+        assert!(parsed.remaining.is_empty());
+
+        let parsed: PartialParse<Ident> = syn2::parse2(quote!(a b c)).unwrap();
+        assert_eq!(parsed.value.unwrap(), "a");
+        assert_eq!(parsed.error.unwrap().to_string(), "unexpected token");
+        assert_eq!(parsed.remaining.to_string(), "b c");
+
+        let parsed: PartialParse<Ident> = syn2::parse2(quote!(1 b)).unwrap();
+        assert!(parsed.value.is_none());
+        assert_eq!(parsed.error.unwrap().to_string(), "expected identifier");
+        assert_eq!(parsed.remaining.to_string(), "1 b");
+    }
+
+    #[test]
+    fn reject_trailing() {
+        let tokens: TokenStream = "a\n  b c".parse().unwrap();
+        let error = parse_complete(Ident::parse, tokens).unwrap_err();
+        assert_eq!(error.to_string(), "unexpected token");
+        let span = error.span();
+        assert_eq!(span.start().line, 2);
+        assert_eq!(span.start().column, 2);
+        assert_eq!(span.end().column, 5);
+
+        super::reject_trailing(TokenStream::new()).unwrap();
+    }
+}
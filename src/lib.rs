@@ -99,23 +99,37 @@
 //! A proc macro function marked as `#[manyhow]` can take and return any
 //! [`TokenStream`](AnyTokenStream), and can also return `Result<TokenStream,
 //! E>` where `E` implements [`ToTokensError`]. As additional parameters a
-//! [dummy](#dummy-mut-tokenstream) and/or [emitter](#emitter-mut-emitter) can
+//! [dummy](#dummy-mut-dummy) and/or [emitter](#emitter-mut-emitter) can
 //! be specified.
 //!
 //! The `manyhow` attribute takes optional flags to configure its behavior.
 //!
 //! When used for `proc_macro` and `proc_macro_attribute`,
 //! `#[manyhow(input_as_dummy, ...)]` will take the input of a function like
-//! `proc_macro` to initialize the [dummy `&mut TokenStream`](#
-//! dummy-mut-tokenstream) while `#[manyhow(item_as_dummy, ...)]` on
-//! `proc_macro_attribute` will initialize the dummy with the annotated item.
+//! `proc_macro` to initialize the
+//! [dummy `&mut Dummy`](#dummy-mut-dummy) while
+//! `#[manyhow(item_as_dummy, ...)]` on `proc_macro_attribute` will initialize
+//! the dummy with the annotated item. Helper attributes that are only
+//! understood by the macro can be removed from the dummy by listing them, e.g.,
+//! `#[manyhow(item_as_dummy(helper, ...))]`, as they would cause "cannot find
+//! attribute" errors otherwise.
+//!
+//! `#[manyhow(strip_attrs(doc, other_tool, ...))]` removes the listed
+//! attributes from the input (`proc_macro`) or item (`proc_macro_attribute`
+//! and `proc_macro_derive`) before parsing, see [`strip_attributes`]. The
+//! dummy is still initialized with the original tokens.
+//!
+//! `#[manyhow(derive_dummy(Trait { fn method(&self); }))]` on
+//! `proc_macro_derive` initializes the dummy with a stub implementation of
+//! `Trait` for the derived type, see [`dummy::derive_stub`].
 //!
 //! You can merge the `#[proc_macro*]` attribute inside the manyhow flags e.g.,
 //! `#[manyhow(proc_macro)]` or `#[manyhow(proc_macro_derive(SomeTrait, ...))]`.
 //!
 //! The `#[manyhow(impl_fn, ...)]` flag will put the actual macro implementation
 //! in a separate function. Making it available for e.g., unit testing with
-//! [`proc_macro_utils::assert_expansion!`](https://docs.rs/proc-macro-utils/latest/proc_macro_utils/macro.assert_expansion.html).
+//! [`proc_macro_utils::assert_expansion!`](https://docs.rs/proc-macro-utils/latest/proc_macro_utils/macro.assert_expansion.html),
+//! or [`assert_expansion!`] to also check the emitted errors.
 //!
 //! ```ignore
 //! #[manyhow(impl_fn)]
@@ -133,6 +147,120 @@
 //! }
 //! ```
 //!
+//! ## Provenance
+//! When the `MANYHOW_PROVENANCE` environment variable is set during
+//! compilation, attribute and derive macros using `#[manyhow]` append a marker
+//! to their output, naming the macro and its crate's version, e.g., when
+//! inspecting a crate with `cargo expand`:
+//!
+//! ```ignore
+//! /// manyhow: expanded from my_macros::my_derive v0.1.0
+//! #[doc(hidden)]
+//! const _: () = ();
+//! ```
+//!
+//! Function like macros are not marked, as they can expand to expressions. As
+//! the variable is not tracked, already expanded macros are only marked after
+//! their crate is rebuilt, e.g., after `cargo clean`.
+//!
+//! ## Debugging
+//! When the `MANYHOW_DEBUG` environment variable is set during compilation,
+//! every expansion of a `#[manyhow]` macro, or of [`function()`],
+//! [`attribute()`] and [`derive()`], is traced with the macro's name, its
+//! input, and its output with the emitted errors as comments. This allows
+//! users of a macro to create actionable bug reports without patching the
+//! macro crate.
+//!
+//! If `MANYHOW_DEBUG` is `1`, the trace is written to stderr. If it points to
+//! a directory, the trace is appended to `<crate name>.log` in it, otherwise
+//! to the file it points to. With the `prettyplease` feature, the tokens are
+//! formatted. As with [provenance](#provenance), the variable is not tracked.
+//! The debug variables, like `MANYHOW_PROVENANCE`, are read once per
+//! compilation, when all of them are unset, expansions are not traced at all.
+//!
+//! ```text
+//! // manyhow: my_macros::my_macro
+//! // input:
+//! a b
+//! // output:
+//! // error: unexpected `b`
+//! ```
+//!
+//! When `MANYHOW_TIMINGS` points to a directory, the wall-clock time of each
+//! expansion is aggregated per macro and written to `<crate name>.tsv` in it,
+//! listing the number of expansions, their total and their maximum duration,
+//! e.g., to find the macros slowing down a build.
+//!
+//! ### Deterministic error order
+//! Errors are emitted in the order the macro produced them, which can vary
+//! between builds, e.g., when iterating a `HashMap`. When
+//! `MANYHOW_DETERMINISTIC` is set, the emitted errors are instead sorted by
+//! their location (file, line and column) and then by their message, and
+//! placed after the rest of the output, e.g., to get stable `.stderr` files in
+//! UI tests. Errors with the same location and message keep their order. This
+//! requires the `syn` feature to find the errors in the output.
+//!
+//! ## Structured diagnostics
+//! When `MANYHOW_DIAGNOSTICS` points to a directory, the errors emitted by
+//! each expansion are additionally written to `<crate name>.jsonl` in it, one
+//! JSON object per line, e.g., for editor tooling that wants more than the
+//! `compile_error!` messages. This requires the `syn` feature.
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "macro": "my_macros::my_macro",
+//!   "code": "my_macro::unknown_option",
+//!   "severity": "error",
+//!   "message": "unknown option `colour`",
+//!   "span": {
+//!     "file": "src/main.rs",
+//!     "start": { "line": 3, "column": 10 },
+//!     "end": { "line": 3, "column": 16 }
+//!   },
+//!   "children": [
+//!     { "code": null, "severity": "note", "message": "...", "span": null, "children": [], "suggestions": [] }
+//!   ],
+//!   "suggestions": [
+//!     { "message": "a similar option exists", "replacement": "color", "span": { ... } }
+//!   ]
+//! }
+//! ```
+//!
+//! - `version` is incremented on breaking changes of the schema.
+//! - `code` is set with [`ErrorMessage::code()`].
+//! - `children` are the [attachments](ErrorMessage::attachment), with their
+//!   label, e.g., `help`, as `severity`.
+//! - `suggestions` are added with [`ErrorMessage::suggestion()`].
+//! - `span`s are `null` if unknown, lines and columns are 1-based.
+//!
+//! Errors not created by [`ErrorMessage`], e.g., `syn::Error`s, only contain
+//! their message and span.
+//!
+//! ### Fix mode
+//! When `MANYHOW_FIX_DIR` points to a directory, the
+//! [suggestions](ErrorMessage::suggestion()) of the emitted errors are written
+//! to `<crate name>.fixes.jsonl` in it, so an external tool can apply them in
+//! bulk, similar to `cargo fix` for lints. Each line contains the location,
+//! the original source text, if the file could be read, and the replacement:
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "macro": "my_macros::my_macro",
+//!   "message": "a similar option exists",
+//!   "file": "src/main.rs",
+//!   "start": { "line": 3, "column": 10 },
+//!   "end": { "line": 3, "column": 16 },
+//!   "original": "colour",
+//!   "replacement": "color"
+//! }
+//! ```
+//!
+//! The end is exclusive. Suggestions without a known location, i.e., outside
+//! of proc macros, are skipped. Like the structured diagnostics, this requires
+//! the `syn` feature.
+//!
 //! # Without macros
 //! `manyhow` can be used without proc macros, and they can be disabled by
 //! adding `manyhow` with `default-features=false`.
@@ -142,8 +270,8 @@
 //! ([`attribute!`]) or [`derive()`] ([`derive!`]) directly. For each version
 //! there exists a function and a `macro_rules` macro, while the function only
 //! supports [`proc_macro::TokenStream`] and [`proc_macro2::TokenStream`], the
-//! macro versions also support any type that implements [`Parse`]
-//! and [`ToTokens`] respectively.
+//! macro versions also support any type that implements [`Parse`] (or
+//! [`ManyhowParse`]) and [`ToTokens`] (or [`ManyhowToTokens`]) respectively.
 //!
 //! While the examples use closures, functions can be passed in as well. The
 //! above example would then change to:
@@ -179,8 +307,12 @@
 //!     )
 //! }
 //! ```
+//! For crates with many macros, the non-generic functions in [`entry`] avoid
+//! instantiating this glue for every macro, supporting only handlers of the
+//! shape `fn(TokenStream) -> Result`.
+//!
 //! [`Emitter`](#emitter-mut-emitter) and [dummy
-//! `TokenStream`](#dummy-mut-tokenstream) can also be used. [`function()`]
+//! `TokenStream`](#dummy-mut-dummy) can also be used. [`function()`]
 //! ([`function!`]) and [`attribute()`] ([`attribute!`]) take an additional
 //! boolean parameter controlling whether the input/item will be used as initial
 //! dummy.
@@ -212,21 +344,57 @@
 //! }
 //! ```
 //!
-//! # `dummy: &mut TokenStream`
+//! # `dummy: &mut Dummy`
 //! [`*MacroHandler`](FunctionMacroHandler)s can also take a mutable reference
-//! to a `TokenStream`, to enable emitting some dummy code to be used in case
-//! the macro errors.
+//! to a [`Dummy`], to enable emitting some dummy code to be used in case the
+//! macro errors.
+//!
+//! This allows either appending tokens with [`Dummy::append`] or replacing the
+//! dummy code with [`Dummy::set`], e.g., `dummy.set(quote!{some tokens})`.
+//! The [`dummy`] module contains builders for common dummies, e.g.,
+//! [`dummy::unit_struct`] or [`dummy::expr_unreachable`].
 //!
-//! This allows either appending tokens e.g., with [`ToTokens::to_tokens`] or
-//! directly setting the dummy code e.g., `*dummy = quote!{some tokens}`.
+//! For backwards compatibility, a `&mut TokenStream` can be used as well,
+//! appending tokens e.g., with [`ToTokens::to_tokens`] or directly setting the
+//! dummy code e.g., `*dummy = quote!{some tokens}`.
+//!
+//! The dummy is only emitted when the handler returns an error (or its input
+//! fails to parse). When the handler returns `Ok`, only its output is used,
+//! even if errors were emitted through the [`Emitter`], so the dummy and the
+//! output cannot define the same items twice.
 //!
 //! # Crate features
 //!
 //! - `macros` **default** Enables [`#[manyhow]`](macros::manyhow) attribute
 //!   macro.
-//! - `syn`/`syn2` **default** Enables errors for [`syn` 2.x](https://docs.rs/syn/latest/syn/).
+//! - `syn`/`syn2` **default** Enables errors for [`syn` 2.x](https://docs.rs/syn/latest/syn/)
+//!   and all of the `syn` integration, i.e., `syn2-error` and `syn2-parse`.
+//!   - `syn2-error` Enables only the conversion of `syn::Error`s into [`Error`]
+//!     and [`ErrorMessage`]s into `syn::Error`s.
+//!   - `syn2-parse` Enables handlers taking inputs implementing [`Parse`] and
+//!     returning outputs implementing [`ToTokens`], as well as [`Syn2`].
 //! - `syn1` Enables errors for [`syn` 1.x](https://docs.rs/syn/1.0.109/syn/index.html).
-//! - `darling` Enables errors for [`darling`](https://docs.rs/darling/latest/index.html).
+//! - `darling` Enables errors for [`darling`](https://docs.rs/darling/latest/index.html)
+//!   0.20 and [`parse_fields()`]/[`parse_variants()`].
+//! - `darling_0_21` Enables errors for `darling` 0.21, both versions can be
+//!   enabled at the same time. Errors collected in darling's `Accumulator` can
+//!   be emitted with `emitter.extend(accumulator.into_inner())`.
+//! - `proc-macro-error2` Enables errors for [`proc-macro-error2`](https://docs.rs/proc-macro-error2)
+//!   diagnostics and their span ranges.
+//! - `litrs` Enables errors for [`litrs`](https://docs.rs/litrs/latest/litrs/)
+//!   and [`parse_literal()`].
+//! - `serde` Enables [`deserialize_tokens()`] and [`Deserialized`] to
+//!   deserialize e.g., attribute arguments with [`serde`](https://docs.rs/serde).
+//! - `serde_json`/`toml`/`serde_yaml` Enable
+//!   `deserialize_json()`/`deserialize_toml()`/`deserialize_yaml()` to
+//!   deserialize JSON/TOML/YAML string literals.
+//! - `prettyplease` Enables [`Expansion::to_pretty_snapshot()`] and formats the
+//!   [debug output](#debugging).
+//! - `arbitrary` Enables the [`fuzz`] module to fuzz macro implementations with
+//!   [`arbitrary`](https://docs.rs/arbitrary) tokens.
+//! - `criterion` Enables the [`bench`](mod@bench) module to benchmark macro implementations
+//!   with [`criterion`](https://docs.rs/criterion).
+//! - `coverage` Records which error sites fire, see [`coverage`].
 
 #[cfg(feature = "macros")]
 pub use macros::manyhow;
@@ -238,12 +406,60 @@ extern crate proc_macro;
 
 #[macro_use]
 mod span_ranged;
-pub use span_ranged::{to_tokens_span_range, SpanRanged};
+#[cfg(feature = "syn2")]
+pub use span_ranged::spanned_range;
+pub use span_ranged::{
+    to_tokens_span_range, DelimSpanRanged, SpanRange, SpanRanged, SubSpan,
+};
 #[macro_use]
 mod macro_rules;
 mod error;
 pub use error::*;
+mod literal;
+pub use literal::*;
+#[cfg(feature = "syn2")]
+mod input;
+#[cfg(feature = "syn2")]
+pub use input::*;
+#[cfg(feature = "syn2")]
+mod args;
+#[cfg(feature = "syn2")]
+pub use args::*;
+#[cfg(feature = "syn2")]
+mod shape;
+#[cfg(feature = "syn2")]
+pub use shape::*;
 
+pub mod dummy;
+pub use dummy::Dummy;
+#[cfg(feature = "criterion")]
+pub mod bench;
+#[cfg(feature = "coverage")]
+pub mod coverage;
+pub mod entry;
+#[cfg(feature = "syn2")]
+pub mod fs;
+#[cfg(feature = "arbitrary")]
+pub mod fuzz;
+#[cfg(feature = "syn2")]
+pub mod testing;
+#[cfg(feature = "syn2")]
+pub mod tracked;
+mod typed;
+pub use typed::*;
+mod filter;
+pub use filter::*;
+mod expansion;
+pub use expansion::*;
+mod boundary;
+pub use boundary::*;
+mod debug;
+#[cfg(feature = "serde")]
+mod deserialize;
+#[cfg(feature = "syn2")]
+mod diagnostics;
+#[cfg(feature = "serde")]
+pub use deserialize::*;
 mod parse_to_tokens;
 
 #[doc(hidden)]
@@ -254,9 +470,73 @@ pub mod __private {
     pub use quote;
 
     pub use crate::span_ranged::*;
-    pub type Dummy = Option<TokenStream>;
+    /// Dummy of `*_as_dummy` options, created lazily.
+    ///
+    /// Boxed, so the handling of the dummy is not instantiated for every
+    /// macro.
+    pub type LazyDummy<'a> = Box<dyn FnOnce() -> TokenStream + 'a>;
+    pub type Dummy<'a> = Option<LazyDummy<'a>>;
+
+    /// Returns the text of `args`, only allocating if it has formatting
+    /// arguments.
+    pub fn args_text(args: std::fmt::Arguments) -> std::borrow::Cow<'static, str> {
+        args.as_str()
+            .map_or_else(|| args.to_string().into(), std::borrow::Cow::Borrowed)
+    }
 
+    /// Creates the dummy of `*_as_dummy` options lazily.
+    pub fn lazy_dummy<'a, T: crate::AnyTokenStream>(
+        dummy: impl FnOnce() -> T + 'a,
+    ) -> LazyDummy<'a> {
+        Box::new(|| dummy().into())
+    }
+
+    pub use crate::debug::Trace;
     pub use crate::parse_to_tokens::*;
+
+    /// Replaces the dummy for `bail!(dummy = ...)`, implemented for both
+    /// dummies a handler can take.
+    pub trait SetDummy {
+        fn __set_dummy(&mut self, tokens: TokenStream);
+    }
+
+    impl SetDummy for TokenStream {
+        fn __set_dummy(&mut self, tokens: TokenStream) {
+            *self = tokens;
+        }
+    }
+
+    impl SetDummy for crate::Dummy {
+        fn __set_dummy(&mut self, tokens: TokenStream) {
+            self.set(tokens);
+        }
+    }
+
+    /// Appends a marker naming the macro that expanded `output` if
+    /// `MANYHOW_PROVENANCE` is set, see [`#[manyhow]`](crate#provenance).
+    pub fn provenance<T: crate::AnyTokenStream>(output: T, name: &str) -> T {
+        if !crate::debug::provenance() {
+            return output;
+        }
+        let mut output: TokenStream = output.into();
+        let marker = format!(" manyhow: expanded from {name}");
+        output.extend(quote::quote! {
+            #[doc(hidden)]
+            #[doc = #marker]
+            const _: () = ();
+        });
+        output.into()
+    }
+}
+
+/// Appends `other` to `tokens`, moving it instead of copying its tokens when
+/// `tokens` is empty, e.g., for the output of a macro without errors.
+pub(crate) fn append_tokens(tokens: &mut TokenStream, other: TokenStream) {
+    if tokens.is_empty() {
+        *tokens = other;
+    } else {
+        tokens.extend(other);
+    }
 }
 
 /// Marker trait for [`proc_macro::TokenStream`] and
@@ -269,7 +549,7 @@ impl AnyTokenStream for proc_macro::TokenStream {}
 #[doc(hidden)]
 macro_rules! __macro_handler {
     ($name:ident; $($(#attr=$attr:tt)? $n:ident: $input:expr),+; $impl:expr$(; dummy:$dummy:expr)?) => {
-        $crate::__macro_handler! {! $name; $($(#attr=$attr)? $n: $input.clone()),+; $impl $(; $crate::__private::Some($dummy))?}
+        $crate::__macro_handler! {! $name; $($(#attr=$attr)? $n: $input.clone()),+; $impl $(; $crate::__private::Some($crate::__private::lazy_dummy(move || $dummy)))?}
     };
     ($name:ident; $($(#attr=$attr:tt)? $n:ident: $input:expr),+; $impl:expr; dummy) => {
         $crate::__macro_handler! {! $name; $($(#attr=$attr)? $n: $input),+; $impl; $crate::__private::Dummy::None}
@@ -290,11 +570,10 @@ macro_rules! __macro_handler {
             )+ $($dummy,)? implementation)
             {
                 Err(tokens) => tokens.into(),
-                Ok((output, mut tokens, mut dummy)) => {
+                Ok((output, mut tokens, dummy)) => {
                     match (&$crate::__private::WhatType::from(&output)).manyhow_try(output) {
                         Err(error) => {
-                            dummy.extend(tokens);
-                            tokens = dummy;
+                            tokens = dummy.prepend_to(tokens);
                             (&$crate::__private::WhatType::from(&error)).manyhow_to_tokens(error, &mut tokens);
                         },
                         Ok(output) => (&$crate::__private::WhatType::from(&output)).manyhow_to_tokens(output, &mut tokens),
@@ -313,12 +592,12 @@ macro_rules! __macro_handler {
 /// `TokenStream`. If `item_as_dummy = true` the item input will be used as
 /// default dummy code on error. `body` takes a [`AttributeMacroHandler`] with
 /// two `TokenStream` parameters. And an optional [`&mut Emitter`](Emitter) and
-/// a `&mut TokenStream` for storing a dummy output.
+/// a [`&mut Dummy`](Dummy) for storing a dummy output.
 ///
 /// ```
 /// # use proc_macro_utils::assert_tokens;
 /// # use quote::{quote, ToTokens};
-/// use manyhow::{attribute, Emitter, Result};
+/// use manyhow::{attribute, Dummy, Emitter, Result};
 /// use proc_macro2::TokenStream;
 /// # let input = quote!();
 /// # let item = quote!();
@@ -329,7 +608,7 @@ macro_rules! __macro_handler {
 ///     false,
 ///     |input: TokenStream,
 ///      item: TokenStream,
-///      dummy: &mut TokenStream,
+///      dummy: &mut Dummy,
 ///      emitter: &mut Emitter|
 ///      -> Result {
 ///         // ..
@@ -338,11 +617,11 @@ macro_rules! __macro_handler {
 /// );
 /// ```
 ///
-/// *Note:* When `item_as_dummy = true` the `dummy: &mut TokenStream` will be
-/// initialized with `item`. To override assign a new `TokenStream`:
+/// *Note:* When `item_as_dummy = true` the `dummy: &mut Dummy` will be
+/// initialized with `item`. To override use [`Dummy::set`]:
 /// ```
 /// # use proc_macro_utils::assert_tokens;
-/// use manyhow::{attribute, Result, SilentError};
+/// use manyhow::{attribute, Dummy, Result, SilentError};
 /// use proc_macro2::TokenStream;
 /// use quote::{quote, ToTokens};
 /// # let input = quote!(input);
@@ -355,14 +634,14 @@ macro_rules! __macro_handler {
 ///     true,
 ///     |input: TokenStream,
 ///      item: TokenStream,
-///      dummy: &mut TokenStream|
+///      dummy: &mut Dummy|
 ///      -> Result<TokenStream, SilentError> {
 ///         assert_tokens!(dummy.to_token_stream(), {
 ///             struct Struct;
 ///         });
-///         *dummy = quote! {
+///         dummy.set(quote! {
 ///             struct Struct(HelloWorld);
-///         };
+///         });
 ///         // ..
 ///         Err(SilentError)
 ///     },
@@ -389,30 +668,79 @@ pub fn attribute<
         Output = Output,
     >,
 ) -> Return {
-    #[allow(unused_mut)]
-    let mut tokens = Dummy::default();
-    let mut tokens = if item_as_dummy {
-        item.clone().into().into()
-    } else {
-        tokens
-    };
+    let trace = debug::Trace::start("attribute()", || {
+        [
+            ("input", input.clone().into()),
+            ("item", item.clone().into()),
+        ]
+    });
+    debug::Trace::finish(
+        trace,
+        TokenStream::from(attribute_expansion(input, item, item_as_dummy, body)),
+    )
+    .into()
+}
+
+/// Like [`attribute()`], but returning the output, errors and dummy
+/// separately, see [`Expansion`].
+pub fn attribute_expansion<
+    Input: AnyTokenStream,
+    Item: AnyTokenStream,
+    Dummy: AnyTokenStream,
+    Output: MacroOutput,
+    Function,
+    Body: AttributeMacroHandler<Function, Item = Item, Input = Input, Dummy = Dummy, Output = Output>,
+>(
+    input: impl AnyTokenStream,
+    item: impl AnyTokenStream,
+    item_as_dummy: bool,
+    body: Body,
+) -> Expansion {
+    let (mut dummy, kept) =
+        dummy_from_input::<_, Body::Dummy>(&item, item_as_dummy, Body::TAKES_DUMMY);
     let mut emitter = Emitter::new();
     let output = body.call(
         input.into().into(),
         item.into().into(),
-        &mut tokens,
+        &mut dummy,
         &mut emitter,
     );
-    let mut tokens = tokens.into();
-    let mut tokens = match output.convert() {
-        Ok(tokens) => tokens,
-        Err(error) => {
-            error.to_tokens(&mut tokens);
-            tokens
-        }
+    expansion_with_kept_dummy(
+        output.convert(),
+        dummy.into(),
+        kept,
+        Body::TAKES_EMITTER.then_some(&emitter),
+    )
+}
+
+/// Creates the dummy of the `*_as_dummy` option from `input`.
+///
+/// Only when the handler takes the dummy, it is created up front. Otherwise,
+/// the input is kept, and only converted into the dummy by
+/// [`expansion_with_kept_dummy()`] when the handler errors.
+fn dummy_from_input<Input: AnyTokenStream, Dummy: AnyTokenStream>(
+    input: &Input,
+    as_dummy: bool,
+    takes_dummy: bool,
+) -> (Dummy, Option<Input>) {
+    match (as_dummy, takes_dummy) {
+        (false, _) => (Dummy::default(), None),
+        (true, true) => (input.clone().into().into(), None),
+        (true, false) => (Dummy::default(), Some(input.clone())),
+    }
+}
+
+fn expansion_with_kept_dummy(
+    output: Result<TokenStream, Error>,
+    dummy: TokenStream,
+    kept: Option<impl AnyTokenStream>,
+    emitter: Option<&Emitter>,
+) -> Expansion {
+    let dummy = match kept {
+        Some(kept) if output.is_err() => kept.into(),
+        _ => dummy,
     };
-    emitter.to_tokens(&mut tokens);
-    tokens.into()
+    Expansion::new(output, dummy, emitter)
 }
 
 /// Handles [`proc_macro_attribute`](https://doc.rust-lang.org/reference/procedural-macros.html#attribute-macros)
@@ -423,20 +751,20 @@ pub fn attribute<
 /// dummy code on error. `body` takes a [`AttributeMacroHandler`] with two
 /// `TokenStream`s or types implementing [`Parse`] parameters and returning a
 /// `TokenStream` or type implementing [`ToTokens`]. And an optional [`&mut
-/// Emitter`](Emitter) and a `&mut TokenStream` for storing a dummy output.
+/// Emitter`](Emitter) and a [`&mut Dummy`](Dummy) for storing a dummy output.
 ///
 ///
 /// ```
 /// # use proc_macro_utils::assert_tokens;
 /// # use quote::{quote, ToTokens};
-/// use manyhow::{attribute, Emitter, Result};
+/// use manyhow::{attribute, Dummy, Emitter, Result};
 /// use proc_macro2::TokenStream;
 /// # let input = quote!();
 /// # let item = quote!();
 /// # let output: TokenStream =
 /// attribute!(input, item, |input: TokenStream,
 ///                          item: TokenStream,
-///                          dummy: &mut TokenStream,
+///                          dummy: &mut Dummy,
 ///                          emitter: &mut Emitter|
 ///  -> Result {
 ///     // ..
@@ -444,12 +772,12 @@ pub fn attribute<
 /// });
 /// ```
 ///
-/// *Note:* When `#[as_dummy]` is specified the `dummy: &mut TokenStream` will
-/// be initialized with `item`. To override assign a new `TokenStream`:
+/// *Note:* When `#[as_dummy]` is specified the `dummy: &mut Dummy` will be
+/// initialized with `item`. To override use [`Dummy::set`]:
 /// ```
 /// # use proc_macro_utils::assert_tokens;
 /// # use syn2 as syn;
-/// use manyhow::{attribute, Result, SilentError};
+/// use manyhow::{attribute, Dummy, Result, SilentError};
 /// use proc_macro2::TokenStream;
 /// use quote::{quote, ToTokens};
 /// # let input = quote!(input);
@@ -462,14 +790,14 @@ pub fn attribute<
 ///     item,
 ///     |input: TokenStream,
 ///      item: syn::ItemStruct,
-///      dummy: &mut TokenStream|
+///      dummy: &mut Dummy|
 ///      -> Result<syn::ItemStruct, SilentError> {
 ///         assert_tokens!(dummy.to_token_stream(), {
 ///             struct Struct;
 ///         });
-///         *dummy = quote! {
+///         dummy.set(quote! {
 ///             struct Struct(HelloWorld);
-///         };
+///         });
 ///         // ..
 ///         Err(SilentError)
 ///     },
@@ -477,10 +805,71 @@ pub fn attribute<
 ///
 /// assert_tokens! {output, {struct Struct(HelloWorld);}};
 /// ```
+///
+/// Helper attributes can be removed from the dummy by listing them in
+/// `#[as_dummy(helper, ...)]`:
+/// ```
+/// use manyhow::{attribute, Result};
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+///
+/// let item = quote!(
+///     struct Struct {
+///         #[helper]
+///         field: u8,
+///     }
+/// );
+/// let output: TokenStream = attribute!(
+///     quote!(),
+///     #[as_dummy(helper)]
+///     item,
+///     |_: TokenStream, item: TokenStream| -> Result { manyhow::bail!("error") }
+/// );
+/// assert_eq!(
+///     output.to_string(),
+///     quote! {
+///         struct Struct { field: u8, }
+///         ::core::compile_error! { "error" }
+///     }
+///     .to_string()
+/// );
+/// ```
+///
+/// Attributes can be removed from the item before parsing by specifying
+/// `#[strip_attrs(...)]` (after `#[as_dummy]`), see [`strip_attributes`],
+/// the dummy still contains the original item:
+/// ```
+/// use manyhow::{attribute, Result};
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+///
+/// let item = quote!(#[other_tool::attr] #[doc = "docs"] struct Struct;);
+/// let output: TokenStream = attribute!(
+///     quote!(),
+///     #[as_dummy]
+///     #[strip_attrs(other_tool, doc)]
+///     item,
+///     |_: TokenStream, item: TokenStream| -> Result {
+///         assert_eq!(item.to_string(), quote!(struct Struct;).to_string());
+///         manyhow::bail!("error")
+///     }
+/// );
+/// assert_eq!(
+///     output.to_string(),
+///     quote!(#[other_tool::attr] #[doc = "docs"] struct Struct; ::core::compile_error! { "error" })
+///         .to_string()
+/// );
+/// ```
 #[macro_export]
 macro_rules! attribute {
-    ($input:expr, #[as_dummy] $item:expr, $impl:expr $(,)?) => {
-        $crate::__macro_handler!{attribute_transparent; #attr=true input: $input, item: $item.clone(); $impl; dummy: $item}
+    ($input:expr, #[as_dummy$(($($helper:ident),* $(,)?))?] #[strip_attrs($($attr:ident),* $(,)?)] $item:expr, $impl:expr $(,)?) => {
+        $crate::__macro_handler!{attribute_transparent; #attr=true input: $input, item: $crate::strip_attributes($item.clone(), &[$(::core::stringify!($attr)),*]); $impl; dummy: $crate::strip_attributes($item, &[$($(::core::stringify!($helper)),*)?])}
+    };
+    ($input:expr, #[strip_attrs($($attr:ident),* $(,)?)] $item:expr, $impl:expr $(,)?) => {
+        $crate::__macro_handler!{attribute_transparent; #attr=true input: $input, item: $crate::strip_attributes($item, &[$(::core::stringify!($attr)),*]); $impl; dummy}
+    };
+    ($input:expr, #[as_dummy$(($($helper:ident),* $(,)?))?] $item:expr, $impl:expr $(,)?) => {
+        $crate::__macro_handler!{attribute_transparent; #attr=true input: $input, item: $item.clone(); $impl; dummy: $crate::strip_attributes($item, &[$($(::core::stringify!($helper)),*)?])}
     };
     ($input:expr, $item:expr, $impl:expr $(,)?) => {
         $crate::__macro_handler!{attribute_transparent; #attr=true input: $input, item: $item; $impl; dummy}
@@ -494,19 +883,19 @@ macro_rules! attribute {
 ///
 /// Takes any `TokenStream` for `item` and returns any `TokenStream`. `body`
 /// takes a [`DeriveMacroHandler`] with one `TokenStream` parameter. And an
-/// optional [`&mut Emitter`](Emitter) and `&mut TokenStream` for storing a
+/// optional [`&mut Emitter`](Emitter) and [`&mut Dummy`](Dummy) for storing a
 /// dummy output.
 ///
 /// ```
 /// # use proc_macro_utils::assert_tokens;
 /// # use quote::{quote, ToTokens};
-/// use manyhow::{derive, Emitter, Result};
+/// use manyhow::{derive, Dummy, Emitter, Result};
 /// use proc_macro2::TokenStream;
 /// # let item = quote!();
 /// # let output: TokenStream =
 /// derive(
 ///     item,
-///     |item: TokenStream, dummy: &mut TokenStream, emitter: &mut Emitter| -> Result {
+///     |item: TokenStream, dummy: &mut Dummy, emitter: &mut Emitter| -> Result {
 ///         // ..
 ///         # Ok(quote!())
 ///     },
@@ -522,19 +911,30 @@ pub fn derive<
     item: impl AnyTokenStream,
     body: impl DeriveMacroHandler<Function, Item = Item, Dummy = Dummy, Output = Output>,
 ) -> Return {
-    let mut tokens = Dummy::default();
+    let trace = debug::Trace::start("derive()", || [("item", item.clone().into())]);
+    debug::Trace::finish(trace, TokenStream::from(derive_expansion(item, body))).into()
+}
+
+/// Like [`derive()`], but returning the output, errors and dummy separately,
+/// see [`Expansion`].
+pub fn derive_expansion<
+    Item: AnyTokenStream,
+    Dummy: AnyTokenStream,
+    Output: MacroOutput,
+    Function,
+    Body: DeriveMacroHandler<Function, Item = Item, Dummy = Dummy, Output = Output>,
+>(
+    item: impl AnyTokenStream,
+    body: Body,
+) -> Expansion {
+    let mut dummy = Dummy::default();
     let mut emitter = Emitter::new();
-    let output = body.call(item.into().into(), &mut tokens, &mut emitter);
-    let mut tokens = tokens.into();
-    let mut tokens = match output.convert() {
-        Ok(tokens) => tokens,
-        Err(error) => {
-            error.to_tokens(&mut tokens);
-            tokens
-        }
-    };
-    emitter.to_tokens(&mut tokens);
-    tokens.into()
+    let output = body.call(item.into().into(), &mut dummy, &mut emitter);
+    Expansion::new(
+        output.convert(),
+        dummy.into(),
+        Body::TAKES_EMITTER.then_some(&emitter),
+    )
 }
 
 /// Handles [`proc_macro_derive`](https://doc.rust-lang.org/reference/procedural-macros.html#derive-macros)
@@ -543,29 +943,68 @@ pub fn derive<
 /// Takes any `TokenStream` for `item` and returns any `TokenStream`. `body`
 /// takes a [`DeriveMacroHandler`] with one `TokenStream` or type implementing
 /// [`Parse`] parameter and returns a `TokenStream` or type implementing
-/// [`ToTokens`]. And an optional [`&mut Emitter`](Emitter) and `&mut
-/// TokenStream` for storing a dummy output.
+/// [`ToTokens`]. And an optional [`&mut Emitter`](Emitter) and [`&mut
+/// Dummy`](Dummy) for storing a dummy output.
 ///
 /// ```
 /// # use proc_macro_utils::assert_tokens;
 /// # use quote::{quote, ToTokens};
 /// # use syn2 as syn;
-/// use manyhow::{derive, Emitter, Result};
+/// use manyhow::{derive, Dummy, Emitter, Result};
 /// use proc_macro2::TokenStream;
 /// # let item = quote!();
 /// # let output: TokenStream =
 /// derive!(item, |item: syn::DeriveInput,
-///                dummy: &mut TokenStream,
+///                dummy: &mut Dummy,
 ///                emitter: &mut Emitter|
 ///  -> Result {
 ///     // ..
 ///         # Ok(quote!())
 /// });
 /// ```
+///
+/// Attributes can be removed from the item before parsing by specifying
+/// `#[strip_attrs(...)]`, see [`attribute!`] and [`strip_attributes`].
+///
+/// Specifying `#[derive_dummy(Trait { ... })]` (before `#[strip_attrs]`)
+/// initializes the dummy with a stub implementation of the trait, see
+/// [`dummy::derive_stub`]:
+/// ```
+/// use manyhow::{derive, Result};
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+///
+/// let output: TokenStream = derive!(
+///     #[derive_dummy(Trait { fn method(&self); })]
+///     quote!(
+///         struct Struct;
+///     ),
+///     |_: TokenStream| -> Result { manyhow::bail!("error") }
+/// );
+/// assert_eq!(
+///     output.to_string(),
+///     quote! {
+///         #[automatically_derived]
+///         #[allow(unused_variables)]
+///         impl Trait for Struct { fn method(&self) { ::core::todo!() } }
+///         ::core::compile_error! { "error" }
+///     }
+///     .to_string()
+/// );
+/// ```
 #[macro_export]
 macro_rules! derive {
+    (#[derive_dummy($($skeleton:tt)*)] #[strip_attrs($($attr:ident),* $(,)?)] $item:expr, $impl:expr $(,)?) => {
+        $crate::__macro_handler! {derive_transparent; item: $crate::strip_attributes($item.clone(), &[$(::core::stringify!($attr)),*]); $impl; dummy: $crate::dummy::derive_stub_tokens($crate::__private::quote::quote!($($skeleton)*), $item)}
+    };
+    (#[derive_dummy($($skeleton:tt)*)] $item:expr, $impl:expr $(,)?) => {
+        $crate::__macro_handler! {derive_transparent; item: $item; $impl; dummy: $crate::dummy::derive_stub_tokens($crate::__private::quote::quote!($($skeleton)*), $item)}
+    };
+    (#[strip_attrs($($attr:ident),* $(,)?)] $item:expr, $impl:expr $(,)?) => {
+        $crate::__macro_handler! {derive_transparent; item: $crate::strip_attributes($item, &[$(::core::stringify!($attr)),*]); $impl; dummy}
+    };
     ($item:expr, $impl:expr $(,)?) => {
-        $crate::__macro_handler! {derive_transparent; item: $item; $impl}
+        $crate::__macro_handler! {derive_transparent; item: $item; $impl; dummy}
     };
 }
 
@@ -578,30 +1017,30 @@ macro_rules! derive {
 /// `TokenStream`. If `input_as_dummy = true` the item input will be used as
 /// default dummy code on error. `body` takes a [`FunctionMacroHandler`] with
 /// one `TokenStream` parameter. And an optional [`&mut Emitter`](Emitter) and a
-/// `&mut TokenStream` for storing a dummy output.
+/// [`&mut Dummy`](Dummy) for storing a dummy output.
 ///
 /// ```
 /// # use proc_macro_utils::assert_tokens;
 /// # use quote::{quote, ToTokens};
-/// use manyhow::{function, Emitter, Result};
+/// use manyhow::{function, Dummy, Emitter, Result};
 /// use proc_macro2::TokenStream;
 /// # let input = quote!();
 /// # let output: TokenStream =
 /// function(
 ///     input,
 ///     false,
-///     |input: TokenStream, dummy: &mut TokenStream, emitter: &mut Emitter| -> Result {
+///     |input: TokenStream, dummy: &mut Dummy, emitter: &mut Emitter| -> Result {
 ///         // ..
 ///         # Ok(quote!())
 ///     },
 /// );
 /// ```
 ///
-/// *Note:* When `input_as_dummy = true` the `dummy: &mut TokenStream` will be
-/// initialized with `input`. To override assign a new `TokenStream`:
+/// *Note:* When `input_as_dummy = true` the `dummy: &mut Dummy` will be
+/// initialized with `input`. To override use [`Dummy::set`]:
 /// ```
 /// # use proc_macro_utils::assert_tokens;
-/// use manyhow::{function, Result, SilentError};
+/// use manyhow::{function, Dummy, Result, SilentError};
 /// use proc_macro2::TokenStream;
 /// use quote::{quote, ToTokens};
 /// let input = quote!(some input);
@@ -609,14 +1048,14 @@ macro_rules! derive {
 ///     input,
 ///     true,
 ///     |input: TokenStream,
-///      dummy: &mut TokenStream|
+///      dummy: &mut Dummy|
 ///      -> Result<TokenStream, SilentError> {
 ///         assert_tokens!(dummy.to_token_stream(), {
 ///             some input
 ///         });
-///         *dummy = quote! {
+///         dummy.set(quote! {
 ///             another input
-///         };
+///         });
 ///         // ..
 ///         Err(SilentError)
 ///     },
@@ -635,23 +1074,37 @@ pub fn function<
     input_as_dummy: bool,
     body: impl FunctionMacroHandler<Function, Input = Input, Dummy = Dummy, Output = Output>,
 ) -> Return {
-    let mut tokens = if input_as_dummy {
-        input.clone().into().into()
-    } else {
-        Dummy::default()
-    };
+    let trace = debug::Trace::start("function()", || [("input", input.clone().into())]);
+    debug::Trace::finish(
+        trace,
+        TokenStream::from(function_expansion(input, input_as_dummy, body)),
+    )
+    .into()
+}
+
+/// Like [`function()`], but returning the output, errors and dummy
+/// separately, see [`Expansion`].
+pub fn function_expansion<
+    Input: AnyTokenStream,
+    Dummy: AnyTokenStream,
+    Output: MacroOutput,
+    Function,
+    Body: FunctionMacroHandler<Function, Input = Input, Dummy = Dummy, Output = Output>,
+>(
+    input: impl AnyTokenStream,
+    input_as_dummy: bool,
+    body: Body,
+) -> Expansion {
+    let (mut dummy, kept) =
+        dummy_from_input::<_, Body::Dummy>(&input, input_as_dummy, Body::TAKES_DUMMY);
     let mut emitter = Emitter::new();
-    let output = body.call(input.into().into(), &mut tokens, &mut emitter);
-    let mut tokens = tokens.into();
-    let mut tokens = match output.convert() {
-        Ok(tokens) => tokens,
-        Err(error) => {
-            error.to_tokens(&mut tokens);
-            tokens
-        }
-    };
-    emitter.to_tokens(&mut tokens);
-    tokens.into()
+    let output = body.call(input.into().into(), &mut dummy, &mut emitter);
+    expansion_with_kept_dummy(
+        output.convert(),
+        dummy.into(),
+        kept,
+        Body::TAKES_EMITTER.then_some(&emitter),
+    )
 }
 
 /// Handles function like [`proc_macro`](https://doc.rust-lang.org/reference/procedural-macros.html#function-like-procedural-macros)
@@ -662,18 +1115,18 @@ pub fn function<
 /// dummy code on error. `body` takes a [`FunctionMacroHandler`] with one
 /// `TokenStream` or type implementing [`Parse`] parameter and returns a
 /// `TokenStream` or type implementing [`ToTokens`]. And an optional [`&mut
-/// Emitter`](Emitter) and a `&mut TokenStream` for storing a dummy output.
+/// Emitter`](Emitter) and a [`&mut Dummy`](Dummy) for storing a dummy output.
 ///
 /// ```
 /// # use proc_macro_utils::assert_tokens;
 /// # use quote::{quote, ToTokens};
 /// # use syn2 as syn;
-/// use manyhow::{function, Emitter, Result};
+/// use manyhow::{function, Dummy, Emitter, Result};
 /// use proc_macro2::TokenStream;
 /// # let input = quote!();
 /// # let output: TokenStream =
 /// function!(input, |input: syn::Item,
-///                   dummy: &mut TokenStream,
+///                   dummy: &mut Dummy,
 ///                   emitter: &mut Emitter|
 ///  -> Result<syn::ItemImpl> {
 ///     // ..
@@ -682,26 +1135,25 @@ pub fn function<
 /// ```
 ///
 /// *Note:* When `#[as_dummy]` is specified on the input, the `dummy: &mut
-/// TokenStream` will be initialized with `input`. To override assign a new
-/// `TokenStream`:
+/// Dummy` will be initialized with `input`. To override use [`Dummy::set`]:
 ///
 /// ```
 /// use proc_macro_utils::assert_tokens;
-/// use manyhow::{function, Result, SilentError};
+/// use manyhow::{function, Dummy, Result, SilentError};
 /// use proc_macro2::TokenStream;
 /// use quote::{quote, ToTokens};
 ///
 /// let input = quote!(some input);
 /// let output: TokenStream = function!(
 ///     #[as_dummy] input,
-///     |input: TokenStream, dummy: &mut TokenStream|
+///     |input: TokenStream, dummy: &mut Dummy|
 ///      -> Result<TokenStream, SilentError> {
 ///         assert_tokens!(dummy.to_token_stream(), {
 ///             some input
 ///         });
-///         *dummy = quote! {
+///         dummy.set(quote! {
 ///             another input
-///         };
+///         });
 ///         // ..
 ///         Err(SilentError)
 ///     },
@@ -709,10 +1161,37 @@ pub fn function<
 ///
 /// assert_tokens! {output, {another input}};
 /// ```
+///
+/// Multiple comma separated arguments can be parsed by using a tuple of types
+/// implementing [`Parse`] as input:
+///
+/// ```
+/// use manyhow::function;
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+/// # use syn2 as syn;
+///
+/// let output: TokenStream = function!(
+///     quote!(name, "value"),
+///     |(name, value): (syn::Ident, syn::LitStr)| -> TokenStream {
+///         quote!(const #name: &str = #value;)
+///     }
+/// );
+/// ```
+///
+/// Attributes can be removed from the input before parsing by specifying
+/// `#[strip_attrs(...)]` (after `#[as_dummy]`), and from the dummy with
+/// `#[as_dummy(helper, ...)]`, see [`attribute!`] and [`strip_attributes`].
 #[macro_export]
 macro_rules! function {
-    (#[as_dummy] $input:expr, $impl:expr $(,)?) => {
-        $crate::__macro_handler! {function_transparent; input: $input; $impl; dummy: $input}
+    (#[as_dummy$(($($helper:ident),* $(,)?))?] #[strip_attrs($($attr:ident),* $(,)?)] $input:expr, $impl:expr $(,)?) => {
+        $crate::__macro_handler! {function_transparent; input: $crate::strip_attributes($input.clone(), &[$(::core::stringify!($attr)),*]); $impl; dummy: $crate::strip_attributes($input, &[$($(::core::stringify!($helper)),*)?])}
+    };
+    (#[strip_attrs($($attr:ident),* $(,)?)] $input:expr, $impl:expr $(,)?) => {
+        $crate::__macro_handler! {function_transparent; input: $crate::strip_attributes($input, &[$(::core::stringify!($attr)),*]); $impl; dummy}
+    };
+    (#[as_dummy$(($($helper:ident),* $(,)?))?] $input:expr, $impl:expr $(,)?) => {
+        $crate::__macro_handler! {function_transparent; input: $input; $impl; dummy: $crate::strip_attributes($input, &[$($(::core::stringify!($helper)),*)?])}
     };
     ($input:expr, $impl:expr $(,)?) => {
         $crate::__macro_handler! {function_transparent; input: $input; $impl; dummy}
@@ -754,6 +1233,126 @@ fn function_macro() {
     }
 }
 
+#[test]
+fn dummy_only_on_err() {
+    use quote::quote;
+
+    let handler = |input: TokenStream, dummy: &mut Dummy, emitter: &mut Emitter| -> Result {
+        dummy.set(quote!(dummy));
+        emitter.emit(error_message!("emitted"));
+        if input.is_empty() {
+            bail!("error");
+        }
+        Ok(input)
+    };
+
+    let output: TokenStream = function(quote!(output), false, handler);
+    assert_eq!(
+        output.to_string(),
+        quote!(output::core::compile_error! { "emitted" }).to_string()
+    );
+    let output: TokenStream = function!(quote!(output), handler);
+    assert_eq!(
+        output.to_string(),
+        quote!(::core::compile_error! { "emitted" } output).to_string()
+    );
+
+    let output: TokenStream = function(quote!(), false, handler);
+    assert_eq!(
+        output.to_string(),
+        quote!(dummy ::core::compile_error! { "error" } ::core::compile_error! { "emitted" })
+            .to_string()
+    );
+    let output: TokenStream = function!(quote!(), handler);
+    assert_eq!(
+        output.to_string(),
+        quote!(dummy ::core::compile_error! { "emitted" } ::core::compile_error! { "error" })
+            .to_string()
+    );
+}
+
+#[test]
+fn lazy_dummy() {
+    use std::cell::Cell;
+
+    use quote::quote;
+
+    let created = Cell::new(0);
+    let input = || {
+        created.set(created.get() + 1);
+        quote!(input)
+    };
+
+    let _: TokenStream = function!(
+        #[as_dummy]
+        input(),
+        |input: TokenStream| -> TokenStream { input }
+    );
+    assert_eq!(created.get(), 1, "dummy is not created on success");
+
+    let _: TokenStream = function!(
+        #[as_dummy]
+        input(),
+        |_: TokenStream| -> Result {
+            bail!("error");
+        }
+    );
+    assert_eq!(created.get(), 3, "dummy is created on error");
+
+    let _: TokenStream = function!(
+        #[as_dummy]
+        input(),
+        |input: TokenStream, _: &mut Dummy| -> TokenStream { input }
+    );
+    assert_eq!(
+        created.get(),
+        5,
+        "dummy is created when taken by the handler"
+    );
+}
+
+#[test]
+fn takes_emitter() {
+    fn takes_emitter<Function, Body: FunctionMacroHandler<Function>>(_: Body) -> bool {
+        Body::TAKES_EMITTER
+    }
+
+    assert!(!takes_emitter(|input: TokenStream| input));
+    assert!(!takes_emitter(|input: TokenStream, _: &mut Dummy| input));
+    assert!(takes_emitter(|input: TokenStream, _: &mut Emitter| input));
+    assert!(takes_emitter(
+        |input: TokenStream, _: &mut Dummy, _: &mut Emitter| input
+    ));
+}
+
+#[test]
+fn kept_dummy() {
+    use quote::quote;
+
+    let expansion = function_expansion(quote!(input), true, |input: TokenStream| input);
+    assert!(expansion.output.is_some());
+    assert!(
+        expansion.dummy.is_empty(),
+        "dummy is not created on success"
+    );
+
+    let expansion = function_expansion(quote!(input), true, |_: TokenStream| -> Result {
+        bail!("error");
+    });
+    assert_eq!(expansion.dummy.to_string(), "input");
+
+    let expansion = attribute_expansion(
+        quote!(),
+        quote!(item),
+        true,
+        |_: TokenStream, item: TokenStream, dummy: &mut Dummy| {
+            assert_eq!(dummy.to_string(), "item");
+            item
+        },
+    );
+    assert_eq!(expansion.dummy.to_string(), "item");
+}
+
 macro_rules! macro_input {
     ($MacroInput:ident; $($input:ident: $Input:ident),+; $a:literal; $name:literal; $token_streams:literal) => {
         /// Input of
@@ -768,7 +1367,8 @@ macro_rules! macro_input {
         #[doc = concat!($token_streams, ".")]
         /// Additionally, they can take optionally in any order a [`&mut
         /// Emitter`](Emitter) which allows emitting errors without returning early. And
-        /// a `&mut TokenStream` to return a dummy `TokenStream` on failure.
+        /// a [`&mut Dummy`](Dummy) (or `&mut TokenStream`) to return a dummy
+        /// `TokenStream` on failure.
         ///
         /// When used with
         #[doc = concat!("[`", $name, "()`]")]
@@ -781,6 +1381,14 @@ macro_rules! macro_input {
             $(type $Input;)+
             type Dummy;
             type Output;
+            /// Whether the handler takes the dummy, if not it is only created
+            /// when needed.
+            #[doc(hidden)]
+            const TAKES_DUMMY: bool = true;
+            /// Whether the handler takes the emitter, if not its errors are
+            /// not collected.
+            #[doc(hidden)]
+            const TAKES_EMITTER: bool = true;
             #[allow(clippy::missing_errors_doc)]
             fn call(
                 self,
@@ -790,15 +1398,15 @@ macro_rules! macro_input {
             ) -> Self::Output;
         }
 
-        macro_input_impl!([$($Input,)+ Dummy: Clone]; $MacroInput; $($input: $Input),*; &mut Dummy, &mut Emitter; dummy: Dummy dummy; emitter emitter);
-        macro_input_impl!([$($Input,)+ Dummy: Clone]; $MacroInput; $($input: $Input),*; &mut Dummy; dummy: Dummy dummy; _emitter);
-        macro_input_impl!([$($Input),+]; $MacroInput; $($input: $Input),*; &mut Emitter; _dummy: TokenStream; emitter emitter);
-        macro_input_impl!([$($Input),+]; $MacroInput; $($input: $Input),*; ; _dummy: TokenStream; _emitter);
+        macro_input_impl!([$($Input,)+ Dummy: Clone]; $MacroInput; $($input: $Input),*; &mut Dummy, &mut Emitter; dummy: Dummy = true dummy; emitter = true emitter);
+        macro_input_impl!([$($Input,)+ Dummy: Clone]; $MacroInput; $($input: $Input),*; &mut Dummy; dummy: Dummy = true dummy; _emitter = false);
+        macro_input_impl!([$($Input),+]; $MacroInput; $($input: $Input),*; &mut Emitter; _dummy: TokenStream = false; emitter = true emitter);
+        macro_input_impl!([$($Input),+]; $MacroInput; $($input: $Input),*; ; _dummy: TokenStream = false; _emitter = false);
     };
 }
 
 macro_rules! macro_input_impl {
-    ([$($gen:tt)*]; $MacroInput:ident; $($input:ident: $Input:ident),+; $($Extra:ty),*; $dummy1:ident: $Dummy:ident $($dummy2:ident)?; $emitter1:ident $($emitter2:ident)?) => {
+    ([$($gen:tt)*]; $MacroInput:ident; $($input:ident: $Input:ident),+; $($Extra:ty),*; $dummy1:ident: $Dummy:ident = $takes_dummy:literal $($dummy2:ident)?; $emitter1:ident = $takes_emitter:literal $($emitter2:ident)?) => {
         impl<$($gen)*, Output, Function> $MacroInput<($($Input,)+ $($Extra,)* Output)> for Function
         where
             Function: Fn($($Input,)+ $($Extra,)*) -> Output,
@@ -807,6 +1415,9 @@ macro_rules! macro_input_impl {
             $(type $Input = $Input;)*
             type Output = Output;
 
+            const TAKES_DUMMY: bool = $takes_dummy;
+            const TAKES_EMITTER: bool = $takes_emitter;
+
             fn call(
                 self,
                 $($input: Self::$Input,)*
@@ -0,0 +1,410 @@
+use std::fmt::Display;
+use std::ops::Range;
+
+#[cfg(feature = "litrs")]
+use proc_macro2::TokenTree;
+use proc_macro2::{Literal, Span};
+
+use crate::{ErrorMessage, SpanRanged, SubSpan};
+
+/// Maps byte offsets in the value of a string literal to the literal's
+/// source code, e.g., for reporting errors in a DSL embedded in a string.
+///
+/// Escapes (`\n`, `\x7F`, `\u{1F980}`, line continuations) as well as raw
+/// string prefixes and hashes are accounted for.
+///
+/// Precise spans require [`Literal::subspan`], which is currently only
+/// supported on nightly, on stable the span of the complete literal is
+/// returned.
+///
+/// ```
+/// use manyhow::{ErrorMessage, LitStrMap};
+/// use proc_macro2::Literal;
+///
+/// let literal: Literal = r#""SELECT \"name\" FORM users""#.parse().unwrap();
+/// let map = LitStrMap::new(&literal).unwrap();
+/// assert_eq!(map.value(), r#"SELECT "name" FORM users"#);
+///
+/// let form = map.value().find("FORM").unwrap();
+/// let form = form..form + "FORM".len();
+/// // Offsets in the source are shifted by the quote and escapes
+/// assert_eq!(map.source_range(form.clone()), 17..21);
+/// let error = ErrorMessage::new(map.span_range(form), "unknown keyword `FORM`");
+/// ```
+#[derive(Debug, Clone)]
+pub struct LitStrMap {
+    literal: Literal,
+    value: String,
+    /// Pairs of offsets in `value` and the source, one for the start of each
+    /// char and a final one for the end.
+    offsets: Vec<(usize, usize)>,
+}
+
+impl LitStrMap {
+    /// Creates a map for `literal`, returns `None` if `literal` is not a
+    /// (raw) string literal.
+    #[must_use]
+    pub fn new(literal: &Literal) -> Option<Self> {
+        let source = literal.to_string();
+        let (value, offsets) = if let Some(raw) = source.strip_prefix('r') {
+            let hashes = raw.len() - raw.trim_start_matches('#').len();
+            let content_start = 1 + hashes + 1;
+            let end = format!("\"{}", "#".repeat(hashes));
+            let content_end = content_start + source.get(content_start..)?.find(&end)?;
+            let value = source[content_start..content_end].to_owned();
+            let offsets = value
+                .char_indices()
+                .map(|(i, _)| (i, content_start + i))
+                .chain([(value.len(), content_end)])
+                .collect();
+            (value, offsets)
+        } else if source.starts_with('"') {
+            unescape(&source)?
+        } else {
+            return None;
+        };
+        Some(Self {
+            literal: literal.clone(),
+            value,
+            offsets,
+        })
+    }
+
+    /// Creates a map for a [`syn::LitStr`](syn2::LitStr)
+    ///
+    /// # Panics
+    /// Panics if the token of `lit` is not a string literal, which cannot
+    /// happen for a `LitStr` created by `syn`.
+    #[cfg(feature = "syn2")]
+    #[must_use]
+    pub fn from_lit_str(lit: &syn2::LitStr) -> Self {
+        Self::new(&lit.token()).expect("`LitStr` should contain a string literal")
+    }
+
+    /// The value of the literal, i.e., the string with all escapes resolved
+    #[must_use]
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Returns the byte range inside the literal's source code corresponding to
+    /// `range` in [`Self::value`].
+    ///
+    /// Ranges partially covering an escape sequence are extended to cover the
+    /// whole sequence, out of bounds ranges are clamped to the literal's
+    /// content.
+    #[must_use]
+    pub fn source_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.offsets[self
+            .offsets
+            .partition_point(|&(value, _)| value <= range.start)
+            .saturating_sub(1)]
+        .1;
+        let end = self.offsets[self
+            .offsets
+            .partition_point(|&(value, _)| value < range.end)
+            .min(self.offsets.len() - 1)]
+        .1;
+        start..end.max(start)
+    }
+
+    /// Returns the [`Span`] for `range` in [`Self::value`] as a range usable as
+    /// [`SpanRanged`].
+    ///
+    /// Falls back to the span of the complete literal, when a precise
+    /// [`Literal::subspan`] is not available.
+    #[must_use]
+    pub fn span_range(&self, range: Range<usize>) -> Range<Span> {
+        SubSpan::new(self.literal.clone(), self.source_range(range)).span_range()
+    }
+
+    /// Returns the byte offset in [`Self::value`] of a 1-based `line` and
+    /// `column`, columns are counted in chars.
+    ///
+    /// Out of bounds lines or columns are clamped to the end of the value or
+    /// line respectively.
+    #[must_use]
+    pub fn offset(&self, line: usize, column: usize) -> usize {
+        let mut offset = 0;
+        for (i, content) in self.value.split_inclusive('\n').enumerate() {
+            if i + 1 == line.max(1) {
+                return offset
+                    + content
+                        .char_indices()
+                        .nth(column.saturating_sub(1))
+                        .map_or(content.trim_end_matches('\n').len(), |(i, _)| i);
+            }
+            offset += content.len();
+        }
+        offset
+    }
+
+    /// Creates an [`ErrorMessage`] spanning the error location inside the
+    /// literal, as reported by an external parser.
+    ///
+    /// ```
+    /// use manyhow::{ErrorLocation, LitStrMap, LocatedError};
+    /// # use std::fmt::{self, Display};
+    ///
+    /// # #[derive(Debug)]
+    /// struct ConfigError {
+    ///     line: usize,
+    ///     column: usize,
+    /// }
+    /// # impl Display for ConfigError {
+    /// #     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    /// #         f.write_str("unknown key")
+    /// #     }
+    /// # }
+    ///
+    /// impl LocatedError for ConfigError {
+    ///     fn location(&self) -> Option<ErrorLocation> {
+    ///         Some(ErrorLocation::LineColumn {
+    ///             line: self.line,
+    ///             column: self.column,
+    ///         })
+    ///     }
+    /// }
+    ///
+    /// let map = LitStrMap::new(&"\"a = 1\\nb = 2\"".parse().unwrap()).unwrap();
+    /// let error = map.error(&ConfigError { line: 2, column: 1 });
+    /// assert_eq!(error.to_string(), "unknown key");
+    /// ```
+    pub fn error(&self, error: &(impl LocatedError + ?Sized)) -> ErrorMessage {
+        ErrorMessage::new(self.location_span_range(error.location()), error.message())
+    }
+
+    /// Creates an [`ErrorMessage`] with `msg` at `location` inside the literal.
+    pub fn error_at(&self, location: impl Into<ErrorLocation>, msg: impl Display) -> ErrorMessage {
+        ErrorMessage::new(self.location_span_range(Some(location.into())), msg)
+    }
+
+    fn location_span_range(&self, location: Option<ErrorLocation>) -> Range<Span> {
+        let range = match location {
+            Some(ErrorLocation::Offset(offset)) => offset..offset + 1,
+            Some(ErrorLocation::Range(range)) => range,
+            Some(ErrorLocation::LineColumn { line, column }) => {
+                let offset = self.offset(line, column);
+                offset..offset + 1
+            }
+            None => 0..self.value.len(),
+        };
+        self.span_range(range)
+    }
+}
+
+/// Location of an error inside a string, e.g., the value of a string literal
+/// mapped with [`LitStrMap`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorLocation {
+    /// Byte offset
+    Offset(usize),
+    /// Byte range
+    Range(Range<usize>),
+    /// 1-based line and column, columns are counted in chars
+    LineColumn {
+        /// 1-based line
+        line: usize,
+        /// 1-based column
+        column: usize,
+    },
+}
+
+impl From<usize> for ErrorLocation {
+    fn from(value: usize) -> Self {
+        Self::Offset(value)
+    }
+}
+
+impl From<Range<usize>> for ErrorLocation {
+    fn from(value: Range<usize>) -> Self {
+        Self::Range(value)
+    }
+}
+
+/// Errors of external parsers that know where in their input they occurred.
+///
+/// Used by [`LitStrMap::error`] to create [`ErrorMessage`]s spanned inside
+/// string literals.
+pub trait LocatedError: Display {
+    /// The location of the error, `None` if unknown.
+    fn location(&self) -> Option<ErrorLocation>;
+
+    /// The message of the error, without any location information.
+    ///
+    /// Defaults to the [`Display`] implementation.
+    fn message(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// Unescapes a (non raw) string literal, returning the value and the offset
+/// pairs of [`LitStrMap`].
+fn unescape(source: &str) -> Option<(String, Vec<(usize, usize)>)> {
+    let mut value = String::new();
+    let mut offsets = Vec::new();
+    // skip opening quote
+    let mut chars = source.char_indices().skip(1).peekable();
+    loop {
+        let (i, c) = chars.next()?;
+        offsets.push((value.len(), i));
+        match c {
+            '"' => return Some((value, offsets)),
+            '\\' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                'r' => value.push('\r'),
+                't' => value.push('\t'),
+                '0' => value.push('\0'),
+                c @ ('\\' | '\'' | '"') => value.push(c),
+                'x' => {
+                    let hex: String = [chars.next()?.1, chars.next()?.1].iter().collect();
+                    value.push(u8::from_str_radix(&hex, 16).ok()?.into());
+                }
+                'u' => {
+                    chars.next().filter(|&(_, c)| c == '{')?;
+                    let mut hex = String::new();
+                    loop {
+                        match chars.next()?.1 {
+                            '}' => break,
+                            '_' => {}
+                            c => hex.push(c),
+                        }
+                    }
+                    value.push(char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?);
+                }
+                // line continuation, does not contribute to value
+                c @ ('\n' | '\r') => {
+                    if c == '\r' {
+                        chars.next_if(|&(_, c)| c == '\n')?;
+                    }
+                    offsets.pop();
+                    while chars.next_if(|(_, c)| c.is_ascii_whitespace()).is_some() {}
+                }
+                _ => return None,
+            },
+            c => value.push(c),
+        }
+    }
+}
+
+/// Parses a literal token using [`litrs`], e.g., into a [`litrs::StringLit`]
+///
+/// # Errors
+/// On failure, the returned [`ErrorMessage`] is spanned at the token and
+/// describes what kind of literal was expected.
+///
+/// ```
+/// use manyhow::parse_literal;
+/// use proc_macro2::{Literal, TokenTree};
+///
+/// let lit: litrs::StringLit<String> = parse_literal(Literal::string("hello")).unwrap();
+/// assert_eq!(lit.value(), "hello");
+///
+/// let error = parse_literal::<litrs::StringLit<String>>(Literal::u8_suffixed(1)).unwrap_err();
+/// assert_eq!(
+///     error.to_string(),
+///     r#"expected a string literal (e.g. "Ferris"), but found an integer literal (e.g. `27`)"#
+/// );
+/// ```
+#[cfg(feature = "litrs")]
+pub fn parse_literal<T>(token: impl Into<TokenTree>) -> Result<T, ErrorMessage>
+where
+    T: TryFrom<TokenTree, Error = litrs::InvalidToken>,
+{
+    let token = token.into();
+    let span = token.span();
+    T::try_from(token).map_err(|error| ErrorMessage::new(span, error))
+}
+
+#[cfg(test)]
+mod test {
+    use proc_macro2::Literal;
+    #[cfg(feature = "litrs")]
+    use proc_macro2::{Ident, Span};
+
+    use super::*;
+
+    #[cfg(feature = "litrs")]
+    #[test]
+    fn parse_literal() {
+        let lit: litrs::IntegerLit<String> =
+            super::parse_literal(Literal::u16_unsuffixed(10)).unwrap();
+        assert_eq!(lit.value::<u16>(), Some(10));
+
+        let lit: litrs::BoolLit =
+            super::parse_literal(Ident::new("true", Span::call_site())).unwrap();
+        assert!(lit.value());
+
+        assert_eq!(
+            super::parse_literal::<litrs::CharLit<String>>(Ident::new("a", Span::call_site()))
+                .unwrap_err()
+                .to_string(),
+            "expected a character literal (e.g. `'P'`), but found an identifier"
+        );
+    }
+
+    fn lit_str_map_of(source: &str) -> LitStrMap {
+        LitStrMap::new(&source.parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn lit_str_map() {
+        let map = lit_str_map_of(r#""a\nb\x41\u{1F980}c""#);
+        assert_eq!(map.value(), "a\nbA🦀c");
+        assert_eq!(map.source_range(0..1), 1..2);
+        assert_eq!(map.source_range(1..2), 2..4);
+        assert_eq!(map.source_range(3..4), 5..9);
+        assert_eq!(map.source_range(4..8), 9..18);
+        assert_eq!(map.source_range(8..9), 18..19);
+        // partially covered escape
+        assert_eq!(map.source_range(5..6), 9..18);
+        // clamped
+        assert_eq!(map.source_range(8..100), 18..19);
+        assert_eq!(map.source_range(100..200), 19..19);
+
+        let map = lit_str_map_of("\"a\\\n    b\"");
+        assert_eq!(map.value(), "ab");
+        assert_eq!(map.source_range(1..2), 8..9);
+
+        let map = lit_str_map_of("\"a\\\r\n    b\"");
+        assert_eq!(map.value(), "ab");
+        assert_eq!(map.source_range(1..2), 9..10);
+
+        // only ASCII whitespace is skipped
+        let map = lit_str_map_of("\"a\\\n\u{A0}b\"");
+        assert_eq!(map.value(), "a\u{A0}b");
+        assert_eq!(map.source_range(1..3), 4..6);
+
+        let map = lit_str_map_of(r###"r##"a"#b"##suffix"###);
+        assert_eq!(map.value(), r##"a"#b"##);
+        assert_eq!(map.source_range(1..3), 5..7);
+
+        assert!(LitStrMap::new(&Literal::u8_suffixed(1)).is_none());
+        assert!(LitStrMap::new(&"b\"bytes\"".parse().unwrap()).is_none());
+    }
+
+    #[test]
+    fn lit_str_map_offset() {
+        let map = lit_str_map_of(r#""a = 1\nbö = \"c\"\n""#);
+        assert_eq!(map.offset(1, 1), 0);
+        assert_eq!(map.offset(1, 5), 4);
+        assert_eq!(map.offset(2, 1), 6);
+        assert_eq!(map.offset(2, 3), 9);
+        assert_eq!(map.source_range(map.offset(2, 6)..map.offset(2, 7)), 14..16);
+        // clamped
+        assert_eq!(map.offset(1, 10), 5);
+        assert_eq!(map.offset(3, 1), 16);
+        assert_eq!(map.offset(10, 1), 16);
+    }
+
+    #[test]
+    fn lit_str_map_span() {
+        let tokens: proc_macro2::TokenStream = r#"  "a\"bc""#.parse().unwrap();
+        let Some(proc_macro2::TokenTree::Literal(literal)) = tokens.into_iter().next() else {
+            unreachable!()
+        };
+        let span = LitStrMap::new(&literal).unwrap().span_range(2..3).start;
+        assert_eq!(span.start().column, 6);
+        assert_eq!(span.end().column, 7);
+    }
+}
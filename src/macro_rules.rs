@@ -6,20 +6,38 @@ use quote::ToTokens;
 #[cfg(doc)]
 use crate::{Emitter, Error, ErrorMessage, SpanRanged};
 
+#[cfg(feature = "coverage")]
 #[doc(hidden)]
 #[macro_export]
-macro_rules! __error_message_internal {
-    ((cs($($fmt:tt)*)$(.$fn:ident($($fmt_fn:tt)*))*), (), ()) => {
-        $crate::ErrorMessage::call_site($($fmt)*)
-            $(.attachment(::core::stringify!($fn), $($fmt_fn)*))*
+macro_rules! __record_error_site {
+    () => {
+        $crate::coverage::record(::core::file!(), ::core::line!())
     };
-    ((new($span:expr)($($fmt:tt)*)$(.$fn:ident($($fmt_fn:tt)*))*), (), ()) => {
-        $crate::ErrorMessage::new(
+}
+
+#[cfg(not(feature = "coverage"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __record_error_site {
+    () => {};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __error_message_internal {
+    ((cs($($fmt:tt)*)$(.$fn:ident$([$fn_span:expr])?($($fmt_fn:tt)*))*), (), ()) => {{
+        let error = $crate::ErrorMessage::__call_site_args(::core::format_args!($($fmt)*));
+        $(let error = $crate::__error_message_attachment!(error, $fn$([$fn_span])?, $($fmt_fn)*);)*
+        error
+    }};
+    ((new($span:expr)($($fmt:tt)*)$(.$fn:ident$([$fn_span:expr])?($($fmt_fn:tt)*))*), (), ()) => {{
+        let error = $crate::ErrorMessage::__new_args(
             $crate::span_range!($span),
-            $($fmt)*
-        )
-            $(.attachment(::core::stringify!($fn), $($fmt_fn)*))*
-    };
+            ::core::format_args!($($fmt)*)
+        );
+        $(let error = $crate::__error_message_attachment!(error, $fn$([$fn_span])?, $($fmt_fn)*);)*
+        error
+    }};
     // ident = expr
     ($head:tt, ($($fmt:tt)*), (, $ident:ident = $expr:expr, $($tail:tt)*)) => {
         $crate::__error_message_internal!($head, ($($fmt)*, $ident = $expr), (, $($tail)*))
@@ -40,12 +58,68 @@ macro_rules! __error_message_internal {
     ($head:tt, ($($fmt:tt)*), (, $expr:expr)) => {
         $crate::__error_message_internal!($head, ($($fmt)*, $expr), ())
     };
+    // ; code = expr
+    (($($head:tt)*), $fmt:tt, ($(,)?; code = $code:expr $(,)? $(; $($tail:tt)*)?)) => {
+        $crate::__error_message_internal!(($($head)*$fmt.code), (@code $code), ($(; $($tail)*)?))
+    };
     // ; ident = "format", arguments
     (($($head:tt)*), $fmt:tt, ($(,)?$(;)?)) => {
-        $crate::__error_message_internal!(($($head)*(::core::format_args!$fmt)), (), ())
+        $crate::__error_message_internal!(($($head)*$fmt), (), ())
+    };
+    (($($head:tt)*), $fmt:tt, ($(,)?; $attachment:ident($span:expr) = $fmt_str:literal $($tail:tt)*)) => {
+        $crate::__error_message_internal!(($($head)*$fmt.$attachment[$span]), ($fmt_str), ($($tail)*))
     };
     (($($head:tt)*), $fmt:tt, ($(,)?; $attachment:ident = $fmt_str:literal $($tail:tt)*)) => {
-        $crate::__error_message_internal!(($($head)*(::core::format_args!$fmt).$attachment), ($fmt_str), ($($tail)*))
+        $crate::__error_message_internal!(($($head)*$fmt.$attachment), ($fmt_str), ($($tail)*))
+    };
+    // ; ident = || lazy
+    (($($head:tt)*), $fmt:tt, ($(,)?; $attachment:ident$(($span:expr))? = || $lazy:expr $(,)? $(; $($tail:tt)*)?)) => {
+        $crate::__error_message_internal!(($($head)*$fmt.$attachment$([$span])?), (@lazy || $lazy), ($(; $($tail)*)?))
+    };
+    (($($head:tt)*), $fmt:tt, ($(,)?; $attachment:ident$(($span:expr))? = move || $lazy:expr $(,)? $(; $($tail:tt)*)?)) => {
+        $crate::__error_message_internal!(($($head)*$fmt.$attachment$([$span])?), (@lazy move || $lazy), ($(; $($tail)*)?))
+    };
+    // ; ident = iterable
+    (($($head:tt)*), $fmt:tt, ($(,)?; $attachment:ident($span:expr) = $iter:expr $(,)? $(; $($tail:tt)*)?)) => {
+        $crate::__error_message_internal!(($($head)*$fmt.$attachment[$span]), (@iter $iter), ($(; $($tail)*)?))
+    };
+    (($($head:tt)*), $fmt:tt, ($(,)?; $attachment:ident = $iter:expr $(,)? $(; $($tail:tt)*)?)) => {
+        $crate::__error_message_internal!(($($head)*$fmt.$attachment), (@iter $iter), ($(; $($tail)*)?))
+    };
+    // ; ..iterable of (label, message)
+    (($($head:tt)*), $fmt:tt, ($(,)?; ..$pairs:expr $(,)? $(; $($tail:tt)*)?)) => {
+        $crate::__error_message_internal!(($($head)*$fmt.attachments), (@pairs $pairs), ($(; $($tail)*)?))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __error_message_attachment {
+    ($error:ident, $fn:ident[$span:expr], @iter $iter:expr) => {{
+        let span = $crate::span_range!($span);
+        ::core::iter::IntoIterator::into_iter($iter)
+            .fold($error, |error, msg| error.spanned_attachment(span.clone(), ::core::stringify!($fn), msg))
+    }};
+    ($error:ident, $fn:ident, @iter $iter:expr) => {
+        $error.attachments(::core::iter::IntoIterator::into_iter($iter).map(|msg| (::core::stringify!($fn), msg)))
+    };
+    ($error:ident, $fn:ident, @pairs $pairs:expr) => {
+        $error.attachments($pairs)
+    };
+    ($error:ident, $fn:ident[$span:expr], @lazy $lazy:expr) => {
+        $error.spanned_attachment_with($crate::span_range!($span), ::core::stringify!($fn), $lazy)
+    };
+    ($error:ident, $fn:ident, @lazy $lazy:expr) => {
+        $error.attachment_with(::core::stringify!($fn), $lazy)
+    };
+    ($error:ident, $fn:ident, @code $code:expr) => {
+        $error.code($code)
+    };
+    ($error:ident, $fn:ident[$span:expr], $($fmt:tt)*) => {
+        $error.spanned_attachment_text($crate::span_range!($span), ::core::stringify!($fn), $crate::__private::args_text(::core::format_args!($($fmt)*)))
+    };
+    ($error:ident, $fn:ident, $($fmt:tt)*) => {
+        $error.attachment_text(::core::stringify!($fn), $crate::__private::args_text(::core::format_args!($($fmt)*)))
     };
 }
 
@@ -99,20 +173,150 @@ macro_rules! __error_message_internal {
 /// "
 /// );
 /// ```
+///
+/// Instead of a format string, an attachment can also be an iterable of
+/// messages implementing [`Display`](std::fmt::Display), attaching each with
+/// the same label. And `..` followed by an iterable of `(label, message)`
+/// pairs attaches each with its own label, see [`ErrorMessage::attachments`].
+///
+/// ```
+/// # use manyhow::error_message;
+/// let notes = vec!["first note", "second note"];
+/// let hints = [("help", "try this"), ("hint", "or this")];
+/// assert_eq!(
+///     error_message!("error"; note = &notes; ..hints; info = "done").to_string(),
+///     "error
+///
+///   = note: first note
+///   = note: second note
+///   = help: try this
+///   = hint: or this
+///   = info: done
+/// "
+/// );
+/// ```
+///
+/// An attachment can also be a closure, only rendering the message when the
+/// error is rendered, see [`ErrorMessage::attachment_with`].
+///
+/// ```
+/// # use manyhow::error_message;
+/// let candidates = vec!["a", "b"];
+/// let error = error_message!("unknown option"; help = move || candidates.join(", "));
+/// assert_eq!(error.to_string(), "unknown option\n\n  = help: a, b\n");
+/// ```
+///
+/// `code = ...` sets the code of the error, see [`ErrorMessage::code`], instead
+/// of adding an attachment. This is supported by [`bail!`] and [`emit!`] as
+/// well.
+///
+/// ```
+/// # use manyhow::error_message;
+/// let error = error_message!("unknown option"; code = "my_macro::unknown_option"; help = "remove it");
+/// assert_eq!(error.to_string(), "unknown option\n\n  = help: remove it\n");
+/// ```
+///
+/// Attachments can point to a different span, emitting them as a separate
+/// error at that span, see [`ErrorMessage::spanned_attachment`].
+///
+/// ```
+/// # use proc_macro2::Span;
+/// # use manyhow::{error_message, ToTokensError};
+/// # use quote::quote;
+/// # let (dup_span, original_span) = (Span::call_site(), Span::call_site());
+/// let error = error_message!(dup_span, "duplicate key"; note(original_span) = "first defined here");
+/// assert_eq!(
+///     error.to_token_stream().to_string(),
+///     quote! {
+///         ::core::compile_error! { "duplicate key" }
+///         ::core::compile_error! { "note: first defined here" }
+///     }
+///     .to_string()
+/// );
+/// ```
 #[macro_export]
 macro_rules! error_message {
-    ($fmt:literal $($tt:tt)*) => {
+    ($fmt:literal $($tt:tt)*) => {{
+        $crate::__record_error_site!();
         $crate::__error_message_internal!((cs), ($fmt), ($($tt)*))
-    };
-    ($span:expr, $fmt:literal $($tt:tt)*) => {
+    }};
+    ($span:expr, $fmt:literal $($tt:tt)*) => {{
+        $crate::__record_error_site!();
         $crate::__error_message_internal!((new($span)), ($fmt), ($($tt)*))
-    };
+    }};
+}
+
+/// Creates an [`ErrorMessage`] spanning the tokens of an expression, like
+/// [`ErrorMessage::spanned`].
+///
+/// Unlike [`error_message!`], the first argument is always taken as the
+/// expression to span, and only needs to implement [`ToTokens`], even if it
+/// also implements [`SpanRanged`]. The remaining arguments have the syntax of
+/// [`error_message!`].
+/// ```
+/// # use manyhow::spanned_error;
+/// # use syn2 as syn;
+/// let field: syn::Field = syn::parse_quote!(value: Vec<u8>);
+/// let error = spanned_error!(field.ty, "unsupported type for `{}`", "value"; help = "use a `String`");
+/// assert_eq!(
+///     error.to_string(),
+///     "unsupported type for `value`\n\n  = help: use a `String`\n"
+/// );
+/// ```
+#[macro_export]
+macro_rules! spanned_error {
+    ($tokens:expr, $fmt:literal $($tt:tt)*) => {{
+        $crate::__record_error_site!();
+        $crate::__error_message_internal!((new($crate::to_tokens_span_range(&$tokens))), ($fmt), ($($tt)*))
+    }};
 }
 
 /// Exit by returning error, matching [`anyhow::bail!`](https://docs.rs/anyhow/latest/anyhow/macro.bail.html).
 ///
 /// The syntax is identical to [`error_message!`], the only difference is, that
 /// a single expression with an error is supported as well.
+///
+/// Attachments can be added to such an error as well. As its span is unknown,
+/// each attachment is emitted as a separate error, like spanned attachments in
+/// [`error_message!`].
+/// ```
+/// # use manyhow::{bail, Error};
+/// # use proc_macro2::Span;
+/// # use syn2 as syn;
+/// # let result = (|| -> Result<(), Error> {
+/// let error = syn::Error::new(Span::call_site(), "an error");
+/// bail!(error; note = "while parsing {}", "the input"; help(Span::call_site()) = "remove it");
+/// # })();
+/// # use manyhow::ToTokensError;
+/// # proc_macro_utils::assert_tokens!(result.unwrap_err().into_token_stream(), {
+/// #     ::core::compile_error!{"an error"}
+/// #     ::core::compile_error!{"note: while parsing the input"}
+/// #     ::core::compile_error!{"help: remove it"}
+/// # });
+/// ```
+///
+/// To also replace the dummy, the `dummy` of the handler can be set before the
+/// other arguments. It can either be a [`&mut Dummy`](crate::Dummy) or a `&mut
+/// TokenStream`.
+/// ```
+/// # use manyhow::{bail, Dummy, Result};
+/// # use proc_macro2::{Span, TokenStream};
+/// # use quote::quote;
+/// fn my_macro(input: TokenStream, dummy: &mut Dummy) -> Result {
+///     if input.is_empty() {
+///         bail!(
+///             dummy = quote! { fn stub() {} },
+///             Span::call_site(),
+///             "expected input"
+///         );
+///     }
+///     Ok(input)
+/// }
+/// # let mut dummy = Dummy::default();
+/// # assert!(my_macro(TokenStream::new(), &mut dummy).is_err());
+/// # assert_eq!(dummy.to_string(), quote! { fn stub() {} }.to_string());
+/// ```
+///
 /// ```should_panic
 /// # use manyhow::bail;
 /// # use proc_macro2::Span;
@@ -129,14 +333,67 @@ macro_rules! bail {
     ($msg:literal) => {
         return ::core::result::Result::Err($crate::error_message!($msg).into());
     };
-    ($error:expr) => {
-        return ::core::result::Result::Err($error.into());
+    ($msg:literal $($tt:tt)*) => {
+        return ::core::result::Result::Err($crate::error_message!($msg $($tt)*).into());
     };
+    ($dummy:ident = $tokens:expr, $($tt:tt)+) => {{
+        $crate::__private::SetDummy::__set_dummy(
+            $dummy,
+            $crate::__private::quote::ToTokens::into_token_stream($tokens),
+        );
+        $crate::bail!($($tt)+);
+    }};
+    ($error:expr) => {{
+        $crate::__record_error_site!();
+        return ::core::result::Result::Err($error.into());
+    }};
+    ($error:expr; $($attachments:tt)+) => {{
+        $crate::__record_error_site!();
+        let mut error = $crate::Error::from($error);
+        $crate::__push_attachments!(error; $($attachments)+);
+        return ::core::result::Result::Err(error.into());
+    }};
     ($($tt:tt)*) => {
         return ::core::result::Result::Err($crate::error_message!($($tt)*).into());
     };
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __push_attachments {
+    ($error:ident $(;)?) => {};
+    ($error:ident; $attachment:ident($span:expr) = $($tail:tt)*) => {
+        $crate::__push_attachments!($error, ($attachment[$span]), (), ($($tail)*))
+    };
+    ($error:ident; $attachment:ident = $($tail:tt)*) => {
+        $crate::__push_attachments!($error, ($attachment), (), ($($tail)*))
+    };
+    // collects the format arguments up to the next `;`
+    ($error:ident, $head:tt, ($($fmt:tt)*), ($(; $($tail:tt)*)?)) => {
+        $crate::__push_attachment!($error, $head, ($($fmt)*));
+        $crate::__push_attachments!($error; $($($tail)*)?)
+    };
+    ($error:ident, $head:tt, ($($fmt:tt)*), ($next:tt $($tail:tt)*)) => {
+        $crate::__push_attachments!($error, $head, ($($fmt)* $next), ($($tail)*))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __push_attachment {
+    ($error:ident, ($attachment:ident[$span:expr]), ($($fmt:tt)*)) => {
+        $error.push($crate::ErrorMessage::new(
+            $crate::span_range!($span),
+            ::core::format_args!("{}: {}", ::core::stringify!($attachment), ::core::format_args!($($fmt)*)),
+        ))
+    };
+    ($error:ident, ($attachment:ident), ($($fmt:tt)*)) => {
+        $error.push($crate::ErrorMessage::call_site(
+            ::core::format_args!("{}: {}", ::core::stringify!($attachment), ::core::format_args!($($fmt)*)),
+        ))
+    };
+}
+
 /// Return early with an error, if a condition is not satisfied, matching
 /// [`anyhow::ensure!`](https://docs.rs/anyhow/latest/anyhow/macro.ensure.html).
 ///
@@ -163,8 +420,40 @@ macro_rules! bail {
 /// ensure!(false, error);
 /// # Ok::<_, manyhow::Error>(())
 /// ```
+///
+/// Without any further arguments, the condition is added as a note to the
+/// error, like [`anyhow::ensure!`](https://docs.rs/anyhow/latest/anyhow/macro.ensure.html)
+/// does for its default message.
+/// ```
+/// # use manyhow::{ensure, ErrorMessage};
+/// fn check(args: &[u8]) -> Result<(), ErrorMessage> {
+///     ensure!(args.len() <= 4);
+///     Ok(())
+/// }
+///
+/// assert_eq!(
+///     check(&[0; 5]).unwrap_err().to_string(),
+///     "assertion failed\n\n  = note: condition `args.len() <= 4` failed\n"
+/// );
+/// ```
 #[macro_export]
 macro_rules! ensure {
+    ($cond:expr $(,)?) => {
+        if !$cond {
+            $crate::bail!(
+                "assertion failed";
+                note = "condition `{}` failed", ::core::stringify!($cond)
+            );
+        }
+    };
+    (let $pat:pat = $expr:expr $(,)?) => {
+        let $pat = $expr else {
+            $crate::bail!(
+                "assertion failed";
+                note = "condition `{}` failed", ::core::stringify!(let $pat = $expr)
+            );
+        };
+    };
     ($cond:expr, $($bail_args:tt)*) => {
         if !$cond {
             $crate::bail!($($bail_args)*);
@@ -177,6 +466,226 @@ macro_rules! ensure {
     };
 }
 
+/// Return early with an error, if a condition is satisfied, the inverse of
+/// [`ensure!`].
+///
+/// The syntax is identical to [`bail!`], with an additional leading condition.
+/// ```
+/// # use manyhow::{bail_if, ErrorMessage};
+/// # use proc_macro2::Span;
+/// fn check(args: &[&str]) -> Result<(), ErrorMessage> {
+///     bail_if!(args.is_empty(), Span::call_site(), "expected arguments"; help = "add an argument");
+///     bail_if!(args.len() > 4, "at most 4 arguments");
+///     Ok(())
+/// }
+///
+/// assert!(check(&["a"]).is_ok());
+/// assert_eq!(
+///     check(&[]).unwrap_err().to_string(),
+///     "expected arguments\n\n  = help: add an argument\n"
+/// );
+/// ```
+#[macro_export]
+macro_rules! bail_if {
+    ($cond:expr, $($bail_args:tt)*) => {
+        if $cond {
+            $crate::bail!($($bail_args)*);
+        }
+    };
+}
+
+/// Emit an error, if a condition is not satisfied, and continue with a
+/// fallback instead of returning.
+///
+/// The first argument is the [`Emitter`], like [`emit!`], followed by the
+/// condition, like [`ensure!`]. The fallback expression is evaluated in place
+/// of returning the error, e.g., to replace an invalid value with a default or
+/// to `continue` with the next item. The remaining arguments have the syntax of
+/// [`emit!`].
+/// ```
+/// # use manyhow::{ensure_or, Emitter};
+/// # use proc_macro2::Span;
+/// let mut emitter = Emitter::new();
+/// let mut count = 5;
+/// ensure_or!(emitter, count <= 4, count = 4, Span::call_site(), "at most 4 arguments"; help = "remove {}", count - 4);
+/// assert_eq!(count, 4);
+///
+/// let mut names = Vec::new();
+/// for name in ["a", "", "b"] {
+///     ensure_or!(emitter, !name.is_empty(), continue, "empty name");
+///     names.push(name);
+/// }
+/// assert_eq!(names, ["a", "b"]);
+/// # assert!(emitter.into_result().is_err());
+/// ```
+///
+/// Like [`ensure!`], the condition can also be a `let ... = ...` pattern
+/// matching, which requires the fallback to diverge.
+/// ```
+/// # use manyhow::{ensure_or, Emitter};
+/// # let mut emitter = Emitter::new();
+/// for arg in [Some(1), None] {
+///     ensure_or!(emitter, let Some(arg) = arg, continue, "missing argument");
+///     assert_eq!(arg, 1);
+/// }
+/// # assert!(emitter.into_result().is_err());
+/// ```
+#[macro_export]
+macro_rules! ensure_or {
+    ($emitter:expr, $cond:expr, $fallback:expr, $($emit_args:tt)*) => {
+        if !$cond {
+            $crate::emit!($emitter, $($emit_args)*);
+            $fallback;
+        }
+    };
+    ($emitter:expr, let $pat:pat = $expr:expr, $fallback:expr, $($emit_args:tt)*) => {
+        let $pat = $expr else {
+            $crate::emit!($emitter, $($emit_args)*);
+            $fallback
+        };
+    };
+}
+
+/// Return early with an error, if two expressions are not equal, matching
+/// [`assert_eq!`].
+///
+/// Both operands are added to the error as `left` and `right` attachments,
+/// using their [`Debug`](std::fmt::Debug) representation. Optionally followed
+/// by a span and/or a message, with the syntax of [`error_message!`].
+/// ```
+/// # use manyhow::{ensure_eq, ErrorMessage};
+/// # use proc_macro2::Span;
+/// fn check(count: usize) -> Result<(), ErrorMessage> {
+///     ensure_eq!(count, 2);
+///     ensure_eq!(count, 2, Span::call_site());
+///     ensure_eq!(count, 2, "expected {} arguments", 2; help = "remove the extra arguments");
+///     Ok(())
+/// }
+///
+/// assert_eq!(
+///     check(1).unwrap_err().to_string(),
+///     "assertion `left == right` failed\n\n  = left: 1\n  = right: 2\n"
+/// );
+/// ```
+#[macro_export]
+macro_rules! ensure_eq {
+    ($left:expr, $right:expr, $($tt:tt)*) => {
+        match (&$left, &$right) {
+            (left, right) => if !(*left == *right) {
+                $crate::__ensure_failed!(
+                    "assertion `left == right` failed",
+                    ::core::format_args!("{:?}", left),
+                    ::core::format_args!("{:?}", right);
+                    $($tt)*
+                );
+            }
+        }
+    };
+    ($left:expr, $right:expr) => {
+        $crate::ensure_eq!($left, $right,)
+    };
+}
+
+/// Return early with an error, if two expressions are equal, matching
+/// [`assert_ne!`].
+///
+/// The syntax is identical to [`ensure_eq!`].
+/// ```
+/// # use manyhow::{ensure_ne, ErrorMessage};
+/// fn check(name: &str) -> Result<(), ErrorMessage> {
+///     ensure_ne!(name, "self", "`self` is reserved");
+///     Ok(())
+/// }
+///
+/// assert_eq!(
+///     check("self").unwrap_err().to_string(),
+///     "`self` is reserved\n\n  = left: \"self\"\n  = right: \"self\"\n"
+/// );
+/// ```
+#[macro_export]
+macro_rules! ensure_ne {
+    ($left:expr, $right:expr, $($tt:tt)*) => {
+        match (&$left, &$right) {
+            (left, right) => if *left == *right {
+                $crate::__ensure_failed!(
+                    "assertion `left != right` failed",
+                    ::core::format_args!("{:?}", left),
+                    ::core::format_args!("{:?}", right);
+                    $($tt)*
+                );
+            }
+        }
+    };
+    ($left:expr, $right:expr) => {
+        $crate::ensure_ne!($left, $right,)
+    };
+}
+
+/// Return early with an error, if an expression does not match a pattern,
+/// matching [`matches!`].
+///
+/// The value is added to the error as `left` attachment, using its
+/// [`Debug`](std::fmt::Debug) representation, the pattern as `right`
+/// attachment. Optionally followed by a span and/or a message, with the
+/// syntax of [`error_message!`].
+/// ```
+/// # use manyhow::{ensure_matches, ErrorMessage};
+/// fn check(value: Option<u8>) -> Result<(), ErrorMessage> {
+///     ensure_matches!(value, Some(1..=9));
+///     ensure_matches!(value, Some(v) if v % 2 == 1, "expected odd digit");
+///     Ok(())
+/// }
+///
+/// assert_eq!(
+///     check(Some(10)).unwrap_err().to_string(),
+///     "assertion `left matches right` failed\n\n  = left: Some(10)\n  = right: Some(1..=9)\n"
+/// );
+/// ```
+#[macro_export]
+macro_rules! ensure_matches {
+    ($expr:expr, $pat:pat $(if $guard:expr)?, $($tt:tt)*) => {
+        match $expr {
+            $pat $(if $guard)? => {}
+            ref left => $crate::__ensure_failed!(
+                "assertion `left matches right` failed",
+                ::core::format_args!("{:?}", left),
+                ::core::stringify!($pat $(if $guard)?);
+                $($tt)*
+            ),
+        }
+    };
+    ($expr:expr, $pat:pat $(if $guard:expr)?) => {
+        $crate::ensure_matches!($expr, $pat $(if $guard)?,)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __ensure_failed {
+    ($default:literal, $left:expr, $right:expr;) => {
+        $crate::__ensure_failed!($default, $left, $right; $default)
+    };
+    ($default:literal, $left:expr, $right:expr; $fmt:literal $($tt:tt)*) => {
+        return ::core::result::Result::Err(
+            $crate::error_message!($fmt $($tt)*)
+                .attachment("left", $left)
+                .attachment("right", $right)
+                .into(),
+        )
+    };
+    ($default:literal, $left:expr, $right:expr; $span:expr $(,)?) => {
+        $crate::__ensure_failed!($default, $left, $right; $span, $default)
+    };
+    ($default:literal, $left:expr, $right:expr; $span:expr, $fmt:literal $($tt:tt)*) => {
+        return ::core::result::Result::Err(
+            $crate::error_message!($span, $fmt $($tt)*)
+                .attachment("left", $left)
+                .attachment("right", $right)
+                .into(),
+        )
+    };
+}
+
 /// Push an error to an emitter.
 ///
 /// The syntax is identical to [`error_message!`] and [`bail!`], but the first
@@ -213,23 +722,113 @@ macro_rules! ensure {
 /// ```
 #[macro_export]
 macro_rules! emit {
-    ($emitter:expr, $msg:literal) => {
+    ($emitter:expr, $msg:literal) => {{
         $emitter.extend(::core::iter::once::<$crate::ErrorMessage>($crate::error_message!($msg)));
-    };
-    ($emitter:expr, $error:expr) => {
+    }};
+    ($emitter:expr, $error:expr) => {{
+        $crate::__record_error_site!();
         $emitter.extend(::core::iter::once($error));
-    };
-    ($emitter:expr, $($tt:tt)*) => {
+    }};
+    ($emitter:expr, $($tt:tt)*) => {{
         $emitter.extend(::core::iter::once::<$crate::ErrorMessage>($crate::error_message!($($tt)*).into()));
+    }};
+}
+
+/// Push an error message to an [`Emitter`], evaluating to an
+/// [`EmittedMessage`](crate::EmittedMessage) to add attachments afterwards.
+///
+/// The syntax is identical to [`emit!`], but only supports creating an
+/// [`ErrorMessage`] and pushing it to an [`Emitter`]. The message is pushed
+/// immediately, the handle only refers to it.
+/// ```
+/// # use manyhow::{emit_handle, Emitter};
+/// # use proc_macro2::Span;
+/// # let (mut emitter, span, reserved) = (Emitter::new(), Span::call_site(), true);
+/// let mut error = emit_handle!(emitter, span, "invalid name");
+/// if reserved {
+///     error.help("the name is reserved").note("see the docs");
+/// }
+/// ```
+#[macro_export]
+macro_rules! emit_handle {
+    ($emitter:expr, $($tt:tt)*) => {
+        $emitter.emit_message($crate::error_message!($($tt)*))
     };
 }
 
+/// Unwrap a result, or emit its error and continue with a fallback, the
+/// [`Emitter`] equivalent of `?`.
+///
+/// The first argument is the [`Emitter`] (or anything else supported by
+/// [`emit!`]), followed by an expression evaluating to a [`Result`] whose error
+/// implements [`ToTokensError`](crate::ToTokensError). On `Ok` the macro
+/// evaluates to the value, on `Err` the error is emitted and the fallback is
+/// evaluated instead, which can also diverge, e.g., with `continue`.
+/// ```
+/// # use manyhow::{try_emit, Emitter};
+/// # use proc_macro2::TokenStream;
+/// # use syn2 as syn;
+/// let mut emitter = Emitter::new();
+/// let mut idents = Vec::new();
+/// for input in ["a", "1", "b"] {
+///     let input: TokenStream = input.parse().unwrap();
+///     idents.push(try_emit!(emitter, syn::parse2::<syn::Ident>(input), continue));
+/// }
+/// assert_eq!(idents.len(), 2);
+///
+/// let count = syn::parse_str::<syn::LitInt>("x").and_then(|lit| lit.base10_parse::<u8>());
+/// let count = try_emit!(emitter, count, 0);
+/// assert_eq!(count, 0);
+/// assert_eq!(emitter.messages().len(), 2);
+/// ```
+#[macro_export]
+macro_rules! try_emit {
+    ($emitter:expr, $expr:expr, $fallback:expr $(,)?) => {
+        match $expr {
+            ::core::result::Result::Ok(value) => value,
+            ::core::result::Result::Err(error) => {
+                $crate::emit!($emitter, error);
+                $fallback
+            }
+        }
+    };
+}
+
+/// Partition results, emitting every error and collecting the values into a
+/// [`Vec`].
+///
+/// The first argument is the [`Emitter`] (or anything else supported by
+/// [`emit!`]), followed by an [`IntoIterator`] of [`Result`]s whose errors
+/// implement [`ToTokensError`](crate::ToTokensError).
+/// ```
+/// # use manyhow::{collect_errors, Emitter};
+/// # use syn2 as syn;
+/// let mut emitter = Emitter::new();
+/// let idents = collect_errors!(
+///     emitter,
+///     ["a", "1", "b", "2"].map(syn::parse_str::<syn::Ident>)
+/// );
+/// assert_eq!(idents.len(), 2);
+/// assert_eq!(emitter.messages().len(), 2);
+/// ```
+#[macro_export]
+macro_rules! collect_errors {
+    ($emitter:expr, $results:expr $(,)?) => {{
+        let mut values = ::std::vec::Vec::new();
+        for result in $results {
+            values.push($crate::try_emit!($emitter, result, continue));
+        }
+        values
+    }};
+}
+
 #[cfg(test)]
 mod test {
     use proc_macro::Span;
+    use proc_macro2::TokenStream;
     use quote::quote;
 
-    use crate::{Emitter, ErrorMessage};
+    use crate::{Emitter, Error, ErrorMessage, ToTokensError};
 
     macro_rules! returned {
         ($ty:ty, $expr:expr) => {
@@ -261,6 +860,130 @@ mod test {
                 .to_string(),
             "format 1 4 2 ho"
         );
+
+        let error = || error_message!("error");
+        let span = proc_macro2::Span::call_site();
+        assert_eq!(
+            returned!(Result<(), Error>, bail!(error(); note = "{}", { let a = 1; a + 1 }; help(span) = "help {a}", a = 3;))
+                .unwrap_err()
+                .into_token_stream()
+                .to_string(),
+            quote! {
+                ::core::compile_error! { "error" }
+                ::core::compile_error! { "note: 2" }
+                ::core::compile_error! { "help: help 3" }
+            }
+            .to_string()
+        );
+        assert_eq!(
+            returned!(Result<(), Error>, ensure!(false, error(); note = "note"))
+                .unwrap_err()
+                .into_token_stream()
+                .to_string(),
+            quote! {
+                ::core::compile_error! { "error" }
+                ::core::compile_error! { "note: note" }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn bail_dummy() {
+        let dummy = &mut TokenStream::new();
+        assert_eq!(
+            returned!(Result<(), ErrorMessage>, bail!(dummy = quote!(dummy), "error {}", 1))
+                .unwrap_err()
+                .to_string(),
+            "error 1"
+        );
+        assert_eq!(dummy.to_string(), "dummy");
+
+        let dummy = &mut crate::Dummy::from(quote!(seed));
+        let span = proc_macro2::Span::call_site();
+        assert_eq!(
+            returned!(Result<(), ErrorMessage>, bail!(dummy = quote!(dummy), span, "error"))
+                .unwrap_err()
+                .to_string(),
+            "error"
+        );
+        assert_eq!(dummy.to_string(), "dummy");
+        assert!(!dummy.is_seed());
+    }
+
+    #[test]
+    fn ensure_condition() {
+        let value = Some(1);
+        assert_eq!(
+            returned!(Result<(), ErrorMessage>, ensure!(value.is_none(),))
+                .unwrap_err()
+                .to_string(),
+            "assertion failed\n\n  = note: condition `value.is_none()` failed\n"
+        );
+        assert_eq!(
+            returned!(Result<(), ErrorMessage>, ensure!(let None = value))
+                .unwrap_err()
+                .to_string(),
+            "assertion failed\n\n  = note: condition `let None = value` failed\n"
+        );
+        let ok = || -> Result<(), ErrorMessage> {
+            ensure!(value.is_some());
+            ensure!(let Some(_) = value);
+            Ok(())
+        };
+        assert!(ok().is_ok());
+    }
+
+    #[test]
+    fn bail_if() {
+        assert_eq!(
+            returned!(Result<(), ErrorMessage>, bail_if!(1 + 1 == 2, "format {}", 1; note = "note"))
+                .unwrap_err()
+                .to_string(),
+            "format 1\n\n  = note: note\n"
+        );
+        let ok = || -> Result<(), ErrorMessage> {
+            bail_if!(false, "unreachable");
+            Ok(())
+        };
+        assert!(ok().is_ok());
+    }
+
+    #[test]
+    fn ensure_cmp() {
+        assert_eq!(
+            returned!(Result<(), ErrorMessage>, ensure_eq!(1 + 1, 3))
+                .unwrap_err()
+                .to_string(),
+            "assertion `left == right` failed\n\n  = left: 2\n  = right: 3\n"
+        );
+        assert_eq!(
+            returned!(Result<(), ErrorMessage>, ensure_eq!("a", "b", proc_macro2::Span::call_site(),))
+                .unwrap_err()
+                .to_string(),
+            "assertion `left == right` failed\n\n  = left: \"a\"\n  = right: \"b\"\n"
+        );
+        assert_eq!(
+            returned!(Result<(), ErrorMessage>, ensure_ne!(1, 1, "{} twice", 1; help = "remove one"))
+                .unwrap_err()
+                .to_string(),
+            "1 twice\n\n  = help: remove one\n  = left: 1\n  = right: 1\n"
+        );
+        assert_eq!(
+            returned!(Result<(), ErrorMessage>, ensure_matches!(Some(2), Some(a) if a > 2))
+                .unwrap_err()
+                .to_string(),
+            "assertion `left matches right` failed\n\n  = left: Some(2)\n  = right: Some(a) if a \
+             > 2\n"
+        );
+
+        let ok = || -> Result<(), ErrorMessage> {
+            ensure_eq!(1, 1);
+            ensure_ne!(1, 2, "unreachable");
+            ensure_matches!(Some(1), Some(1 | 2),);
+            Ok(())
+        };
+        assert!(ok().is_ok());
     }
 
     #[test]
@@ -307,6 +1030,195 @@ mod test {
         }
     }
 
+    #[test]
+    fn emit_handle() {
+        let emitter = &mut Emitter::new();
+        emit_handle!(emitter, "first").help("help");
+        let mut error = emit_handle!(emitter, "second {}", 2);
+        for note in ["a", "b"] {
+            error.note(note);
+        }
+        emit_handle!(emitter, proc_macro2::Span::call_site(), "third").warning("warning");
+        assert_eq!(
+            emitter
+                .into_result()
+                .unwrap_err()
+                .into_token_stream()
+                .to_string(),
+            quote! {
+                ::core::compile_error! { "first\n\n  = help: help\n" }
+                ::core::compile_error! { "second 2\n\n  = note: a\n  = note: b\n" }
+                ::core::compile_error! { "third\n\n  = warning: warning\n" }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    #[allow(
+        clippy::let_unit_value,
+        clippy::match_bool,
+        clippy::semicolon_if_nothing_returned,
+        clippy::single_match
+    )]
+    fn emit_unit() {
+        let mut emitter = Emitter::new();
+        let errors = &mut Vec::new();
+        for c in [true, false] {
+            if c {
+                emit!(emitter, "a")
+            }
+            match c {
+                true => emit!(errors, "b {}", 1),
+                false => (),
+            }
+            let x = emit!(emitter, "c");
+            assert!(!emitter.is_empty());
+            let () = x;
+        }
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            emitter.into_result().unwrap_err().to_token_stream().to_string(),
+            quote! {
+                ::core::compile_error! { "a" }
+                ::core::compile_error! { "c" }
+                ::core::compile_error! { "c" }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn ensure_or() {
+        let mut emitter = Emitter::new();
+        let mut value = 0;
+        ensure_or!(emitter, value > 0, value = 1, "positive {}", "value"; note = "was {value}");
+        ensure_or!(emitter, value > 0, value = 2, "unreachable");
+        assert_eq!(value, 1);
+        let values: Vec<_> = [Some(1), None, Some(2)]
+            .into_iter()
+            .filter_map(|value| {
+                ensure_or!(emitter, let Some(value) = value, return None, "missing");
+                Some(value)
+            })
+            .collect();
+        assert_eq!(values, [1, 2]);
+        assert_eq!(
+            emitter.into_result().unwrap_err().to_token_stream().to_string(),
+            quote! {
+                ::core::compile_error! { "positive value\n\n  = note: was 0\n" }
+                ::core::compile_error! { "missing" }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn error_message_lazy() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        let span = proc_macro2::Span::call_site();
+        let error = error_message!(span, "test"; help = move || {
+            counter.set(counter.get() + 1);
+            "help"
+        }; note(span) = || "note",);
+        assert_eq!(calls.get(), 0);
+        assert_eq!(
+            error.into_token_stream().to_string(),
+            quote! {
+                ::core::compile_error! { "test\n\n  = help: help\n" }
+                ::core::compile_error! { "note: note" }
+            }
+            .to_string()
+        );
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn try_emit() {
+        let mut emitter = Emitter::new();
+        assert_eq!(try_emit!(emitter, Ok::<_, ErrorMessage>(1), 0), 1);
+        assert_eq!(try_emit!(emitter, Err(error_message!("error")), 0,), 0);
+        let mut errors = Vec::new();
+        let value: Option<u8> = try_emit!(errors, Err(error_message!("error")), None);
+        assert!(value.is_none());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            emitter.into_result().unwrap_err().to_token_stream().to_string(),
+            quote! { ::core::compile_error! { "error" } }.to_string()
+        );
+    }
+
+    #[test]
+    fn collect_errors() {
+        let mut emitter = Emitter::new();
+        let results = (0..4).map(|i| {
+            if i % 2 == 0 {
+                Ok(i)
+            } else {
+                Err(error_message!("odd {i}"))
+            }
+        });
+        assert_eq!(collect_errors!(emitter, results), [0, 2]);
+        let values: Vec<()> = collect_errors!(emitter, Vec::<Result<_, ErrorMessage>>::new(),);
+        assert!(values.is_empty());
+        assert_eq!(
+            emitter.into_result().unwrap_err().to_token_stream().to_string(),
+            quote! {
+                ::core::compile_error! { "odd 1" }
+                ::core::compile_error! { "odd 3" }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn error_message_dynamic() {
+        let notes: Vec<String> = (1..=2).map(|i| format!("note {i}")).collect();
+        assert_eq!(
+            error_message!("test {}", 1; note = notes.iter(),; help = "help").to_string(),
+            "test 1\n\n  = note: note 1\n  = note: note 2\n  = help: help\n"
+        );
+        let pairs = vec![("help", "a".to_owned()), ("note", "b".to_owned())];
+        assert_eq!(
+            error_message!("test"; ..pairs).to_string(),
+            "test\n\n  = help: a\n  = note: b\n"
+        );
+        let span = proc_macro2::Span::call_site();
+        assert_eq!(
+            error_message!(span, "test"; note(span) = ["a", "b"])
+                .into_token_stream()
+                .to_string(),
+            quote! {
+                ::core::compile_error! { "test" }
+                ::core::compile_error! { "note: a" }
+                ::core::compile_error! { "note: b" }
+            }
+            .to_string()
+        );
+        assert_eq!(
+            error_message!("test"; note = Vec::<String>::new()).to_string(),
+            "test"
+        );
+        assert_eq!(
+            error_message!("test"; note = vec![String::new()]; help = [""]).to_string(),
+            "test\n\n  = note:\n  = help:\n"
+        );
+    }
+
+    #[test]
+    fn spanned_error() {
+        let tokens: TokenStream = "a b c".parse().unwrap();
+        let error = spanned_error!(tokens, "error {}", 1; note = "note");
+        for tt in error.into_token_stream() {
+            assert_eq!(tt.span().start().column, 0);
+            assert_eq!(tt.span().end().column, 5);
+        }
+    }
+
     // Only tests that it compiles
     fn _error_message_spanned() {
         let span = Span::call_site();
@@ -4,6 +4,7 @@ use std::marker::PhantomData;
 
 use proc_macro2::TokenStream;
 
+use crate::__private::LazyDummy;
 use crate::{
     AnyTokenStream, AttributeMacroHandler, DeriveMacroHandler, Emitter, FunctionMacroHandler,
     ToTokensError,
@@ -46,27 +47,15 @@ impl<T> Clone for WhatType<T> {
 
 impl<T> Copy for WhatType<T> {}
 
-impl<T: Into<TokenStream> + From<TokenStream>> ManyhowParse<T> for WhatType<T> {
-    fn manyhow_parse(&self, input: impl AnyTokenStream, _attr: bool) -> Result<T, TokenStream> {
-        Ok(input.into().into())
-    }
-}
-
-impl ManyhowToTokens<TokenStream> for WhatType<TokenStream> {
-    fn manyhow_to_tokens(&self, input: TokenStream, tokens: &mut TokenStream) {
-        tokens.extend(input);
-    }
-}
-
-impl ManyhowToTokens<proc_macro::TokenStream> for WhatType<proc_macro::TokenStream> {
-    fn manyhow_to_tokens(&self, input: proc_macro::TokenStream, tokens: &mut TokenStream) {
-        tokens.extend(TokenStream::from(input));
+impl<T: crate::ManyhowParse> ManyhowParse<T> for WhatType<T> {
+    fn manyhow_parse(&self, input: impl AnyTokenStream, attr: bool) -> Result<T, TokenStream> {
+        T::manyhow_parse(input.into(), attr).map_err(|e| e.into_token_stream())
     }
 }
 
-impl<E: ToTokensError> ManyhowToTokens<E> for WhatType<E> {
-    fn manyhow_to_tokens(&self, input: E, tokens: &mut TokenStream) {
-        input.to_tokens(tokens);
+impl<T: crate::ManyhowToTokens> ManyhowToTokens<T> for WhatType<T> {
+    fn manyhow_to_tokens(&self, input: T, tokens: &mut TokenStream) {
+        input.manyhow_to_tokens(tokens);
     }
 }
 
@@ -88,22 +77,84 @@ impl<T> ManyhowTry<T> for &WhatType<T> {
     }
 }
 
-#[cfg(feature = "syn2")]
+#[cfg(feature = "syn2-parse")]
 impl<T: syn2::parse::Parse> ManyhowParse<T> for &WhatType<T> {
     fn manyhow_parse(&self, input: impl AnyTokenStream, attr: bool) -> Result<T, TokenStream> {
         let input = input.into();
         let empty = input.is_empty();
-        syn2::parse2(input).map_err(|e| {
-            let mut e = e.into_compile_error();
-            if attr && empty {
-                error_message!("while parsing attribute argument (`#[... (...)]`)")
-                    .to_tokens(&mut e)
-            }
-            e
-        })
+        crate::parse_complete(T::parse, input).map_err(|e| syn_parse_failed(e, attr && empty))
     }
 }
+
+/// Returns the errors of parsing an input, `empty_attr` if it was the empty
+/// input of an attribute.
+#[cfg(feature = "syn2-parse")]
+fn syn_parse_failed(error: syn2::Error, empty_attr: bool) -> TokenStream {
+    let mut error = error.into_compile_error();
+    if empty_attr {
+        error_message!("while parsing attribute argument (`#[... (...)]`)").to_tokens(&mut error)
+    }
+    error
+}
+
 #[cfg(feature = "syn2")]
+#[test]
+fn tuple_input() {
+    use quote::quote;
+    use syn2::{Ident, LitStr};
+
+    use crate::function;
+
+    let output: TokenStream =
+        function!(quote!(a, "b",), |(a, b): (Ident, LitStr)| -> TokenStream {
+            quote!(#b #a)
+        });
+    assert_eq!(output.to_string(), quote!("b" a).to_string());
+
+    let output: TokenStream = function!(quote!(a, "b", c), |_: (Ident, LitStr)| -> TokenStream {
+        unreachable!()
+    });
+    assert_eq!(
+        output.to_string(),
+        quote!(::core::compile_error! { "expected 2 comma separated arguments\n\n  = help: remove the additional arguments\n" })
+            .to_string()
+    );
+
+    let output: TokenStream = function!(quote!(a), |_: (Ident, LitStr)| -> TokenStream {
+        unreachable!()
+    });
+    assert_eq!(
+        output.to_string(),
+        quote!(::core::compile_error! { "expected 2 comma separated arguments, found 1" })
+            .to_string()
+    );
+
+    // commas inside of arguments do not separate them
+    let output: TokenStream = function!(
+        quote!(HashMap<K, V>, |a, b| a + b, foo::<A, B>()),
+        |(ty, closure, call): (syn2::Type, syn2::Expr, syn2::Expr)| -> TokenStream {
+            quote!(#call #closure #ty)
+        }
+    );
+    assert_eq!(
+        output.to_string(),
+        quote!(foo::<A, B>() |a, b| a + b HashMap<K, V>).to_string()
+    );
+
+    let output: TokenStream = function!(quote!(1, 2), |_: (Ident, LitStr)| -> TokenStream {
+        unreachable!()
+    });
+    assert_eq!(
+        output.to_string(),
+        quote!(
+            ::core::compile_error! { "expected identifier" }
+            ::core::compile_error! { "expected string literal" }
+        )
+        .to_string()
+    );
+}
+
+#[cfg(feature = "syn2-parse")]
 impl<T: quote::ToTokens> ManyhowToTokens<T> for &WhatType<T> {
     fn manyhow_to_tokens(&self, input: T, tokens: &mut TokenStream) {
         input.to_tokens(tokens);
@@ -137,36 +188,96 @@ fn test_inference() {
     }
 }
 
+// The following helpers are not generic, to only be instantiated once, instead
+// of for every macro. The transparent handlers below stay generic over the
+// handler, but only keep the call of the body and delegate everything else.
+
+/// The dummy of a handler, only created when the handler errors.
+pub enum DeferredDummy<'a> {
+    Lazy(LazyDummy<'a>),
+    Taken(TokenStream),
+}
+
+impl DeferredDummy<'_> {
+    fn create(dummy: Option<LazyDummy<'_>>) -> TokenStream {
+        dummy.map_or_else(TokenStream::new, |dummy| dummy())
+    }
+
+    /// Returns the dummy followed by `tokens`.
+    pub fn prepend_to(self, tokens: TokenStream) -> TokenStream {
+        let mut dummy = match self {
+            DeferredDummy::Lazy(dummy) => dummy(),
+            DeferredDummy::Taken(dummy) => dummy,
+        };
+        crate::append_tokens(&mut dummy, tokens);
+        dummy
+    }
+}
+
+/// Returns the emitted errors and the dummy after the handler was called.
+fn handler_called<'a>(
+    taken: TokenStream,
+    dummy: Option<LazyDummy<'a>>,
+    emitter: Option<&Emitter>,
+) -> (TokenStream, DeferredDummy<'a>) {
+    let mut tokens = TokenStream::new();
+    // Handlers without an emitter cannot have emitted any errors.
+    if let Some(emitter) = emitter {
+        emitter.to_tokens(&mut tokens);
+    }
+    (tokens, match dummy {
+        Some(dummy) => DeferredDummy::Lazy(dummy),
+        None => DeferredDummy::Taken(taken),
+    })
+}
+
+/// Returns the output when parsing an input failed with `errors`.
+fn parse_failed(
+    dummy: Option<LazyDummy<'_>>,
+    errors: TokenStream,
+    context: Option<&str>,
+) -> TokenStream {
+    let mut dummy = DeferredDummy::create(dummy);
+    dummy.extend(errors);
+    if let Some(context) = context {
+        crate::error_message!("{context}").to_tokens(&mut dummy);
+    }
+    dummy
+}
+
 macro_rules! transparent_handlers {
-    ($name:ident; $MacroInput:ident; $($input:ident: $Input:ident $($context:expr)?),*; $($dummy:ident)?) => {
+    ($name:ident; $MacroInput:ident; $($input:ident: $Input:ident $($context:expr)?),*) => {
         /// Internal implementation for macro.
-        pub fn $name<$($Input,)* Dummy: AnyTokenStream, Output, Function,>(
+        pub fn $name<$($Input,)* Dummy: AnyTokenStream, Output, Function, Body>(
             $($input: Result<$Input, TokenStream>,)*
-            $($dummy: Option<impl AnyTokenStream>,)?
-            body: impl $MacroInput<Function, $($Input = $Input,)* Dummy = Dummy, Output = Output>,
-        ) -> Result<(Output, TokenStream, TokenStream), TokenStream> {
-            // use $crate::ToTokensError as _;
-            #[allow(unused)]
-            let mut dummy = TokenStream::new();
-            $(let mut dummy = $dummy.unwrap_or_default().into();)?
+            dummy: Option<LazyDummy<'_>>,
+            body: Body,
+        ) -> Result<(Output, TokenStream, DeferredDummy<'_>), TokenStream>
+        where
+            Body: $MacroInput<Function, $($Input = $Input,)* Dummy = Dummy, Output = Output>,
+        {
             $(let $input = match $input {
                 Ok($input) => $input,
-                Err(tokens) => {
-                    dummy.extend(tokens);
-                    $($crate::error_message!($context).to_tokens(&mut dummy);)?
-                    return Err(dummy);
-                }
+                Err(tokens) => return Err(parse_failed(dummy, tokens, None$(.or(Some($context)))?)),
             };)*
-            let mut dummy = dummy.into();
+            // Only create the dummy up front, when the handler can observe it.
+            let (mut taken, dummy) = if Body::TAKES_DUMMY {
+                (DeferredDummy::create(dummy).into(), None)
+            } else {
+                (Dummy::default(), dummy)
+            };
             let mut emitter = Emitter::new();
-            let output = body.call($($input,)+ &mut dummy, &mut emitter);
-            let mut tokens = TokenStream::new();
-            emitter.to_tokens(&mut tokens);
-            Ok((output, tokens, dummy.into()))
+            let output = body.call($($input,)+ &mut taken, &mut emitter);
+            let (tokens, dummy) = handler_called(
+                taken.into(),
+                dummy,
+                Body::TAKES_EMITTER.then_some(&emitter),
+            );
+            Ok((output, tokens, dummy))
         }
     };
 }
 
-transparent_handlers! { function_transparent; FunctionMacroHandler; input: Input; dummy }
-transparent_handlers! { derive_transparent; DeriveMacroHandler; item: Item;}
-transparent_handlers! { attribute_transparent; AttributeMacroHandler; input: Input, item: Item; dummy }
+transparent_handlers! { function_transparent; FunctionMacroHandler; input: Input }
+transparent_handlers! { derive_transparent; DeriveMacroHandler; item: Item }
+transparent_handlers! { attribute_transparent; AttributeMacroHandler; input: Input, item: Item }
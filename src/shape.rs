@@ -0,0 +1,470 @@
+use std::fmt::Display;
+use std::ops::Range;
+
+use proc_macro2::Span;
+use syn2::{Data, DataEnum, DataStruct, DeriveInput, Fields, FieldsNamed, FieldsUnnamed, Variant};
+
+use crate::{ErrorMessage, SpanRanged};
+
+/// Returns the [`DataStruct`] of `input`, erroring with `msg` on enums and
+/// unions.
+///
+/// See [`ensure_struct!`](crate::ensure_struct) for a macro returning early.
+///
+/// # Errors
+/// Errors if `input` is not a struct, spanning the `enum`/`union` keyword and
+/// the name.
+pub fn expect_struct(input: &DeriveInput, msg: impl Display) -> Result<&DataStruct, ErrorMessage> {
+    match &input.data {
+        Data::Struct(data) => Ok(data),
+        Data::Enum(data) => Err(
+            ErrorMessage::new(data.enum_token.span..input.ident.span(), msg)
+                .note(format_args!("`{}` is an enum", input.ident)),
+        ),
+        Data::Union(data) => Err(
+            ErrorMessage::new(data.union_token.span..input.ident.span(), msg)
+                .note(format_args!("`{}` is a union", input.ident)),
+        ),
+    }
+}
+
+/// Returns the [`DataEnum`] of `input`, erroring with `msg` on structs and
+/// unions.
+///
+/// See [`ensure_enum!`](crate::ensure_enum) for a macro returning early.
+///
+/// # Errors
+/// Errors if `input` is not an enum, spanning the `struct`/`union` keyword
+/// and the name.
+pub fn expect_enum(input: &DeriveInput, msg: impl Display) -> Result<&DataEnum, ErrorMessage> {
+    match &input.data {
+        Data::Enum(data) => Ok(data),
+        Data::Struct(data) => Err(ErrorMessage::new(
+            data.struct_token.span..input.ident.span(),
+            msg,
+        )
+        .note(format_args!("`{}` is a struct", input.ident))),
+        Data::Union(data) => Err(
+            ErrorMessage::new(data.union_token.span..input.ident.span(), msg)
+                .note(format_args!("`{}` is a union", input.ident)),
+        ),
+    }
+}
+
+/// Returns the [`FieldsNamed`] of `fields`, erroring with `msg` on tuple and
+/// unit fields.
+///
+/// See [`ensure_named_fields!`](crate::ensure_named_fields) for a macro
+/// returning early.
+///
+/// # Errors
+/// Errors if `fields` are not named, spanning the fields.
+pub fn expect_named_fields<'a>(
+    fields: impl HasFields<'a>,
+    msg: impl Display,
+) -> Result<&'a FieldsNamed, ErrorMessage> {
+    match fields.fields() {
+        Fields::Named(fields) => Ok(fields),
+        Fields::Unnamed(unnamed) => {
+            Err(ErrorMessage::new(unnamed.paren_token.span, msg).note("found tuple fields"))
+        }
+        Fields::Unit => Err(ErrorMessage::new(fields.unit_span(), msg).note("found no fields")),
+    }
+}
+
+/// Returns the [`FieldsUnnamed`] of `fields`, erroring with `msg` on named
+/// and unit fields.
+///
+/// See [`ensure_unnamed_fields!`](crate::ensure_unnamed_fields) for a macro
+/// returning early.
+///
+/// # Errors
+/// Errors if `fields` are not unnamed, spanning the fields.
+pub fn expect_unnamed_fields<'a>(
+    fields: impl HasFields<'a>,
+    msg: impl Display,
+) -> Result<&'a FieldsUnnamed, ErrorMessage> {
+    match fields.fields() {
+        Fields::Unnamed(fields) => Ok(fields),
+        Fields::Named(named) => {
+            Err(ErrorMessage::new(named.brace_token.span, msg).note("found named fields"))
+        }
+        Fields::Unit => Err(ErrorMessage::new(fields.unit_span(), msg).note("found no fields")),
+    }
+}
+
+/// References to types containing [`Fields`], i.e., [`Fields`],
+/// [`DataStruct`] and [`Variant`].
+pub trait HasFields<'a>: Copy {
+    /// Returns the fields.
+    fn fields(self) -> &'a Fields;
+    /// Returns the span to use for errors on [`Fields::Unit`].
+    fn unit_span(self) -> Range<Span>;
+}
+
+impl<'a> HasFields<'a> for &'a Fields {
+    fn fields(self) -> &'a Fields {
+        self
+    }
+
+    fn unit_span(self) -> Range<Span> {
+        Span::call_site().span_range()
+    }
+}
+
+impl<'a> HasFields<'a> for &'a DataStruct {
+    fn fields(self) -> &'a Fields {
+        &self.fields
+    }
+
+    fn unit_span(self) -> Range<Span> {
+        self.semi_token.as_ref().map_or_else(
+            || self.struct_token.span.span_range(),
+            |semi| self.struct_token.span..semi.span,
+        )
+    }
+}
+
+impl<'a> HasFields<'a> for &'a Variant {
+    fn fields(self) -> &'a Fields {
+        &self.fields
+    }
+
+    fn unit_span(self) -> Range<Span> {
+        self.ident.span().span_range()
+    }
+}
+
+impl<'a, T> HasFields<'a> for &&'a T
+where
+    &'a T: HasFields<'a>,
+{
+    fn fields(self) -> &'a Fields {
+        (*self).fields()
+    }
+
+    fn unit_span(self) -> Range<Span> {
+        (*self).unit_span()
+    }
+}
+
+/// Returns the [`DataStruct`] of a [`DeriveInput`], or returns early with an
+/// error.
+///
+/// Uses [`expect_struct`], the message defaults to `only structs are
+/// supported`, but can be specified using [`format_args!`] syntax.
+/// ```
+/// # use manyhow::ensure_struct;
+/// # use syn2 as syn;
+/// let input: syn::DeriveInput = syn::parse_quote!(
+///     struct Struct;
+/// );
+/// let data = ensure_struct!(input);
+///
+/// let input: syn::DeriveInput = syn::parse_quote!(
+///     enum Enum {}
+/// );
+/// let data = (|| {
+///     Ok::<_, manyhow::ErrorMessage>(ensure_struct!(
+///         input,
+///         "`{}` only supports structs",
+///         "MyDerive"
+///     ))
+/// })();
+/// assert_eq!(
+///     data.map(drop).unwrap_err().to_string(),
+///     "`MyDerive` only supports structs\n\n  = note: `Enum` is an enum\n"
+/// );
+/// # Ok::<_, manyhow::ErrorMessage>(())
+/// ```
+#[macro_export]
+macro_rules! ensure_struct {
+    ($input:expr $(,)?) => {
+        $crate::ensure_struct!($input, "only structs are supported")
+    };
+    ($input:expr, $($fmt:tt)*) => {
+        match $crate::expect_struct(&$input, ::core::format_args!($($fmt)*)) {
+            ::core::result::Result::Ok(data) => data,
+            ::core::result::Result::Err(error) => return ::core::result::Result::Err(error.into()),
+        }
+    };
+}
+
+/// Returns the [`DataEnum`] of a [`DeriveInput`], or returns early with an
+/// error.
+///
+/// Uses [`expect_enum`], the message defaults to `only enums are supported`,
+/// but can be specified using [`format_args!`] syntax.
+/// ```
+/// # use manyhow::ensure_enum;
+/// # use syn2 as syn;
+/// let input: syn::DeriveInput = syn::parse_quote!(
+///     enum Enum {
+///         A,
+///         B,
+///     }
+/// );
+/// let data = ensure_enum!(input);
+/// assert_eq!(data.variants.len(), 2);
+///
+/// let input: syn::DeriveInput = syn::parse_quote!(
+///     struct Struct;
+/// );
+/// let data = (|| Ok::<_, manyhow::ErrorMessage>(ensure_enum!(input)))();
+/// assert_eq!(
+///     data.map(drop).unwrap_err().to_string(),
+///     "only enums are supported\n\n  = note: `Struct` is a struct\n"
+/// );
+/// # Ok::<_, manyhow::ErrorMessage>(())
+/// ```
+#[macro_export]
+macro_rules! ensure_enum {
+    ($input:expr $(,)?) => {
+        $crate::ensure_enum!($input, "only enums are supported")
+    };
+    ($input:expr, $($fmt:tt)*) => {
+        match $crate::expect_enum(&$input, ::core::format_args!($($fmt)*)) {
+            ::core::result::Result::Ok(data) => data,
+            ::core::result::Result::Err(error) => return ::core::result::Result::Err(error.into()),
+        }
+    };
+}
+
+/// Returns the [`FieldsNamed`] of a [`HasFields`], or returns early with an
+/// error.
+///
+/// Uses [`expect_named_fields`], the message defaults to `only named fields
+/// are supported`, but can be specified using [`format_args!`] syntax.
+/// ```
+/// # use manyhow::{ensure_named_fields, ensure_struct};
+/// # use syn2 as syn;
+/// let input: syn::DeriveInput = syn::parse_quote!(
+///     struct Struct {
+///         a: u8,
+///     }
+/// );
+/// let data = ensure_struct!(input);
+/// let fields = ensure_named_fields!(data);
+/// assert_eq!(fields.named.len(), 1);
+///
+/// let input: syn::DeriveInput = syn::parse_quote!(
+///     struct Struct(u8);
+/// );
+/// let fields = (|| {
+///     let data = ensure_struct!(input);
+///     Ok::<_, manyhow::ErrorMessage>(ensure_named_fields!(
+///         data,
+///         "MyDerive only supports structs with named fields"
+///     ))
+/// })();
+/// assert_eq!(
+///     fields.map(drop).unwrap_err().to_string(),
+///     "MyDerive only supports structs with named fields\n\n  = note: found tuple fields\n"
+/// );
+/// # Ok::<_, manyhow::ErrorMessage>(())
+/// ```
+#[macro_export]
+macro_rules! ensure_named_fields {
+    ($fields:expr $(,)?) => {
+        $crate::ensure_named_fields!($fields, "only named fields are supported")
+    };
+    ($fields:expr, $($fmt:tt)*) => {
+        match $crate::expect_named_fields(&$fields, ::core::format_args!($($fmt)*)) {
+            ::core::result::Result::Ok(fields) => fields,
+            ::core::result::Result::Err(error) => return ::core::result::Result::Err(error.into()),
+        }
+    };
+}
+
+/// Returns the [`FieldsUnnamed`] of a [`HasFields`], or returns early with an
+/// error.
+///
+/// Uses [`expect_unnamed_fields`], the message defaults to `only tuple fields
+/// are supported`, but can be specified using [`format_args!`] syntax.
+/// ```
+/// # use manyhow::{ensure_enum, ensure_unnamed_fields};
+/// # use syn2 as syn;
+/// let input: syn::DeriveInput = syn::parse_quote!(
+///     enum Enum {
+///         A(u8),
+///         B,
+///     }
+/// );
+/// let data = ensure_enum!(input);
+/// let fields = ensure_unnamed_fields!(data.variants[0]);
+/// assert_eq!(fields.unnamed.len(), 1);
+///
+/// let fields = (|| Ok::<_, manyhow::ErrorMessage>(ensure_unnamed_fields!(data.variants[1])))();
+/// assert_eq!(
+///     fields.map(drop).unwrap_err().to_string(),
+///     "only tuple fields are supported\n\n  = note: found no fields\n"
+/// );
+/// # Ok::<_, manyhow::ErrorMessage>(())
+/// ```
+#[macro_export]
+macro_rules! ensure_unnamed_fields {
+    ($fields:expr $(,)?) => {
+        $crate::ensure_unnamed_fields!($fields, "only tuple fields are supported")
+    };
+    ($fields:expr, $($fmt:tt)*) => {
+        match $crate::expect_unnamed_fields(&$fields, ::core::format_args!($($fmt)*)) {
+            ::core::result::Result::Ok(fields) => fields,
+            ::core::result::Result::Err(error) => return ::core::result::Result::Err(error.into()),
+        }
+    };
+}
+
+/// Parses all fields of a struct or union `input` as `T`, emitting every
+/// error into `emitter` instead of aborting on the first one.
+///
+/// Fields that fail to parse are omitted from the result.
+///
+/// ```
+/// use darling_core::FromField;
+/// use manyhow::{parse_fields, Emitter};
+/// # use syn2 as syn;
+///
+/// struct Field(syn::Ident);
+///
+/// impl FromField for Field {
+///     fn from_field(field: &syn::Field) -> darling_core::Result<Self> {
+///         match &field.ident {
+///             Some(ident) if ident != "invalid" => Ok(Self(ident.clone())),
+///             _ => Err(darling_core::Error::custom("unsupported field").with_span(field)),
+///         }
+///     }
+/// }
+///
+/// let input: syn::DeriveInput = syn::parse_quote! {
+///     struct Struct { a: u8, invalid: u8, b: u8 }
+/// };
+/// let mut emitter = Emitter::new();
+/// let fields: Vec<Field> = parse_fields(&input, &mut emitter);
+/// assert_eq!(fields.len(), 2);
+/// assert!(!emitter.is_empty());
+/// ```
+#[cfg(feature = "darling")]
+pub fn parse_fields<T: darling_core::FromField>(
+    input: &DeriveInput,
+    emitter: &mut crate::Emitter,
+) -> Vec<T> {
+    let fields = match &input.data {
+        Data::Struct(data) => data.fields.iter().collect(),
+        Data::Union(data) => data.fields.named.iter().collect(),
+        Data::Enum(data) => {
+            emitter.emit(
+                ErrorMessage::new(
+                    data.enum_token.span..input.ident.span(),
+                    "expected struct or union",
+                )
+                .note(format_args!("`{}` is an enum", input.ident)),
+            );
+            Vec::new()
+        }
+    };
+    fields
+        .into_iter()
+        .filter_map(|field| T::from_field(field).map_err(|e| emitter.emit(e)).ok())
+        .collect()
+}
+
+/// Parses all variants of an enum `input` as `T`, emitting every error into
+/// `emitter` instead of aborting on the first one.
+///
+/// Variants that fail to parse are omitted from the result.
+///
+/// ```
+/// use darling_core::FromVariant;
+/// use manyhow::{parse_variants, Emitter};
+/// # use syn2 as syn;
+///
+/// struct Variant(syn::Ident);
+///
+/// impl FromVariant for Variant {
+///     fn from_variant(variant: &syn::Variant) -> darling_core::Result<Self> {
+///         if variant.fields.is_empty() {
+///             Ok(Self(variant.ident.clone()))
+///         } else {
+///             Err(darling_core::Error::unsupported_shape("fields").with_span(variant))
+///         }
+///     }
+/// }
+///
+/// let input: syn::DeriveInput = syn::parse_quote! {
+///     enum Enum { A, B(u8), C { c: u8 } }
+/// };
+/// let mut emitter = Emitter::new();
+/// let variants: Vec<Variant> = parse_variants(&input, &mut emitter);
+/// assert_eq!(variants.len(), 1);
+/// assert!(!emitter.is_empty());
+/// ```
+#[cfg(feature = "darling")]
+pub fn parse_variants<T: darling_core::FromVariant>(
+    input: &DeriveInput,
+    emitter: &mut crate::Emitter,
+) -> Vec<T> {
+    match expect_enum(input, "expected enum") {
+        Ok(data) => data
+            .variants
+            .iter()
+            .filter_map(|variant| T::from_variant(variant).map_err(|e| emitter.emit(e)).ok())
+            .collect(),
+        Err(error) => {
+            emitter.emit(error);
+            Vec::new()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use syn2::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn shapes() {
+        let input: DeriveInput = parse_quote!(union Union { a: u8 });
+        assert_eq!(
+            expect_struct(&input, "struct")
+                .map(drop)
+                .unwrap_err()
+                .to_string(),
+            "struct\n\n  = note: `Union` is a union\n"
+        );
+        assert_eq!(
+            expect_enum(&input, "enum")
+                .map(drop)
+                .unwrap_err()
+                .to_string(),
+            "enum\n\n  = note: `Union` is a union\n"
+        );
+
+        let input: DeriveInput = parse_quote!(
+            struct Struct;
+        );
+        let data = expect_struct(&input, "struct").unwrap();
+        assert_eq!(
+            expect_named_fields(data, "named")
+                .map(drop)
+                .unwrap_err()
+                .to_string(),
+            "named\n\n  = note: found no fields\n"
+        );
+
+        let input: DeriveInput = parse_quote!(
+            struct Struct {
+                a: u8,
+            }
+        );
+        let data = expect_struct(&input, "struct").unwrap();
+        assert!(expect_named_fields(data, "named").is_ok());
+        assert_eq!(
+            expect_unnamed_fields(&data.fields, "unnamed")
+                .map(drop)
+                .unwrap_err()
+                .to_string(),
+            "unnamed\n\n  = note: found named fields\n"
+        );
+    }
+}
@@ -7,16 +7,59 @@ use quote::ToTokens;
 use crate::ErrorMessage;
 
 /// Get a [`Range`](std::ops::Range)[`<Span>`](proc_macro2::Span) from a
-/// type that **either** implements [`SpanRanged`] or [`ToTokens`] (**NOT**
-/// both).
+/// type that implements [`SpanRanged`] or [`ToTokens`], preferring
+/// [`SpanRanged`] if both are implemented.
+///
+/// Multiple comma separated expressions return the union of their ranges, i.e.,
+/// a range covering all of them. Where spans cannot be joined (i.e., on stable)
+/// this is the range from the start of the first to the end of the last
+/// expression.
+///
+/// ```
+/// # use manyhow::span_range;
+/// # use proc_macro2::Span;
+/// # let (first_token, last_token, middle_thing) = (Span::call_site(), Span::call_site(), Span::call_site());
+/// let range = span_range!(first_token, last_token, middle_thing);
+/// ```
 #[macro_export]
 macro_rules! span_range {
-    ($span:expr) => {{
+    ($span:expr $(,)?) => {{
         // Warning is triggered if span is incorrect type
         #[allow(unused_imports)]
         use $crate::__private::*;
-        ($span).FIRST_ARG_MUST_IMPLEMENT_SpanRanged_OR_ToTokens()
+        (&$span).FIRST_ARG_MUST_IMPLEMENT_SpanRanged_OR_ToTokens()
     }};
+    ($($span:expr),+ $(,)?) => {
+        $crate::__private::span_range_union(&[$($crate::span_range!($span)),+])
+    };
+}
+
+/// Like [`quote::quote_spanned!`], but spanned with a [`SpanRanged`] or
+/// [`ToTokens`] (see [`span_range!`]) instead of a single [`Span`].
+///
+/// Spans tokens the same way as [`ErrorMessage`]s, i.e., the first token gets
+/// the start span and all others the end span, or all get the joined span on
+/// nightly. This allows dummies and generated code to point at the
+/// originating input. Interpolated tokens keep their spans, unless they are
+/// the first token.
+///
+/// ```
+/// # use proc_macro2::Span;
+/// use manyhow::quote_spanned_range;
+///
+/// # let (first, last) = (Span::call_site(), Span::call_site());
+/// let ty = quote::format_ident!("Struct");
+/// let tokens = quote_spanned_range!(first..last => impl Default for #ty {});
+/// assert_eq!(tokens.to_string(), "impl Default for Struct { }");
+/// ```
+#[macro_export]
+macro_rules! quote_spanned_range {
+    ($range:expr => $($tt:tt)*) => {
+        $crate::__private::quote_spanned_range(
+            $crate::span_range!($range),
+            |span| $crate::__private::quote::quote_spanned!(span => $($tt)*),
+        )
+    };
 }
 
 /// Returns the [`Range`](Range)[`<Span>`](Span) from the start to the end of
@@ -28,8 +71,8 @@ macro_rules! span_range {
 /// `SpanRanged for T: ToTokens`, therefor there is
 /// [`to_tokens_span_range()`].
 ///
-/// For types that **either** implement [`SpanRanged`] or [`ToTokens`] (but
-/// **NOT** both) the [`span_range!`] macro can be used as well.
+/// For types that implement [`SpanRanged`] or [`ToTokens`] the
+/// [`span_range!`] macro can be used as well.
 ///
 /// # Motivation
 /// This is superior to a normal [`Span`] (at least until [`Span::join`] works
@@ -69,6 +112,31 @@ pub trait SpanRanged {
         range.start.join(range.end)
     }
 
+    /// Returns [`Self::span_range`] with the name resolution behavior of
+    /// `span`, i.e., located at `self` but resolved at `span`.
+    /// [more](proc_macro2::Span::resolved_at)
+    ///
+    /// ```
+    /// use manyhow::SpanRanged;
+    /// use proc_macro2::Span;
+    /// # let user_token = Span::mixed_site();
+    ///
+    /// // Point at the user's token, but resolve paths like `::core` at the call site
+    /// let range = user_token.span_range_resolved_at(Span::call_site());
+    /// ```
+    fn span_range_resolved_at(&self, span: Span) -> Range<Span> {
+        let range = self.span_range();
+        range.start.resolved_at(span)..range.end.resolved_at(span)
+    }
+
+    /// Returns `location` with the name resolution behavior of `self`, i.e.,
+    /// located at `location` but resolved at `self`.
+    /// [more](proc_macro2::Span::located_at)
+    fn span_range_located_at(&self, location: Range<Span>) -> Range<Span> {
+        let resolution = self.span_range().start;
+        location.start.resolved_at(resolution)..location.end.resolved_at(resolution)
+    }
+
     #[doc(hidden)]
     #[deprecated]
     fn joined(&self) -> Option<Span> {
@@ -76,7 +144,7 @@ pub trait SpanRanged {
     }
 }
 
-impl<T: SpanRanged> SpanRanged for &T {
+impl<T: SpanRanged + ?Sized> SpanRanged for &T {
     fn span_range(&self) -> Range<Span> {
         (*self).span_range()
     }
@@ -95,6 +163,41 @@ impl<A: SpanRanged, B: SpanRanged> SpanRanged for (A, B) {
     }
 }
 
+impl<A: SpanRanged, B: SpanRanged, C: SpanRanged> SpanRanged for (A, B, C) {
+    fn span_range(&self) -> Range<Span> {
+        (self.0.span_range().start)..(self.2.span_range().end)
+    }
+}
+
+impl<A: SpanRanged, B: SpanRanged, C: SpanRanged, D: SpanRanged> SpanRanged for (A, B, C, D) {
+    fn span_range(&self) -> Range<Span> {
+        (self.0.span_range().start)..(self.3.span_range().end)
+    }
+}
+
+/// Spans from the start of the first to the end of the last element,
+/// [`Span::call_site`] if empty.
+impl<T: SpanRanged> SpanRanged for [T] {
+    fn span_range(&self) -> Range<Span> {
+        match (self.first(), self.last()) {
+            (Some(first), Some(last)) => first.span_range().start..last.span_range().end,
+            _ => Span::call_site().span_range(),
+        }
+    }
+}
+
+impl<T: SpanRanged, const N: usize> SpanRanged for [T; N] {
+    fn span_range(&self) -> Range<Span> {
+        self.as_slice().span_range()
+    }
+}
+
+impl<T: SpanRanged> SpanRanged for Vec<T> {
+    fn span_range(&self) -> Range<Span> {
+        self.as_slice().span_range()
+    }
+}
+
 impl SpanRanged for Span {
     fn span_range(&self) -> Range<Span> {
         *self..*self
@@ -115,10 +218,10 @@ impl<T: SpanRanged> SpanRanged for Range<T> {
 impl SpanRanged for proc_macro::TokenStream {
     fn span_range(&self) -> Range<Span> {
         let mut this = self.clone().into_iter();
-        let first = this
-            .next()
-            .as_ref()
-            .map_or_else(proc_macro::Span::call_site, proc_macro::TokenTree::span);
+        // `proc_macro::Span::call_site` panics outside of proc macros
+        let Some(first) = this.next().as_ref().map(proc_macro::TokenTree::span) else {
+            return Span::call_site().span_range();
+        };
 
         let last = this
             .last()
@@ -134,6 +237,218 @@ impl SpanRanged for proc_macro2::extra::DelimSpan {
     }
 }
 
+/// Spans from the opening to the closing delimiter.
+impl SpanRanged for proc_macro2::Group {
+    fn span_range(&self) -> Range<Span> {
+        self.span_open()..self.span_close()
+    }
+}
+
+/// Spans from the opening to the closing delimiter.
+impl SpanRanged for proc_macro::Group {
+    fn span_range(&self) -> Range<Span> {
+        self.span_open().into()..self.span_close().into()
+    }
+}
+
+/// A range of spans from the `start` to the `end` of multi-token structures,
+/// see [`SpanRanged`].
+///
+/// Converts from and into [`Range`](Range)[`<Span>`](Span), and can be
+/// combined and applied to tokens.
+///
+/// ```
+/// use manyhow::SpanRange;
+/// use proc_macro2::Span;
+/// use quote::quote;
+///
+/// let range = SpanRange::from(Span::call_site()).union(Span::call_site());
+/// let tokens = range.respan(quote!(a + b));
+/// assert_eq!(tokens.to_string(), "a + b");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct SpanRange {
+    start: Span,
+    end: Span,
+}
+
+impl SpanRange {
+    /// Creates a range from `start` to `end`.
+    #[must_use]
+    pub fn new(start: Span, end: Span) -> Self {
+        Self { start, end }
+    }
+
+    /// Returns the span of the start of the range.
+    #[must_use]
+    pub fn start_span(&self) -> Span {
+        self.start
+    }
+
+    /// Returns the span of the end of the range.
+    #[must_use]
+    pub fn end_span(&self) -> Span {
+        self.end
+    }
+
+    /// Returns the range covering both `self` and `other`, see
+    /// [`span_range!`] for details.
+    #[must_use]
+    pub fn union(self, other: impl SpanRanged) -> Self {
+        span_range_union(&[self.into(), other.span_range()]).into()
+    }
+
+    /// Returns the range as a single span if possible, currently only
+    /// possible on nightly. [more](proc_macro2::Span::join)
+    #[must_use]
+    pub fn joined(&self) -> Option<Span> {
+        self.span_joined()
+    }
+
+    /// Sets the spans of `tokens` (including nested groups) to this range,
+    /// i.e., the first token gets the start span and all others the end span,
+    /// or the joined span where possible, mirroring how errors are spanned.
+    #[must_use]
+    pub fn respan(&self, tokens: impl ToTokens) -> proc_macro2::TokenStream {
+        let range = self.joined().map_or(*self, |span| Self::new(span, span));
+        let mut first = true;
+        range.respan_inner(tokens.into_token_stream(), &mut first)
+    }
+
+    fn respan_inner(
+        self,
+        tokens: proc_macro2::TokenStream,
+        first: &mut bool,
+    ) -> proc_macro2::TokenStream {
+        tokens
+            .into_iter()
+            .map(|mut tt| {
+                let span = if std::mem::take(first) {
+                    self.start
+                } else {
+                    self.end
+                };
+                if let proc_macro2::TokenTree::Group(group) = &tt {
+                    let stream = self.respan_inner(group.stream(), first);
+                    tt = proc_macro2::Group::new(group.delimiter(), stream).into();
+                }
+                tt.set_span(span);
+                tt
+            })
+            .collect()
+    }
+}
+
+impl SpanRanged for SpanRange {
+    fn span_range(&self) -> Range<Span> {
+        self.start..self.end
+    }
+}
+
+impl From<Range<Span>> for SpanRange {
+    fn from(value: Range<Span>) -> Self {
+        Self::new(value.start, value.end)
+    }
+}
+
+impl From<SpanRange> for Range<Span> {
+    fn from(value: SpanRange) -> Self {
+        value.start..value.end
+    }
+}
+
+impl From<Span> for SpanRange {
+    fn from(value: Span) -> Self {
+        Self::new(value, value)
+    }
+}
+
+/// Bytes `N..M` of a single token's source code, e.g., to point at a part of
+/// an identifier or literal.
+///
+/// Precise spans require [`Literal::subspan`](proc_macro2::Literal::subspan),
+/// which is currently only supported on nightly. On stable, and for tokens
+/// other than literals, the span of the complete token is used.
+///
+/// ```
+/// use manyhow::{ErrorMessage, SubSpan};
+/// use proc_macro2::Literal;
+///
+/// let literal = Literal::u32_suffixed(10);
+/// let error = ErrorMessage::new(SubSpan::new(literal, 2..5), "unsupported suffix `u32`");
+/// ```
+#[derive(Debug, Clone)]
+pub struct SubSpan {
+    token: proc_macro2::TokenTree,
+    range: Range<usize>,
+}
+
+impl SubSpan {
+    /// Creates a sub span for the bytes `range` of `token`'s source code.
+    #[must_use]
+    pub fn new(token: impl Into<proc_macro2::TokenTree>, range: Range<usize>) -> Self {
+        Self {
+            token: token.into(),
+            range,
+        }
+    }
+
+    /// Returns the span of the bytes if possible, otherwise the span of the
+    /// complete token.
+    #[must_use]
+    pub fn span(&self) -> Span {
+        match &self.token {
+            proc_macro2::TokenTree::Literal(literal) => literal
+                .subspan(self.range.clone())
+                .unwrap_or_else(|| literal.span()),
+            token => token.span(),
+        }
+    }
+}
+
+impl SpanRanged for SubSpan {
+    fn span_range(&self) -> Range<Span> {
+        self.span().span_range()
+    }
+}
+
+/// Ranges of the opening and closing delimiters of delimited tokens, e.g.,
+/// to point at a missing closing brace.
+///
+/// ```
+/// use manyhow::{DelimSpanRanged, ErrorMessage};
+/// use proc_macro2::Group;
+/// # let group: Group = syn2::parse_quote!((a, b));
+///
+/// let error = ErrorMessage::new(group.span_close_range(), "expected `c` before `)`");
+/// ```
+pub trait DelimSpanRanged {
+    /// Returns the [`Range`](Range)[`<Span>`](Span) of the opening delimiter.
+    fn span_open_range(&self) -> Range<Span>;
+    /// Returns the [`Range`](Range)[`<Span>`](Span) of the closing delimiter.
+    fn span_close_range(&self) -> Range<Span>;
+}
+
+impl DelimSpanRanged for proc_macro2::extra::DelimSpan {
+    fn span_open_range(&self) -> Range<Span> {
+        self.open().span_range()
+    }
+
+    fn span_close_range(&self) -> Range<Span> {
+        self.close().span_range()
+    }
+}
+
+impl DelimSpanRanged for proc_macro2::Group {
+    fn span_open_range(&self) -> Range<Span> {
+        self.span_open().span_range()
+    }
+
+    fn span_close_range(&self) -> Range<Span> {
+        self.span_close().span_range()
+    }
+}
+
 #[cfg(feature = "syn2")]
 const _: () = {
     impl SpanRanged for syn2::token::Brace {
@@ -151,14 +466,155 @@ const _: () = {
             self.span.span_range()
         }
     }
+    impl DelimSpanRanged for syn2::token::Brace {
+        fn span_open_range(&self) -> Range<Span> {
+            self.span.span_open_range()
+        }
+
+        fn span_close_range(&self) -> Range<Span> {
+            self.span.span_close_range()
+        }
+    }
+    impl DelimSpanRanged for syn2::token::Bracket {
+        fn span_open_range(&self) -> Range<Span> {
+            self.span.span_open_range()
+        }
+
+        fn span_close_range(&self) -> Range<Span> {
+            self.span.span_close_range()
+        }
+    }
+    impl DelimSpanRanged for syn2::token::Paren {
+        fn span_open_range(&self) -> Range<Span> {
+            self.span.span_open_range()
+        }
+
+        fn span_close_range(&self) -> Range<Span> {
+            self.span.span_close_range()
+        }
+    }
+    /// Spans the first message of the error, e.g., to replace it with a more
+    /// detailed [`ErrorMessage`].
+    impl SpanRanged for syn2::Error {
+        fn span_range(&self) -> Range<Span> {
+            // The compile error is spanned from the start to the end of the message
+            self.into_iter().next().map_or_else(
+                || Span::call_site().span_range(),
+                |error| token_stream_span_range(error.to_compile_error()),
+            )
+        }
+    }
+    /// Spans from the first to the last element, including a trailing
+    /// punctuation, [`Span::call_site`] if empty.
+    impl<T: ToTokens, P: ToTokens> SpanRanged for syn2::punctuated::Punctuated<T, P> {
+        fn span_range(&self) -> Range<Span> {
+            match (self.first(), self.pairs().last()) {
+                (Some(first), Some(last)) => first_last_span_range(first, last),
+                _ => Span::call_site().span_range(),
+            }
+        }
+    }
 };
 
+#[cfg(feature = "proc-macro-error2")]
+impl SpanRanged for proc_macro_error2::SpanRange {
+    fn span_range(&self) -> Range<Span> {
+        self.first..self.last
+    }
+}
+
+/// Spans the first message of the error, e.g., to replace it with a more
+/// detailed [`ErrorMessage`].
+#[cfg(feature = "syn1")]
+impl SpanRanged for syn1::Error {
+    fn span_range(&self) -> Range<Span> {
+        // The compile error is spanned from the start to the end of the message
+        self.into_iter().next().map_or_else(
+            || Span::call_site().span_range(),
+            |error| token_stream_span_range(error.to_compile_error()),
+        )
+    }
+}
+
+/// Returns the [`Range`](Range)[`<Span>`](Span) of a `syn` node implementing
+/// [`Spanned`](syn2::spanned::Spanned).
+///
+/// The range is made up of the single span `node.span()` returns, i.e., the
+/// joined span on nightly and the span of the first token on stable. Use
+/// [`to_tokens_span_range()`] for a range spanning the complete node on
+/// stable.
+///
+/// ```
+/// # use syn2 as syn;
+/// use manyhow::{spanned_range, ErrorMessage};
+///
+/// let ty: syn::Type = syn::parse_quote!(Vec<u8>);
+/// let error = ErrorMessage::new(spanned_range(&ty), "unsupported type");
+/// ```
+#[cfg(feature = "syn2")]
+pub fn spanned_range(node: &impl syn2::spanned::Spanned) -> Range<Span> {
+    node.span().span_range()
+}
+
 /// Implementation of [`SpanRanged`](SpanRanged)` for T: `[`ToTokens`]
 ///
 /// This is necessary to put in a standalone function due to compiler
 /// limitations.
+///
+/// The range is computed on the [`proc_macro2`] tokens directly, so it works
+/// the same in and outside of proc macros, without converting the tokens.
 pub fn to_tokens_span_range(tokens: impl ToTokens) -> Range<Span> {
-    proc_macro::TokenStream::from(tokens.to_token_stream()).span_range()
+    token_stream_span_range(tokens.to_token_stream())
+}
+
+/// Spans from the first to the last token of `tokens`.
+fn token_stream_span_range(tokens: proc_macro2::TokenStream) -> Range<Span> {
+    let mut tokens = tokens.into_iter();
+    let first = tokens
+        .next()
+        .as_ref()
+        .map_or_else(proc_macro2::Span::call_site, proc_macro2::TokenTree::span);
+
+    let last = tokens
+        .last()
+        .as_ref()
+        .map_or(first, proc_macro2::TokenTree::span);
+    first..last
+}
+
+#[doc(hidden)]
+pub fn quote_spanned_range(
+    range: Range<Span>,
+    quote: impl FnOnce(Span) -> proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if let Some(span) = range.span_joined() {
+        return quote(span);
+    }
+    let mut tokens = quote(range.end).into_iter();
+    let first = tokens.next().map(|mut first| {
+        first.set_span(range.start);
+        first
+    });
+    first.into_iter().chain(tokens).collect()
+}
+
+#[doc(hidden)]
+#[must_use]
+pub fn span_range_union(ranges: &[Range<Span>]) -> Range<Span> {
+    let (Some(first), Some(last)) = (ranges.first(), ranges.last()) else {
+        return Span::call_site().span_range();
+    };
+    ranges
+        .iter()
+        .try_fold(None, |union: Option<Span>, range| {
+            let range = range.span_joined()?;
+            Some(Some(match union {
+                Some(union) => union.join(range)?,
+                None => range,
+            }))
+        })
+        .flatten()
+        .map_or(first.start..last.end, |union| union..union)
 }
 
 #[doc(hidden)]
@@ -178,20 +634,11 @@ pub trait ToTokensToSpanRange {
     #[allow(non_snake_case)]
     fn FIRST_ARG_MUST_IMPLEMENT_SpanRanged_OR_ToTokens(&self) -> Range<Span>;
 }
-impl<T: ToTokens> ToTokensToSpanRange for T {
+// Implemented on `&T` so that `SpanRanged` takes precedence.
+impl<T: ToTokens> ToTokensToSpanRange for &T {
     #[allow(non_snake_case)]
     fn FIRST_ARG_MUST_IMPLEMENT_SpanRanged_OR_ToTokens(&self) -> Range<Span> {
-        let mut this = self.to_token_stream().into_iter();
-        let first = this
-            .next()
-            .as_ref()
-            .map_or_else(proc_macro2::Span::call_site, proc_macro2::TokenTree::span);
-
-        let last = this
-            .last()
-            .as_ref()
-            .map_or(first, proc_macro2::TokenTree::span);
-        first..last
+        token_stream_span_range(self.to_token_stream())
     }
 }
 
@@ -200,27 +647,30 @@ pub trait ToTokensTupleToSpanRange {
     #[allow(non_snake_case)]
     fn FIRST_ARG_MUST_IMPLEMENT_SpanRanged_OR_ToTokens(&self) -> Range<Span>;
 }
-impl<A: ToTokens, B: ToTokens> ToTokensTupleToSpanRange for (A, B) {
+impl<A: ToTokens, B: ToTokens> ToTokensTupleToSpanRange for &(A, B) {
     #[allow(non_snake_case)]
     fn FIRST_ARG_MUST_IMPLEMENT_SpanRanged_OR_ToTokens(&self) -> Range<Span> {
-        let first = self
-            .0
-            .to_token_stream()
-            .into_iter()
-            .next()
-            .as_ref()
-            .map_or_else(proc_macro2::Span::call_site, proc_macro2::TokenTree::span);
+        first_last_span_range(&self.0, &self.1)
+    }
+}
 
-        let last = self
-            .1
-            .to_token_stream()
-            .into_iter()
-            .last()
-            .as_ref()
-            .map_or(first, proc_macro2::TokenTree::span);
+/// Spans from the first token of `first` to the last token of `last`.
+fn first_last_span_range(first: impl ToTokens, last: impl ToTokens) -> Range<Span> {
+    let first = first
+        .to_token_stream()
+        .into_iter()
+        .next()
+        .as_ref()
+        .map_or_else(proc_macro2::Span::call_site, proc_macro2::TokenTree::span);
 
-        first..last
-    }
+    let last = last
+        .to_token_stream()
+        .into_iter()
+        .last()
+        .as_ref()
+        .map_or(first, proc_macro2::TokenTree::span);
+
+    first..last
 }
 
 #[cfg(test)]
@@ -230,4 +680,211 @@ mod test {
         span_range!(1);
         span_range!((1, 2));
     }
+
+    #[test]
+    fn outside_proc_macro() {
+        use super::{to_tokens_span_range, SpanRanged};
+
+        let tokens: proc_macro2::TokenStream = "a b c".parse().unwrap();
+        let range = to_tokens_span_range(&tokens);
+        assert_eq!(range.start.start().column, 0);
+        assert_eq!(range.end.start().column, 4);
+        let error = crate::ErrorMessage::spanned(&tokens, "error");
+        assert!(!crate::ToTokensError::to_token_stream(&error).is_empty());
+        let range = proc_macro::TokenStream::new().span_range();
+        assert_eq!(range.start.start().column, 0);
+    }
+
+    #[test]
+    fn sub_span() {
+        use proc_macro2::{Ident, TokenTree};
+
+        use super::SubSpan;
+
+        let tokens: Vec<_> =
+            r#"  "abc" ident"#.parse::<proc_macro2::TokenStream>().unwrap().into_iter().collect();
+        let span = SubSpan::new(tokens[0].clone(), 2..3).span();
+        assert_eq!(span.start().column, 4);
+        assert_eq!(span.end().column, 5);
+        // Out of bounds
+        let span = SubSpan::new(tokens[0].clone(), 2..10).span();
+        assert_eq!(span.start().column, 2);
+        assert_eq!(span.end().column, 7);
+        // Identifiers do not support sub spans
+        let TokenTree::Ident(ident) = &tokens[1] else { unreachable!() };
+        let span = SubSpan::new(Ident::clone(ident), 1..3).span();
+        assert_eq!(span.start().column, 8);
+        assert_eq!(span.end().column, 13);
+    }
+
+    #[test]
+    fn hygiene() {
+        use proc_macro2::Span;
+
+        use super::SpanRanged;
+
+        let tokens: Vec<_> = "a b"
+            .parse::<proc_macro2::TokenStream>()
+            .unwrap()
+            .into_iter()
+            .collect();
+        let location = tokens[0].span()..tokens[1].span();
+        let range = location.span_range_resolved_at(Span::mixed_site());
+        assert_eq!(range.start.start().column, 0);
+        assert_eq!(range.end.start().column, 2);
+        let range = Span::mixed_site().span_range_located_at(location.clone());
+        assert_eq!(range.start.start().column, 0);
+        assert_eq!(range.end.start().column, 2);
+    }
+
+    #[test]
+    #[cfg(feature = "syn2")]
+    fn syn_error() {
+        use super::SpanRanged;
+
+        let tokens: Vec<_> = "a b c"
+            .parse::<proc_macro2::TokenStream>()
+            .unwrap()
+            .into_iter()
+            .collect();
+        let mut error = syn2::Error::new_spanned(quote::quote!(#(#tokens)*), "error");
+        error.combine(syn2::Error::new(tokens[0].span(), "other"));
+        let range = error.span_range();
+        assert_eq!(range.start.start().column, 0);
+        assert_eq!(range.end.start().column, 4);
+        let error = crate::ErrorMessage::new(&error, "detailed error");
+        assert_eq!(error.to_string(), "detailed error");
+    }
+
+    #[test]
+    fn span_range_struct() {
+        use super::SpanRange;
+
+        let tokens: Vec<_> = "a b c d"
+            .parse::<proc_macro2::TokenStream>()
+            .unwrap()
+            .into_iter()
+            .collect();
+        let range = SpanRange::new(tokens[0].span(), tokens[1].span()).union(tokens[3].span());
+        assert_eq!(range.start_span().start().column, 0);
+        assert_eq!(range.end_span().end().column, 7);
+        assert!(range.joined().is_some());
+
+        // All tokens, including nested ones, are respanned
+        let range = SpanRange::from(tokens[2].span());
+        let respanned: Vec<_> = range.respan(quote::quote!(x(y))).into_iter().collect();
+        assert_eq!(respanned[0].span().start().column, 4);
+        let proc_macro2::TokenTree::Group(group) = &respanned[1] else {
+            unreachable!()
+        };
+        assert_eq!(group.span().start().column, 4);
+        for tt in group.stream() {
+            assert_eq!(tt.span().start().column, 4);
+        }
+    }
+
+    #[test]
+    fn quote_spanned_range() {
+        let tokens: Vec<_> = "a b c"
+            .parse::<proc_macro2::TokenStream>()
+            .unwrap()
+            .into_iter()
+            .collect();
+        let ident = proc_macro2::Ident::new("ident", tokens[1].span());
+        let output = quote_spanned_range!(tokens[0].span()..tokens[2].span() => x #ident y);
+        // Interpolated tokens keep their spans
+        for (tt, columns) in output.into_iter().zip([0..5, 2..3, 0..5]) {
+            assert_eq!(tt.span().start().column, columns.start);
+            assert_eq!(tt.span().end().column, columns.end);
+        }
+    }
+
+    #[test]
+    fn union() {
+        let tokens: Vec<_> = "a b c d"
+            .parse::<proc_macro2::TokenStream>()
+            .unwrap()
+            .into_iter()
+            .collect();
+        let range = span_range!(tokens[0], tokens[3], tokens[1]);
+        assert_eq!(range.start.start().column, 0);
+        assert_eq!(range.end.end().column, 7);
+        let range = span_range!(tokens[2], (&tokens[1], &tokens[2]),);
+        assert_eq!(range.start.start().column, 2);
+        assert_eq!(range.end.end().column, 5);
+    }
+
+    #[test]
+    fn group() {
+        use super::{DelimSpanRanged, SpanRanged};
+
+        let Some(proc_macro2::TokenTree::Group(group)) = "(a, b)"
+            .parse::<proc_macro2::TokenStream>()
+            .unwrap()
+            .into_iter()
+            .next()
+        else {
+            unreachable!()
+        };
+        let range = group.span_range();
+        assert_eq!(range.start.start().column, 0);
+        assert_eq!(range.end.start().column, 5);
+        let range = span_range!(group);
+        assert_eq!(range.end.start().column, 5);
+        let range = group.span_open_range();
+        assert_eq!(range.start.start().column, 0);
+        assert_eq!(range.end.end().column, 1);
+        let range = group.span_close_range();
+        assert_eq!(range.start.start().column, 5);
+        assert_eq!(range.end.end().column, 6);
+    }
+
+    #[test]
+    fn collections() {
+        use proc_macro2::Span;
+
+        use super::SpanRanged;
+
+        let spans: Vec<Span> = "a b c"
+            .parse::<proc_macro2::TokenStream>()
+            .unwrap()
+            .into_iter()
+            .map(|tt| tt.span())
+            .collect();
+        let range = spans.span_range();
+        assert_eq!(range.start.start().column, 0);
+        assert_eq!(range.end.start().column, 4);
+        let range = spans[1..].span_range();
+        assert_eq!(range.start.start().column, 2);
+        let range = (spans[0], spans[1], spans[2]).span_range();
+        assert_eq!(range.end.start().column, 4);
+        let range = [spans[1], spans[2]].span_range();
+        assert_eq!(range.start.start().column, 2);
+        assert_eq!(range.end.start().column, 4);
+        let empty: Vec<Span> = Vec::new();
+        span_range!(empty);
+    }
+
+    #[test]
+    #[cfg(feature = "syn2")]
+    fn punctuated() {
+        use super::SpanRanged;
+
+        let generics: syn2::Generics = syn2::parse_str("<'a, T: Clone, const N: usize>").unwrap();
+        let range = generics.params.span_range();
+        assert_eq!(range.start.start().column, 1);
+        assert_eq!(range.end.start().column, 24);
+        // `SpanRanged` takes precedence over `ToTokens`
+        let range = span_range!(generics.params);
+        assert_eq!(range.end.start().column, 24);
+    }
+
+    #[test]
+    #[cfg(feature = "syn2")]
+    fn spanned_range() {
+        let ty: syn2::Type = syn2::parse_str("  Vec<u8>").unwrap();
+        let range = super::spanned_range(&ty);
+        assert_eq!(range.start.start().column, 2);
+        assert_eq!(range.end.start().column, 2);
+    }
 }
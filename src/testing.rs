@@ -0,0 +1,641 @@
+//! Helpers to test proc macros, e.g., bootstrapping
+//! [trybuild](https://docs.rs/trybuild) UI tests or rendering errors for
+//! snapshots.
+use std::fmt::Display;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+
+use crate::error::{compile_errors, split_compile_errors};
+use crate::{to_tokens_span_range, token_stream_from_str, SpanRange, SpanRanged, ToTokensError};
+
+/// Writes [trybuild](https://docs.rs/trybuild)-style UI tests for a function
+/// like macro, i.e., a `.rs` file invoking the macro and a `.stderr` file with
+/// the expected errors for each case.
+///
+/// The expected `.stderr` contains the messages of every `compile_error!` the
+/// macro emits, rendered like `rustc` does. As the locations of the errors are
+/// only known to the compiler, they are not included. Once the messages look
+/// right, run trybuild with `TRYBUILD=overwrite` to add them.
+///
+/// Existing files are not overwritten, so cases can be added over time and
+/// generated files can be edited.
+///
+/// ```
+/// use manyhow::testing::UiTests;
+/// use manyhow::{bail, function};
+/// use proc_macro2::TokenStream;
+///
+/// # let dir = std::env::temp_dir().join("manyhow-doctest-ui");
+/// # let _ = std::fs::remove_dir_all(&dir);
+/// let written = UiTests::new(&dir, "my_crate::my_macro")
+///     .case("empty", "")
+///     .case("literal", "1")
+///     .write(|input| {
+///         function!(input, |input: TokenStream| -> manyhow::Result {
+///             if input.is_empty() {
+///                 bail!("expected input");
+///             }
+///             Ok(input)
+///         })
+///     })
+///     .unwrap();
+/// assert_eq!(written.len(), 3);
+/// assert_eq!(
+///     std::fs::read_to_string(dir.join("empty.stderr")).unwrap(),
+///     "error: expected input\n"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct UiTests {
+    dir: PathBuf,
+    macro_path: String,
+    cases: Vec<(String, String)>,
+}
+
+impl UiTests {
+    /// Creates UI tests written to `dir`, e.g., `tests/ui`, invoking the macro
+    /// at `macro_path`, e.g., `my_crate::my_macro`.
+    pub fn new(dir: impl Into<PathBuf>, macro_path: impl Display) -> Self {
+        Self {
+            dir: dir.into(),
+            macro_path: macro_path.to_string(),
+            cases: Vec::new(),
+        }
+    }
+
+    /// Adds a case called `name`, invoking the macro with `input`.
+    pub fn case(mut self, name: impl Display, input: impl Display) -> Self {
+        self.cases.push((name.to_string(), input.to_string()));
+        self
+    }
+
+    /// Expands every case with `expand` and writes the files, returning the
+    /// paths of the newly written files.
+    ///
+    /// The `.stderr` file is only written when the expansion emits errors,
+    /// i.e., the case is expected to fail.
+    ///
+    /// # Errors
+    /// Errors if an input is not a valid token stream or a file cannot be
+    /// written.
+    pub fn write(&self, expand: impl Fn(TokenStream) -> TokenStream) -> io::Result<Vec<PathBuf>> {
+        std::fs::create_dir_all(&self.dir)?;
+        let mut written = Vec::new();
+        for (name, input) in &self.cases {
+            let tokens = token_stream_from_str(input).map_err(|error| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("case `{name}`: {error}"),
+                )
+            })?;
+            let source = format!(
+                "{}! {{\n    {input}\n}}\n\nfn main() {{}}\n",
+                self.macro_path
+            );
+            write_new(&self.dir.join(format!("{name}.rs")), &source, &mut written)?;
+            let stderr = render_tokens_stderr(expand(tokens));
+            if !stderr.is_empty() {
+                write_new(
+                    &self.dir.join(format!("{name}.stderr")),
+                    &stderr,
+                    &mut written,
+                )?;
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// Renders the errors of `error` as stable text for snapshot tests, e.g., with
+/// [`insta`](https://docs.rs/insta).
+///
+/// Each error is rendered with its message, followed by the source text it
+/// is spanned at, if available, i.e., for tokens parsed from a string with
+/// `proc-macro2`'s `span-locations` feature enabled. Spans are not rendered as
+/// file locations, so snapshots do not change when unrelated code moves. Errors
+/// of an [`Emitter`](crate::Emitter) can be rendered through
+/// [`Emitter::into_result()`](crate::Emitter::into_result).
+///
+/// ```
+/// use manyhow::ErrorMessage;
+/// use manyhow::testing::render_errors;
+/// use proc_macro2::TokenStream;
+///
+/// let input: TokenStream = "struct Unit;".parse().unwrap();
+/// let error = ErrorMessage::spanned(&input, "expected enum").help("use `enum`");
+/// assert_eq!(
+///     render_errors(&error),
+///     "error: expected enum\n\n  = help: use `enum`\n  --> `struct Unit;`\n"
+/// );
+/// ```
+pub fn render_errors(error: &(impl ToTokensError + ?Sized)) -> String {
+    error
+        .to_syn()
+        .into_iter()
+        .flatten()
+        .map(|error| {
+            let msg = error.to_string();
+            let source = source_text(error.into_compile_error())
+                .map(|source| format!("  --> `{source}`\n"))
+                .unwrap_or_default();
+            format!("error: {}\n{source}", msg.trim_end())
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Asserts the output and errors of a macro implementation, e.g., created by
+/// [`#[manyhow(impl_fn)]`](crate#without-macros).
+///
+/// The implementation is called like with [`function!`](crate::function!) or,
+/// when an item is given, [`attribute!`](crate::attribute!), i.e., it can take
+/// any input supported by them, including a fresh [`Dummy`](crate::Dummy) and
+/// [`Emitter`](crate::Emitter). The output, or the dummy if the implementation
+/// returned an error, is compared to the expected tokens, and the messages of
+/// all emitted errors to the expected `errors`, which default to none.
+///
+/// ```
+/// use manyhow::{assert_expansion, emit, Emitter, Result};
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+///
+/// fn my_macro_impl(input: TokenStream, emitter: &mut Emitter) -> Result {
+///     if input.is_empty() {
+///         emit!(emitter, "expected input");
+///     }
+///     Ok(quote!(fn f() {}))
+/// }
+///
+/// assert_expansion!(my_macro_impl, { input } => { fn f() {} });
+/// assert_expansion!(my_macro_impl, {} => { fn f() {} }, errors: ["expected input"]);
+///
+/// fn my_attribute_impl(input: TokenStream, item: TokenStream) -> TokenStream {
+///     quote!(#[derive(#input)] #item)
+/// }
+///
+/// assert_expansion!(
+///     my_attribute_impl,
+///     { Debug },
+///     { struct Struct; }
+///     => { #[derive(Debug)] struct Struct; }
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_expansion {
+    ($impl:expr, {$($input:tt)*}, {$($item:tt)*} => {$($output:tt)*} $(, errors: [$($error:expr),* $(,)?])? $(,)?) => {
+        $crate::testing::assert_expansion(
+            $crate::attribute!(
+                $crate::__private::quote::quote!($($input)*),
+                $crate::__private::quote::quote!($($item)*),
+                $impl,
+            ),
+            &$crate::__private::quote::quote!($($output)*),
+            &[$($($error),*)?],
+        )
+    };
+    ($impl:expr, {$($input:tt)*} => {$($output:tt)*} $(, errors: [$($error:expr),* $(,)?])? $(,)?) => {
+        $crate::testing::assert_expansion(
+            $crate::function!($crate::__private::quote::quote!($($input)*), $impl),
+            &$crate::__private::quote::quote!($($output)*),
+            &[$($($error),*)?],
+        )
+    };
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_expansion(tokens: TokenStream, output: &TokenStream, errors: &[&str]) {
+    let (tokens, actual_errors) = split_compile_errors(tokens);
+    let actual_errors: Vec<_> = actual_errors.into_iter().map(|(_, msg)| msg).collect();
+    assert_eq!(tokens.to_string(), output.to_string(), "output differs");
+    assert_eq!(actual_errors, errors, "errors differ");
+}
+
+/// Asserts that a macro implementation fails with the expected errors.
+///
+/// The implementation is called like with [`assert_expansion!`], the output or
+/// dummy is ignored. Each expected error is given as its message, optionally
+/// followed by `at "source"`, the source text of the input it should be
+/// spanned at. On mismatch, the expected and actual errors are shown in the
+/// same form.
+///
+/// As the source text of spans is only available for tokens parsed from a
+/// string, the input is converted to a string and parsed again, this requires
+/// `proc-macro2`'s `span-locations` feature, e.g., as a dev-dependency.
+///
+/// ```
+/// use manyhow::{assert_expansion_err, bail, emit, Emitter, Result};
+/// use proc_macro2::TokenStream;
+///
+/// fn my_macro_impl(input: TokenStream, emitter: &mut Emitter) -> Result {
+///     for token in input {
+///         if token.to_string() != "ok" {
+///             emit!(emitter, token, "unexpected `{token}`");
+///         }
+///     }
+///     bail!("no output")
+/// }
+///
+/// assert_expansion_err!(my_macro_impl, { ok a ok b }, [
+///     "unexpected `a`" at "a",
+///     "unexpected `b`" at "b",
+///     "no output",
+/// ]);
+/// ```
+#[macro_export]
+macro_rules! assert_expansion_err {
+    ($impl:expr, {$($input:tt)*}, {$($item:tt)*}, [$($error:literal $(at $source:literal)?),* $(,)?] $(,)?) => {
+        $crate::testing::assert_expansion_err(
+            $crate::attribute!(
+                $crate::testing::reparse(::core::stringify!($($input)*)),
+                $crate::testing::reparse(::core::stringify!($($item)*)),
+                $impl,
+            ),
+            &[$(($error, $crate::__private::None$(.or($crate::__private::Some($source)))?)),*],
+        )
+    };
+    ($impl:expr, {$($input:tt)*}, [$($error:literal $(at $source:literal)?),* $(,)?] $(,)?) => {
+        $crate::testing::assert_expansion_err(
+            $crate::function!($crate::testing::reparse(::core::stringify!($($input)*)), $impl),
+            &[$(($error, $crate::__private::None$(.or($crate::__private::Some($source)))?)),*],
+        )
+    };
+}
+
+#[doc(hidden)]
+#[track_caller]
+#[must_use]
+pub fn reparse(input: &str) -> TokenStream {
+    token_stream_from_str(input).expect("input should be valid tokens")
+}
+
+#[doc(hidden)]
+#[track_caller]
+pub fn assert_expansion_err(tokens: TokenStream, errors: &[(&str, Option<&str>)]) {
+    let render = |msg: &str, source: Option<&str>| match source {
+        Some(source) => format!("{msg:?} at {source:?}"),
+        None => format!("{msg:?}"),
+    };
+    let expected: Vec<_> = errors
+        .iter()
+        .map(|(msg, source)| render(msg, *source))
+        .collect();
+    let actual: Vec<_> = compile_errors(tokens)
+        .into_iter()
+        .enumerate()
+        .map(|(i, (invocation, msg))| {
+            // Only compare spans, when expected
+            if errors.get(i).is_some_and(|(_, source)| source.is_some()) {
+                let source = source_text(invocation).unwrap_or_else(|| {
+                    "<unknown, enable `span-locations` of `proc-macro2`>".into()
+                });
+                render(&msg, Some(&source))
+            } else {
+                render(&msg, None)
+            }
+        })
+        .collect();
+    assert!(
+        expected == actual,
+        "errors differ\nexpected:\n  {}\nactual:\n  {}",
+        expected.join("\n  "),
+        actual.join("\n  ")
+    );
+}
+
+/// Returns the source text a `compile_error!` `invocation` is spanned at.
+fn source_text(invocation: TokenStream) -> Option<String> {
+    let span = to_tokens_span_range(invocation);
+    span.span_joined().unwrap_or(span.start).source_text()
+}
+
+/// Renders `error` as `rustc` displays the emitted `compile_error!`s, without
+/// their locations, e.g., to generate `.stderr` files of UI tests.
+///
+/// Each `compile_error!` is rendered as `error: <message>`, in the order they
+/// are emitted, including attachments, which are part of the message, and
+/// [spanned attachments](crate::ErrorMessage::spanned_attachment), which are
+/// emitted as separate errors.
+///
+/// ```
+/// use manyhow::ErrorMessage;
+/// use manyhow::testing::render_stderr;
+/// use proc_macro2::Span;
+///
+/// let error = ErrorMessage::call_site("duplicate key")
+///     .help("remove one")
+///     .spanned_attachment(Span::call_site(), "note", "first defined here");
+/// assert_eq!(
+///     render_stderr(&error),
+///     "error: duplicate key\n\n  = help: remove one\n\n\nerror: note: first defined here\n"
+/// );
+/// ```
+pub fn render_stderr(error: &(impl ToTokensError + ?Sized)) -> String {
+    render_tokens_stderr(error.to_token_stream())
+}
+
+/// Renders the messages of all `compile_error!`s in `tokens`, see
+/// [`render_stderr()`].
+fn render_tokens_stderr(tokens: TokenStream) -> String {
+    compile_errors(tokens)
+        .into_iter()
+        .map(|(_, msg)| format!("error: {msg}\n"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Asserts that whenever the macro emits errors for an input of the
+/// `corpus`, its complete output, i.e., the output or dummy together with the
+/// `compile_error!`s, still parses as `T`, e.g., `syn::File` for items or
+/// `syn::Expr` for expressions.
+///
+/// The dummy keeps code using the macro compiling while it has errors, this
+/// catches dummies that are not valid themselves, which would bury the actual
+/// errors under follow-up errors.
+///
+/// ```
+/// # use syn2 as syn;
+/// use manyhow::testing::assert_output_parses_on_error;
+/// use manyhow::{bail, function, Dummy, Result};
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+///
+/// fn my_macro_impl(input: TokenStream, dummy: &mut Dummy) -> Result {
+///     dummy.set(quote!(struct Output;));
+///     if input.is_empty() {
+///         bail!("expected input");
+///     }
+///     Ok(quote!(struct Output(#input);))
+/// }
+///
+/// assert_output_parses_on_error::<syn::File>(["", "u8", "u8, u16"], |input| {
+///     function!(input, my_macro_impl)
+/// });
+/// ```
+///
+/// # Panics
+/// Panics if an input is not a valid token stream, or the output of an input
+/// with errors does not parse.
+#[track_caller]
+pub fn assert_output_parses_on_error<T: syn2::parse::Parse>(
+    corpus: impl IntoIterator<Item = impl Display>,
+    expand: impl Fn(TokenStream) -> TokenStream,
+) {
+    for input in corpus {
+        let tokens = token_stream_from_str(&input.to_string())
+            .unwrap_or_else(|error| panic!("input `{input}`: {error}"));
+        let output = expand(tokens);
+        let errors = compile_errors(output.clone());
+        if errors.is_empty() {
+            continue;
+        }
+        if let Err(error) = syn2::parse2::<T>(output.clone()) {
+            let errors: Vec<_> = errors.into_iter().map(|(_, msg)| msg).collect();
+            panic!(
+                "output for input `{input}` with errors {errors:?} does not parse as `{}`: \
+                 {error}\noutput: {output}",
+                std::any::type_name::<T>()
+            );
+        }
+    }
+}
+
+/// Named spans for tests, giving errors stable locations independent of
+/// whether the code runs inside a proc macro.
+///
+/// Each name is parsed as its own source, so every name has a distinct span
+/// whose [source text](proc_macro2::Span::source_text) is the name, e.g.,
+/// rendered by [`render_errors()`], and that is mapped back to its name by
+/// [`name_of()`](Self::name_of). Distinct spans require `proc-macro2`'s
+/// `span-locations` feature, e.g., as a dev-dependency, otherwise all spans
+/// are the same.
+///
+/// ```
+/// use manyhow::ErrorMessage;
+/// use manyhow::testing::{render_errors, Spans};
+///
+/// let spans = Spans::new(["field", "u8"]);
+/// let error = ErrorMessage::new(spans.span("u8"), "expected `u16`");
+/// assert_eq!(render_errors(&error), "error: expected `u16`\n  --> `u8`\n");
+/// assert_eq!(spans.name_of(spans.range("u8")), Some("u8"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Spans(Vec<(String, SpanRange)>);
+
+impl Spans {
+    /// Creates a span for each of `names`.
+    ///
+    /// # Panics
+    /// Panics if a name is empty or not valid tokens.
+    #[track_caller]
+    #[must_use]
+    pub fn new(names: impl IntoIterator<Item = impl Display>) -> Self {
+        Self(
+            names
+                .into_iter()
+                .map(|name| {
+                    let name = name.to_string();
+                    let tokens = token_stream_from_str(&name)
+                        .unwrap_or_else(|error| panic!("span `{name}`: {error}"));
+                    assert!(!tokens.is_empty(), "span name should not be empty");
+                    let range = to_tokens_span_range(tokens);
+                    (name, SpanRange::new(range.start, range.end))
+                })
+                .collect(),
+        )
+    }
+
+    /// Returns the span of `name`, joined if it consists of multiple tokens.
+    ///
+    /// # Panics
+    /// Panics if `name` was not passed to [`new()`](Self::new).
+    #[track_caller]
+    #[must_use]
+    pub fn span(&self, name: &str) -> Span {
+        let range = self.range(name);
+        range.joined().unwrap_or_else(|| range.start_span())
+    }
+
+    /// Returns the span range of `name`.
+    ///
+    /// # Panics
+    /// Panics if `name` was not passed to [`new()`](Self::new).
+    #[track_caller]
+    #[must_use]
+    pub fn range(&self, name: &str) -> SpanRange {
+        self.0
+            .iter()
+            .find(|(n, _)| n == name)
+            .unwrap_or_else(|| panic!("unknown span `{name}`"))
+            .1
+    }
+
+    /// Returns the name of the span starting at `span`, `None` if it was not
+    /// created by these `Spans`.
+    #[must_use]
+    pub fn name_of(&self, span: impl SpanRanged) -> Option<&str> {
+        // `Debug` of a span is its identity, i.e., the byte range in the source
+        let start = format!("{:?}", span.span_range().start);
+        self.0
+            .iter()
+            .find(|(_, range)| format!("{:?}", range.start_span()) == start)
+            .map(|(name, _)| name.as_str())
+    }
+}
+
+/// Sets the span of all tokens in `tokens`, including groups, to the call
+/// site, e.g., to compare their [`Debug`](std::fmt::Debug) output in golden
+/// tests, which would otherwise include the spans, that differ between
+/// running inside and outside of a proc macro.
+///
+/// ```
+/// use manyhow::testing::erase_spans;
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+///
+/// let parsed: TokenStream = "f(a)".parse().unwrap();
+/// assert_eq!(
+///     format!("{:?}", erase_spans(parsed)),
+///     format!("{:?}", erase_spans(quote!(f(a))))
+/// );
+/// ```
+#[must_use]
+pub fn erase_spans(tokens: TokenStream) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut tt| {
+            if let TokenTree::Group(group) = &tt {
+                tt = Group::new(group.delimiter(), erase_spans(group.stream())).into();
+            }
+            tt.set_span(Span::call_site());
+            tt
+        })
+        .collect()
+}
+
+fn write_new(path: &Path, content: &str, written: &mut Vec<PathBuf>) -> io::Result<()> {
+    if !path.exists() {
+        std::fs::write(path, content)?;
+        written.push(path.to_owned());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use quote::quote;
+
+    use super::*;
+
+    #[test]
+    fn ui_tests() {
+        let dir = std::env::temp_dir().join(format!("manyhow-ui-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let tests = UiTests::new(&dir, "my_macro")
+            .case("two_errors", "a b")
+            .case("passing", "");
+        let expand = |input: TokenStream| {
+            input
+                .into_iter()
+                .map(|tt| {
+                    let msg = format!("unexpected `{tt}`");
+                    quote!(::core::compile_error! { #msg })
+                })
+                .collect()
+        };
+
+        let written = tests.write(expand).unwrap();
+        assert_eq!(written.len(), 3);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("two_errors.rs")).unwrap(),
+            "my_macro! {\n    a b\n}\n\nfn main() {}\n"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("two_errors.stderr")).unwrap(),
+            "error: unexpected `a`\n\nerror: unexpected `b`\n"
+        );
+        assert!(!dir.join("passing.stderr").exists());
+
+        // Existing files are kept
+        assert!(tests.write(expand).unwrap().is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    #[should_panic = "errors differ\nexpected:\n  \"a\" at \"a\"\nactual:\n  \"a\" at \"b\""]
+    fn assert_expansion_err() {
+        crate::assert_expansion_err!(
+            |input: TokenStream| -> crate::Result {
+                let b = input.into_iter().nth(1).unwrap();
+                Err(crate::ErrorMessage::new(b.span(), "a").into())
+            },
+            { a b },
+            ["a" at "a"]
+        );
+    }
+
+    #[test]
+    #[should_panic = "output for input `a` with errors [\"error\"] does not parse as \
+                      `syn::file::File`"]
+    fn assert_output_parses_on_error() {
+        let expand = |input: TokenStream| {
+            if input.is_empty() {
+                quote! { struct A; ::core::compile_error! { "error" } }
+            } else {
+                quote! { struct; ::core::compile_error! { "error" } }
+            }
+        };
+        super::assert_output_parses_on_error::<syn2::File>([""], expand);
+        super::assert_output_parses_on_error::<syn2::File>(["a"], expand);
+    }
+
+    #[test]
+    fn render_errors() {
+        use crate::{Error, ErrorMessage};
+
+        let input: TokenStream = "struct Unit;\nenum Enum {}".parse().unwrap();
+        let tokens: Vec<_> = input.into_iter().collect();
+        let mut error = Error::from(ErrorMessage::new(
+            tokens[0].span()..tokens[2].span(),
+            "expected enum",
+        ));
+        error.push(ErrorMessage::new(tokens[4].span(), "unexpected enum").note("multiple\nlines"));
+        assert_eq!(
+            super::render_errors(&error),
+            "error: expected enum\n  --> `struct Unit;`\n\nerror: unexpected enum\n\n  = note: \
+             multiple\n          lines\n  --> `Enum`\n"
+        );
+    }
+
+    #[test]
+    fn spans() {
+        let spans = Spans::new(["a", "a::b", "c"]);
+        assert_eq!(spans.span("a::b").source_text().as_deref(), Some("a::b"));
+        assert_eq!(spans.name_of(spans.span("c")), Some("c"));
+        assert_eq!(spans.name_of(spans.range("a::b")), Some("a::b"));
+        assert_eq!(spans.name_of(Span::call_site()), None);
+    }
+
+    #[test]
+    #[should_panic = "unknown span `d`"]
+    fn spans_unknown() {
+        let _ = Spans::new(["a"]).span("d");
+    }
+
+    #[test]
+    fn erase_spans() {
+        let parsed = reparse("f(a) { [b] }");
+        assert_ne!(
+            format!("{parsed:?}"),
+            format!("{:?}", quote! { f(a) { [b] } })
+        );
+        assert_eq!(
+            format!("{:?}", super::erase_spans(parsed)),
+            format!("{:?}", super::erase_spans(quote! { f(a) { [b] } }))
+        );
+    }
+}
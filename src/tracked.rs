@@ -0,0 +1,157 @@
+//! Environment variables and paths read by a macro, tracked so the macro is
+//! rerun when they change.
+//!
+//! `proc_macro::tracked_env` and `proc_macro::tracked_path` are not yet
+//! available on stable, therefore the values are tracked by emitting
+//! [`Tracked`] in the macro's output, which expands to
+//! `option_env!`/`include_bytes!` invocations the compiler tracks.
+//!
+//! ```
+//! # use syn2 as syn;
+//! use manyhow::{tracked, Result};
+//! use proc_macro2::TokenStream;
+//! use quote::quote;
+//!
+//! fn macro_impl(name: syn::LitStr) -> Result {
+//!     let value = tracked::env_var(&name)?;
+//!     let string = &*value;
+//!     Ok(quote! {
+//!         #value
+//!         const VALUE: &str = #string;
+//!     })
+//! }
+//! # let output = macro_impl(syn::parse_quote!("CARGO_PKG_NAME")).unwrap();
+//! # assert!(output.to_string().contains("\"manyhow\""));
+//! ```
+use std::ops::Deref;
+use std::path::PathBuf;
+
+use proc_macro2::{Literal, TokenStream};
+use quote::{quote_spanned, ToTokens};
+use syn2::LitStr;
+
+use crate::ErrorMessage;
+
+/// A value read by a macro, emitting the tokens tracking it when converted to
+/// tokens.
+///
+/// Dereferences to the value. The tracking tokens are items, i.e., they need
+/// to be emitted in item position, e.g., next to the generated items or
+/// inside a block.
+#[derive(Debug, Clone)]
+pub struct Tracked<T> {
+    value: T,
+    tracking: TokenStream,
+}
+
+impl<T> Tracked<T> {
+    /// Returns the value, dropping the tracking tokens.
+    pub fn into_value(self) -> T {
+        self.value
+    }
+
+    /// Returns the value and the tracking tokens.
+    pub fn into_parts(self) -> (T, TokenStream) {
+        (self.value, self.tracking)
+    }
+}
+
+impl<T> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+
+impl<T> ToTokens for Tracked<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.tracking.to_tokens(tokens);
+    }
+}
+
+/// Reads the environment variable named by `name`.
+///
+/// # Errors
+/// Errors, spanned at `name`, if the variable is not defined or not valid
+/// unicode.
+pub fn env_var(name: &LitStr) -> Result<Tracked<String>, ErrorMessage> {
+    let var = name.value();
+    let tracking = quote_spanned! {name.span()=>
+        const _: ::core::option::Option<&str> = ::core::option_env!(#name);
+    };
+    match std::env::var(&var) {
+        Ok(value) => Ok(Tracked { value, tracking }),
+        Err(std::env::VarError::NotPresent) => Err(ErrorMessage::new(
+            name.span(),
+            format_args!("environment variable `{var}` is not defined"),
+        )),
+        Err(std::env::VarError::NotUnicode(_)) => Err(ErrorMessage::new(
+            name.span(),
+            format_args!("environment variable `{var}` is not valid unicode"),
+        )),
+    }
+}
+
+/// Resolves the file `path` relative to `CARGO_MANIFEST_DIR`.
+///
+/// # Errors
+/// Errors, spanned at `path`, if the file does not exist.
+pub fn path(path: &LitStr) -> Result<Tracked<PathBuf>, ErrorMessage> {
+    let value = path.value();
+    let resolved = crate::fs::resolve(path);
+    if !resolved.is_file() {
+        return Err(
+            ErrorMessage::new(path.span(), format_args!("file `{value}` does not exist"))
+                .note(format_args!("resolved to `{}`", resolved.display())),
+        );
+    }
+    // Non unicode paths cannot be tracked through `include_bytes!`
+    let tracking = resolved
+        .to_str()
+        .map(|resolved| {
+            let resolved = Literal::string(resolved);
+            quote_spanned! {path.span()=>
+                const _: &[u8] = ::core::include_bytes!(#resolved);
+            }
+        })
+        .unwrap_or_default();
+    Ok(Tracked {
+        value: resolved,
+        tracking,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use syn2::parse_quote;
+
+    use super::*;
+
+    #[test]
+    fn env_var() {
+        let value = super::env_var(&parse_quote!("CARGO_PKG_NAME")).unwrap();
+        assert_eq!(*value, "manyhow");
+        assert!(value.to_token_stream().to_string().contains("option_env"));
+
+        let error = super::env_var(&parse_quote!("MANYHOW_UNDEFINED_VAR")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "environment variable `MANYHOW_UNDEFINED_VAR` is not defined"
+        );
+    }
+
+    #[test]
+    fn path() {
+        let path = super::path(&parse_quote!("Cargo.toml")).unwrap();
+        assert!(path.ends_with("Cargo.toml"));
+        assert!(path.to_token_stream().to_string().contains("include_bytes"));
+
+        let error = super::path(&parse_quote!("missing.toml")).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("file `missing.toml` does not exist")
+        );
+    }
+}
@@ -0,0 +1,433 @@
+#[cfg(feature = "syn2-parse")]
+use std::ops::Range;
+
+#[cfg(feature = "syn2-parse")]
+use proc_macro2::Span;
+use proc_macro2::{Group, Ident, Literal, Punct, TokenStream, TokenTree};
+#[cfg(feature = "syn2-parse")]
+use syn2::parse::{ParseStream, Parser};
+
+#[cfg(doc)]
+use crate::function;
+use crate::{Result, ToTokensError};
+
+/// Types that can be used as typed input of [`#[manyhow]`](crate::manyhow)
+/// handlers and the [`function!`], [`attribute!`](crate::attribute!) and
+/// [`derive!`](crate::derive!) macros.
+///
+/// Types implementing [`syn::parse::Parse`](syn2::parse::Parse) are
+/// supported without implementing this trait, implement it for types that
+/// parse with custom (non-syn) logic instead.
+///
+/// [`TokenTree`], [`Group`], [`Ident`], [`Literal`] and [`Punct`] accept
+/// exactly one token, erroring with, e.g., "expected a single identifier"
+/// otherwise. As [`syn::Ident`](syn2::Ident) is the same type as [`Ident`],
+/// identifiers are parsed with [`syn`](syn2) when the `syn2-parse` feature is
+/// enabled, rejecting keywords and `_`.
+///
+/// ```
+/// use manyhow::{function, ManyhowParse};
+/// use proc_macro2::{TokenStream, TokenTree};
+/// use quote::quote;
+///
+/// /// Counts the tokens of the input.
+/// struct TokenCount(usize);
+///
+/// impl ManyhowParse for TokenCount {
+///     fn manyhow_parse(input: TokenStream, _attr: bool) -> manyhow::Result<Self> {
+///         Ok(Self(input.into_iter().count()))
+///     }
+/// }
+///
+/// let output: TokenStream = function!(quote!(a b c), |count: TokenCount| -> TokenStream {
+///     let count = count.0;
+///     quote!(#count)
+/// });
+/// assert_eq!(output.to_string(), "3usize");
+/// ```
+pub trait ManyhowParse: Sized {
+    /// Parses `input`, `attr` is `true` when parsing the arguments of an
+    /// attribute macro, i.e., `#[attr(...)]`.
+    ///
+    /// # Errors
+    /// Errors if `input` is not valid, the errors are emitted and the handler
+    /// is not called.
+    fn manyhow_parse(input: TokenStream, attr: bool) -> Result<Self>;
+}
+
+impl ManyhowParse for TokenStream {
+    fn manyhow_parse(input: TokenStream, _attr: bool) -> Result<Self> {
+        Ok(input)
+    }
+}
+
+impl ManyhowParse for proc_macro::TokenStream {
+    fn manyhow_parse(input: TokenStream, _attr: bool) -> Result<Self> {
+        Ok(input.into())
+    }
+}
+
+/// Parses exactly one token, `kind` describes the expected token for errors.
+fn single_token<T>(
+    input: TokenStream,
+    kind: &str,
+    convert: impl FnOnce(TokenTree) -> Option<T>,
+) -> Result<T> {
+    let mut input = input.into_iter();
+    let Some(first) = input.next() else {
+        bail!("expected a single {kind}, found end of input");
+    };
+    let span = first.span();
+    let Some(token) = convert(first) else {
+        bail!(span, "expected a single {kind}");
+    };
+    if let Some(second) = input.next() {
+        let span = second.span()..input.last().map_or_else(|| second.span(), |tt| tt.span());
+        bail!(span, "expected a single {kind}"; help = "remove the additional tokens");
+    }
+    Ok(token)
+}
+
+impl ManyhowParse for TokenTree {
+    fn manyhow_parse(input: TokenStream, _attr: bool) -> Result<Self> {
+        single_token(input, "token", Some)
+    }
+}
+
+macro_rules! single_token_input {
+    ($($Type:ident, $kind:literal;)*) => {$(
+        impl ManyhowParse for $Type {
+            fn manyhow_parse(input: TokenStream, _attr: bool) -> Result<Self> {
+                single_token(input, $kind, |tt| match tt {
+                    TokenTree::$Type(token) => Some(token),
+                    _ => None,
+                })
+            }
+        }
+    )*};
+}
+
+single_token_input! {
+    Group, "group";
+    Literal, "literal";
+    Punct, "punctuation";
+}
+
+#[cfg(not(feature = "syn2-parse"))]
+single_token_input! {
+    Ident, "identifier";
+}
+
+#[cfg(feature = "syn2-parse")]
+impl ManyhowParse for Ident {
+    fn manyhow_parse(input: TokenStream, attr: bool) -> Result<Self> {
+        Syn2::manyhow_parse(input, attr).map(|Syn2(ident)| ident)
+    }
+}
+
+/// Input parsed with [`syn` 1.x](syn1), e.g., while migrating a macro crate
+/// from `syn` 1 to 2 one handler at a time.
+///
+/// Types implementing [`syn2::parse::Parse`] can be used as input directly, or
+/// wrapped in [`Syn2`] to make the version explicit. As both versions use the
+/// same [`quote::ToTokens`], `syn` 1 types can be returned without a wrapper.
+///
+/// ```
+/// use manyhow::{function, Syn1, Syn2};
+/// use proc_macro2::TokenStream;
+/// use quote::quote;
+///
+/// let output: TokenStream = function!(quote!(a), |Syn1(ident): Syn1<syn1::Ident>| -> TokenStream {
+///     quote!(#ident)
+/// });
+/// assert_eq!(output.to_string(), "a");
+///
+/// let output: TokenStream = function!(quote!(a), |Syn2(ident): Syn2<syn2::Ident>| -> TokenStream {
+///     quote!(#ident)
+/// });
+/// assert_eq!(output.to_string(), "a");
+/// ```
+#[cfg(feature = "syn1")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Syn1<T>(pub T);
+
+#[cfg(feature = "syn1")]
+impl<T: syn1::parse::Parse> ManyhowParse for Syn1<T> {
+    fn manyhow_parse(input: TokenStream, attr: bool) -> Result<Self> {
+        let empty = input.is_empty();
+        syn1::parse2(input)
+            .map(Self)
+            .map_err(|error| attr_context(error.into(), attr && empty))
+    }
+}
+
+/// Input parsed with [`syn` 2.x](syn2), see [`Syn1`].
+#[cfg(feature = "syn2-parse")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Syn2<T>(pub T);
+
+#[cfg(feature = "syn2-parse")]
+impl<T: syn2::parse::Parse> ManyhowParse for Syn2<T> {
+    fn manyhow_parse(input: TokenStream, attr: bool) -> Result<Self> {
+        let empty = input.is_empty();
+        crate::parse_complete(T::parse, input)
+            .map(Self)
+            .map_err(|error| attr_context(error.into(), attr && empty))
+    }
+}
+
+/// Notes that the error occurred while parsing empty attribute arguments.
+#[cfg(any(feature = "syn1", feature = "syn2-parse"))]
+fn attr_context(mut error: crate::Error, empty_attr: bool) -> crate::Error {
+    if empty_attr {
+        error.push(error_message!(
+            "while parsing attribute argument (`#[... (...)]`)"
+        ));
+    }
+    error
+}
+
+/// Types that can be returned from [`#[manyhow]`](crate::manyhow) handlers and
+/// the [`function!`], [`attribute!`](crate::attribute!) and
+/// [`derive!`](crate::derive!) macros.
+///
+/// Types implementing [`quote::ToTokens`] are supported without implementing
+/// this trait, implement it for types that cannot implement
+/// [`ToTokens`](quote::ToTokens), e.g., because they need to consume
+/// themselves or some configuration to render.
+///
+/// ```
+/// use manyhow::{function, ManyhowToTokens};
+/// use proc_macro2::TokenStream;
+/// use quote::{quote, ToTokens};
+///
+/// /// Renders the items, wrapped in a module if `module` is set.
+/// struct Items {
+///     items: Vec<syn::Item>,
+///     module: Option<syn::Ident>,
+/// }
+/// # use syn2 as syn;
+///
+/// impl ManyhowToTokens for Items {
+///     fn manyhow_to_tokens(self, tokens: &mut TokenStream) {
+///         let items = self.items;
+///         match self.module {
+///             Some(module) => quote!(mod #module { #(#items)* }).to_tokens(tokens),
+///             None => quote!(#(#items)*).to_tokens(tokens),
+///         }
+///     }
+/// }
+///
+/// let output: TokenStream = function!(quote!(struct A;), |item: syn::Item| -> Items {
+///     Items {
+///         items: vec![item],
+///         module: Some(syn::parse_quote!(module)),
+///     }
+/// });
+/// assert_eq!(output.to_string(), quote!(mod module { struct A; }).to_string());
+/// ```
+pub trait ManyhowToTokens {
+    /// Appends the output to `tokens`.
+    fn manyhow_to_tokens(self, tokens: &mut TokenStream);
+}
+
+impl ManyhowToTokens for TokenStream {
+    fn manyhow_to_tokens(self, tokens: &mut TokenStream) {
+        crate::append_tokens(tokens, self);
+    }
+}
+
+impl ManyhowToTokens for proc_macro::TokenStream {
+    fn manyhow_to_tokens(self, tokens: &mut TokenStream) {
+        crate::append_tokens(tokens, self.into());
+    }
+}
+
+impl<E: ToTokensError> ManyhowToTokens for E {
+    fn manyhow_to_tokens(self, tokens: &mut TokenStream) {
+        self.to_tokens(tokens);
+    }
+}
+
+/// Parses one of the comma separated arguments of a tuple input with
+/// `parser`, consuming the following comma.
+///
+/// On errors, the error is emitted and the input is skipped to after the next
+/// comma, to also report errors of the following arguments.
+#[cfg(feature = "syn2-parse")]
+fn parse_arg<T>(
+    input: ParseStream,
+    parser: impl FnOnce(ParseStream) -> syn2::Result<T>,
+    emitter: &mut crate::Emitter,
+) -> Option<T> {
+    let value = parser(input).and_then(|value| {
+        if !input.is_empty() {
+            input.parse::<syn2::Token![,]>()?;
+        }
+        Ok(value)
+    });
+    match value {
+        Ok(value) => Some(value),
+        Err(error) => {
+            emitter.emit(error);
+            input
+                .step(|cursor| {
+                    let mut rest = *cursor;
+                    while let Some((tt, next)) = rest.token_tree() {
+                        rest = next;
+                        if matches!(tt, TokenTree::Punct(punct) if punct.as_char() == ',') {
+                            break;
+                        }
+                    }
+                    Ok(((), rest))
+                })
+                .expect("skipping tokens does not fail");
+            None
+        }
+    }
+}
+
+/// Errors if not exactly `count` arguments were found in the tuple input
+/// spanning `span`, consuming the remaining tokens.
+#[cfg(feature = "syn2-parse")]
+fn check_arg_count(
+    input: ParseStream,
+    span: Range<Span>,
+    found: usize,
+    count: usize,
+    attr: bool,
+    emitter: &mut crate::Emitter,
+) -> syn2::Result<()> {
+    let remaining: TokenStream = input.parse()?;
+    if !remaining.is_empty() {
+        emitter.emit(error_message!(
+            crate::to_tokens_span_range(remaining),
+            "expected {count} comma separated arguments";
+            help = "remove the additional arguments"
+        ));
+    } else if found < count {
+        emitter.emit(error_message!(
+            span,
+            "expected {count} comma separated arguments, found {found}"
+        ));
+        if attr && found == 0 {
+            emitter.emit(error_message!(
+                "while parsing attribute argument (`#[... (...)]`)"
+            ));
+        }
+    }
+    Ok(())
+}
+
+macro_rules! tuple_input {
+    ($count:literal; $($T:ident),+) => {
+        #[cfg(feature = "syn2-parse")]
+        impl<$($T: syn2::parse::Parse),+> ManyhowParse for ($($T,)+) {
+            #[allow(non_snake_case)]
+            fn manyhow_parse(input: TokenStream, attr: bool) -> Result<Self> {
+                let span = crate::to_tokens_span_range(&input);
+                let mut emitter = crate::Emitter::new();
+                let parser = |input: ParseStream| {
+                    let mut found = 0;
+                    $(let $T = if input.is_empty() {
+                        None
+                    } else {
+                        found += 1;
+                        parse_arg(input, $T::parse, &mut emitter)
+                    };)+
+                    check_arg_count(input, span, found, $count, attr, &mut emitter)?;
+                    Ok(($($T,)+))
+                };
+                let ($($T,)+) = parser.parse2(input)?;
+                emitter.into_result()?;
+                Ok(($($T.expect("errors are checked"),)+))
+            }
+        }
+    };
+}
+
+tuple_input!(2; A, B);
+tuple_input!(3; A, B, C);
+tuple_input!(4; A, B, C, D);
+tuple_input!(5; A, B, C, D, E);
+tuple_input!(6; A, B, C, D, E, F);
+
+#[cfg(test)]
+mod test {
+    use proc_macro2::{Ident, Literal, Punct, TokenStream};
+    use quote::quote;
+
+    use crate::function;
+
+    #[test]
+    fn single_token_input() {
+        let output: TokenStream =
+            function!(quote!(a), |ident: Ident| -> TokenStream { quote!(#ident) });
+        assert_eq!(output.to_string(), "a");
+
+        let output: TokenStream =
+            function!(quote!(+ - *), |_: Punct| -> TokenStream { unreachable!() });
+        assert_eq!(
+            output.to_string(),
+            quote!(::core::compile_error! { "expected a single punctuation\n\n  = help: remove the additional tokens\n" })
+                .to_string()
+        );
+
+        let output: TokenStream =
+            function!(quote!(a), |_: Literal| -> TokenStream { unreachable!() });
+        assert_eq!(
+            output.to_string(),
+            quote!(::core::compile_error! { "expected a single literal" }).to_string()
+        );
+
+        let output: TokenStream =
+            function!(quote!(), |_: Literal| -> TokenStream { unreachable!() });
+        assert_eq!(
+            output.to_string(),
+            quote!(::core::compile_error! { "expected a single literal, found end of input" })
+                .to_string()
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "syn2-parse")]
+    fn ident_input() {
+        // parsed like `syn::Ident`, as it is the same type
+        let output: TokenStream =
+            function!(quote!(fn), |_: Ident| -> TokenStream { unreachable!() });
+        assert_eq!(
+            output.to_string(),
+            quote!(::core::compile_error! { "expected identifier, found keyword `fn`" })
+                .to_string()
+        );
+
+        let output: TokenStream =
+            function!(quote!(a b), |_: syn2::Ident| -> TokenStream { unreachable!() });
+        assert_eq!(
+            output.to_string(),
+            quote!(::core::compile_error! { "unexpected token" }).to_string()
+        );
+    }
+
+    #[test]
+    #[cfg(all(feature = "syn1", feature = "syn2"))]
+    fn syn_versions() {
+        use crate::{attribute, Syn1, Syn2};
+
+        let output: TokenStream =
+            attribute!(quote!(), quote!(a), |_: Syn1<syn1::Ident>,
+                                             Syn2(item): Syn2<syn2::Ident>|
+             -> TokenStream {
+                quote!(#item)
+            });
+        assert_eq!(
+            output.to_string(),
+            quote! {
+                // `syn` 1 does not use `::core`
+                compile_error! { "unexpected end of input, expected identifier" }
+                ::core::compile_error! { "while parsing attribute argument (`#[... (...)]`)" }
+            }
+            .to_string()
+        );
+    }
+}